@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    runtime::Runtime,
+};
+
+use mikrotik_api::{
+    bench_support::{decode_sentence, deserialize_sentence, encode_sentence},
+    Interface,
+};
+
+const INTERFACE_REPLY: &[&str] = &[
+    "!re",
+    ".tag=4242",
+    "=.id=*1",
+    "=name=ether1",
+    "=type=ether",
+    "=mtu=1500",
+    "=actual-mtu=1500",
+    "=last-link-up=jan/02/2022 10:12:54",
+    "=link-downs=3",
+    "=rx-byte=1234567890",
+    "=tx-byte=987654321",
+    "=rx-packet=12345678",
+    "=tx-packet=9876543",
+    "=rx-drop=0",
+    "=tx-drop=0",
+    "=tx-queue-drop=0",
+    "=rx-error=0",
+    "=tx-error=0",
+    "=fp-rx-byte=1234567890",
+    "=fp-tx-byte=0",
+    "=fp-rx-packet=12345678",
+    "=fp-tx-packet=0",
+    "=running=true",
+    "=disabled=false",
+];
+
+fn owned(words: &[&str]) -> Vec<String> {
+    words.iter().map(|w| w.to_string()).collect()
+}
+
+fn sentence(c: &mut Criterion) {
+    let command = [
+        "/ip/address/add",
+        ".tag=4242",
+        "=address=192.168.88.1/24",
+        "=interface=bridge",
+        "=comment=defconf",
+    ];
+
+    c.bench_function("encode command", |b| {
+        b.iter(|| encode_sentence(black_box(&command)))
+    });
+
+    let bytes = encode_sentence(INTERFACE_REPLY).unwrap();
+
+    c.bench_function("decode interface reply", |b| {
+        b.iter(|| decode_sentence(black_box(&bytes)))
+    });
+}
+
+fn deserialization(c: &mut Criterion) {
+    let reply = owned(INTERFACE_REPLY);
+
+    c.bench_function("deserialize Interface", |b| {
+        b.iter(|| deserialize_sentence::<Interface>(black_box(&reply)))
+    });
+
+    c.bench_function("deserialize HashMap", |b| {
+        b.iter(|| deserialize_sentence::<HashMap<String, String>>(black_box(&reply)))
+    });
+}
+
+/// Minimal router: `/login` succeeds, any other command gets a single reply
+async fn mock_router(listener: TcpListener) {
+    let (mut socket, _) = listener.accept().await.unwrap();
+
+    let mut buffer = vec![];
+    let mut chunk = [0u8; 4096];
+
+    loop {
+        let read = socket.read(&mut chunk).await.unwrap();
+        if read == 0 {
+            return;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        while let Some((words, consumed)) = decode_sentence(&buffer) {
+            buffer.drain(..consumed);
+
+            let tag = words
+                .iter()
+                .find(|word| word.starts_with(".tag="))
+                .cloned()
+                .unwrap_or_default();
+
+            if words[0] != "/login" {
+                let reply = encode_sentence(&["!re", &tag, "=name=MikroTik"]).unwrap();
+                socket.write_all(&reply).await.unwrap();
+            }
+
+            socket
+                .write_all(&encode_sentence(&["!done", &tag]).unwrap())
+                .await
+                .unwrap();
+        }
+    }
+}
+
+fn end_to_end(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+
+    let mut api = runtime.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(mock_router(listener));
+
+        mikrotik_api::connect(addr)
+            .await
+            .unwrap()
+            .authenticate("admin", "")
+            .await
+            .unwrap()
+    });
+
+    c.bench_function("one-off call latency", |b| {
+        b.iter(|| {
+            runtime.block_on(
+                api.generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None),
+            )
+        })
+    });
+}
+
+criterion_group!(benches, sentence, deserialization, end_to_end);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+#![no_main]
+
+use std::collections::HashMap;
+
+use libfuzzer_sys::fuzz_target;
+use mikrotik_api::{
+    bench_support::{decode_sentence, deserialize_sentence},
+    ActiveUser, Interface, SystemResources,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // Any word the router may send, the closing empty word included
+    let sentence: Vec<String> = match decode_sentence(data) {
+        Some((words, _)) => words,
+        None => return,
+    };
+
+    let _ = deserialize_sentence::<HashMap<String, String>>(&sentence);
+    let _ = deserialize_sentence::<serde_json::Value>(&sentence);
+    let _ = deserialize_sentence::<Interface>(&sentence);
+    let _ = deserialize_sentence::<ActiveUser>(&sentence);
+    let _ = deserialize_sentence::<SystemResources>(&sentence);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mikrotik_api::bench_support::decode_len;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((len, consumed)) = decode_len(data) {
+        assert!((1..=5).contains(&consumed));
+        assert!(consumed <= data.len());
+
+        // Encodings shorter than 5 bytes hold at most 28 bits
+        if consumed < 5 {
+            assert!(len < 1 << 28);
+        }
+    }
+});
@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mikrotik_api::bench_support::{decode_sentence, encode_sentence};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((words, consumed)) = decode_sentence(data) {
+        assert!(consumed <= data.len());
+        assert_eq!(Some(""), words.last().map(String::as_str));
+
+        // Words read back are encoded the same way, short lengths aside
+        let words: Vec<&str> = words[..words.len() - 1]
+            .iter()
+            .map(String::as_str)
+            .collect();
+        let encoded = encode_sentence(&words).unwrap();
+
+        assert_eq!(
+            Some((words.len() + 1, encoded.len())),
+            decode_sentence(&encoded).map(|(w, c)| (w.len(), c))
+        );
+    }
+});
@@ -0,0 +1,107 @@
+use tokio::sync::broadcast;
+
+/// Number of events kept for subscribers lagging behind, older events being dropped
+pub(crate) const AUDIT_CAPACITY: usize = 1024;
+
+/// Placeholder for the value of secret attributes
+const REDACTED: &str = "<redacted>";
+
+/// Attribute keys whose value is never included in audit events
+const SECRET_KEYS: &[&str] = &[
+    "password",
+    "secret",
+    "passphrase",
+    "pre-shared-key",
+    "private-key",
+    "response",
+];
+
+/// Something the crate did to (or heard from) the router, see `MikrotikAPI::audit_log`
+#[derive(Debug, Clone)]
+pub enum AuditEvent {
+    /// A command was written to the connection
+    CommandSent {
+        /// Tag of the command
+        tag: u16,
+        /// Command, ie `/interface/print`
+        command: String,
+        /// Attributes, secrets being redacted
+        attributes: Vec<(String, String)>,
+    },
+
+    /// The router answered a command with a `!trap`
+    Trap {
+        /// Tag of the command
+        tag: u16,
+        /// Command, ie `/interface/print`
+        command: String,
+        /// Error message sent by the router
+        message: String,
+    },
+
+    /// A command is over, either completed by a `!done` or abandoned
+    Finished {
+        /// Tag of the command
+        tag: u16,
+        /// Command, ie `/interface/print`
+        command: String,
+        /// Number of sentences received for the command
+        sentences: u64,
+    },
+
+    /// Authentication succeeded
+    Login {
+        /// User name
+        user: String,
+    },
+
+    /// Authentication failed
+    LoginFailed {
+        /// User name
+        user: String,
+        /// Error message sent by the router
+        message: String,
+    },
+}
+
+pub(crate) fn is_secret(key: &str) -> bool {
+    let key = key.trim_start_matches(['=', '?']);
+
+    SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+pub(crate) fn redact(attributes: &[(&str, &str)]) -> Vec<(String, String)> {
+    attributes
+        .iter()
+        .map(|(key, value)| {
+            let value = if is_secret(key) { REDACTED } else { value };
+
+            (key.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+pub(crate) type AuditSender = broadcast::Sender<AuditEvent>;
+
+#[cfg(test)]
+mod tests {
+    use super::redact;
+
+    #[test]
+    fn test_redact_secrets() {
+        let attributes = redact(&[
+            ("name", "admin"),
+            ("password", "hunter2"),
+            ("wpa2-pre-shared-key", "hunter2"),
+        ]);
+
+        assert_eq!(
+            vec![
+                ("name".to_string(), "admin".to_string()),
+                ("password".to_string(), "<redacted>".to_string()),
+                ("wpa2-pre-shared-key".to_string(), "<redacted>".to_string()),
+            ],
+            attributes
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::sync::Arc;
+
+use futures::future;
+use log::warn;
+use tokio::sync::Notify;
+
+use super::{
+    cancel::Cancels,
+    error::Error,
+    lock_tags,
+    options::{BudgetOverflow, MemoryBudget},
+    SharedTagMap, TagMap, TaggedCall,
+};
+
+/// `MemoryBudget` of a connection, along with what the event loop needs to enforce it
+pub(crate) struct Budget {
+    pub budget: MemoryBudget,
+    pub cancels: Arc<Cancels>,
+}
+
+/// Whether a reply fits in the budget
+pub(crate) enum Admission {
+    Admit,
+
+    /// Its call was failed, the reply is to be dropped
+    Drop,
+
+    /// Reads are to pause until one of the streams holding bytes is consumed, see `room`
+    Wait(Vec<Arc<Notify>>),
+}
+
+enum Check {
+    Admit,
+    Wait,
+    Fail(u16),
+}
+
+/// Resolves once one of `streams` is consumed or dropped, never if there is none
+pub(crate) async fn room(streams: Vec<Arc<Notify>>) {
+    match streams.is_empty() {
+        true => future::pending().await,
+        false => {
+            future::select_all(streams.iter().map(|consumed| Box::pin(consumed.notified()))).await;
+        }
+    }
+}
+
+/// Whether `tagged` is a stream holding items its consumer will take out
+fn is_draining(tagged: &TaggedCall) -> bool {
+    tagged.call.is_streaming() && tagged.call.buffered_bytes() > 0
+}
+
+/// Bytes held on behalf of a call: items waiting in a stream, or replies of a call still in progress
+fn usage(tagged: &TaggedCall) -> usize {
+    match tagged.call.is_streaming() {
+        true => tagged.call.buffered_bytes(),
+        false => tagged.pending_bytes,
+    }
+}
+
+impl Budget {
+    /// Whether a reply of `bytes` for `id` fits in the budget, failing calls if need be
+    pub fn admit_reply(&self, tags: &SharedTagMap, id: u16, bytes: usize) -> Admission {
+        let mut tags = lock_tags(tags);
+
+        loop {
+            match self.check(&tags, id, bytes) {
+                Check::Admit => return Admission::Admit,
+
+                Check::Wait => {
+                    let streams = tags
+                        .values()
+                        .filter(|tagged| is_draining(tagged))
+                        .filter_map(|tagged| tagged.call.consumed())
+                        .collect();
+
+                    return Admission::Wait(streams);
+                }
+
+                Check::Fail(tag) => {
+                    self.fail(&mut tags, tag);
+
+                    if tag == id {
+                        return Admission::Drop;
+                    }
+                }
+            }
+        }
+    }
+
+    fn check(&self, tags: &TagMap, id: u16, bytes: usize) -> Check {
+        if !tags.contains_key(&id) {
+            return Check::Admit;
+        }
+
+        let used: usize = tags.values().map(usage).sum();
+
+        if used + bytes <= self.budget.limit {
+            return Check::Admit;
+        }
+
+        // Nobody will ever drain them
+        let abandoned = tags
+            .iter()
+            .find(|(_, tagged)| tagged.call.is_abandoned())
+            .map(|(tag, _)| *tag);
+
+        if let Some(tag) = abandoned {
+            return Check::Fail(tag);
+        }
+
+        let draining = tags.values().any(is_draining);
+
+        if let (BudgetOverflow::Backpressure, true) = (self.budget.overflow, draining) {
+            return Check::Wait;
+        }
+
+        let greediest = tags
+            .iter()
+            .map(|(tag, tagged)| match *tag == id {
+                true => (usage(tagged) + bytes, *tag),
+                false => (usage(tagged), *tag),
+            })
+            .max()
+            .map_or(id, |(_, tag)| tag);
+
+        Check::Fail(greediest)
+    }
+
+    /// Fails the call of `tag` with `Error::MemoryBudgetExceeded`, see `Cancels::fail`
+    fn fail(&self, tags: &mut TagMap, tag: u16) {
+        if let Some(tagged) = tags.get(&tag) {
+            warn!(
+                "memory budget exceeded: failing call with tag {} ({} bytes)",
+                tag,
+                usage(tagged)
+            );
+        }
+
+        self.cancels.fail(tags, tag, Error::MemoryBudgetExceeded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use futures::StreamExt;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        api::{
+            error::Error,
+            model::Response,
+            options::{CallOptions, ConnectOptions, MemoryBudget},
+            MikrotikAPI,
+        },
+        mock::{MockReply, MockRouter},
+    };
+
+    /// Answers `/big/print` and `/interface/listen` with 20 replies of 100 bytes, `/small/print` with a single one
+    async fn connect(budget: MemoryBudget) -> MikrotikAPI<crate::Authenticated> {
+        let padding = "x".repeat(90);
+
+        let replies = |count: usize| {
+            (0..count).fold(MockReply::new(), |reply, i| {
+                reply.re(&[("name", &i.to_string()), ("comment", &padding)])
+            })
+        };
+
+        let router = MockRouter::new()
+            .on("/big/print", replies(20))
+            .on("/interface/listen", replies(20).endless())
+            .on("/small/print", replies(1));
+
+        let options = ConnectOptions::default().with_memory_budget(budget);
+
+        MikrotikAPI::from_transport_with_options(router.spawn(), options)
+            .authenticate("admin", "")
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fail_greediest() {
+        let mut api = connect(MemoryBudget::new(1000).failing_greediest()).await;
+
+        let result = api
+            .generic_array_call::<HashMap<String, String>>("/big/print", None)
+            .await;
+
+        assert!(matches!(result, Err(Error::MemoryBudgetExceeded)));
+
+        // Other calls go on, the failed one being cancelled along with them
+        let small = api
+            .generic_array_call::<HashMap<String, String>>("/small/print", None)
+            .await
+            .unwrap();
+
+        assert_eq!(1, small.len());
+    }
+
+    #[tokio::test]
+    async fn test_backpressure() {
+        let mut api = connect(MemoryBudget::new(1000)).await;
+
+        let mut tag = 0;
+        let mut stream = Box::pin(
+            api.generic_streaming_call::<HashMap<String, String>>(
+                "/interface/listen",
+                None,
+                &mut tag,
+            )
+            .await
+            .unwrap(),
+        );
+
+        // The stream is not consumed: reads are paused, holding back other calls
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(100),
+            api.generic_array_call::<HashMap<String, String>>("/small/print", None),
+        )
+        .await;
+
+        assert!(blocked.is_err());
+
+        let mut names = vec![];
+
+        while names.len() < 20 {
+            match stream.next().await {
+                Some(Response::Reply(mut item)) => names.push(item.remove("name").unwrap()),
+                other => panic!("unexpected item: {:?}", other),
+            }
+        }
+
+        // Nothing was lost
+        let expected: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        assert_eq!(expected, names);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_stream() {
+        let mut api = connect(MemoryBudget::new(1000)).await;
+
+        let mut tag = 0;
+        let stream = api
+            .generic_streaming_call::<HashMap<String, String>>("/interface/listen", None, &mut tag)
+            .await
+            .unwrap();
+
+        // Dropped without being cancelled: nobody will ever make room, the stream is failed rather than waited for
+        drop(stream);
+
+        let small = tokio::time::timeout(
+            Duration::from_secs(1),
+            api.generic_array_call::<HashMap<String, String>>("/small/print", None),
+        )
+        .await
+        .expect("reads stayed paused")
+        .unwrap();
+
+        assert_eq!(1, small.len());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_while_paused() {
+        let mut api = connect(MemoryBudget::new(1000)).await;
+
+        let token = CancellationToken::new();
+        let options = CallOptions::default().with_cancellation(token.clone());
+
+        let mut tag = 0;
+        let _stream = api
+            .generic_streaming_call_with_options::<HashMap<String, String>>(
+                "/interface/listen",
+                None,
+                &mut tag,
+                &options,
+            )
+            .await
+            .unwrap();
+
+        // Reads are paused by the stream not being consumed, its cancellation still ends it
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            token.cancel();
+        });
+
+        let small = tokio::time::timeout(
+            Duration::from_secs(1),
+            api.generic_array_call::<HashMap<String, String>>("/small/print", None),
+        )
+        .await
+        .expect("reads stayed paused")
+        .unwrap();
+
+        assert_eq!(1, small.len());
+    }
+}
@@ -9,9 +9,13 @@ use serde::de::DeserializeOwned;
 
 use std::fmt::Debug;
 
-use crate::api::{de::deserialize_sentence, Response};
+use crate::api::{
+    de::{deserialize_sentence, deserialize_sentence_strict},
+    error::Error,
+    Response,
+};
 
-use super::{AsyncCall, CallError, InnerCall, ThreadSafeInnerCall};
+use super::{lock_call, AsyncCall, CallError, InnerCall, ThreadSafeInnerCall};
 
 pub struct ArrayListCall<T>(ThreadSafeInnerCall<Vec<Response<T>>>);
 
@@ -38,20 +42,45 @@ impl<T: DeserializeOwned + Debug> AsyncCall for ArrayListCall<T> {
     fn push_reply(&mut self, sentence: Vec<String>) -> Result<(), CallError> {
         let value = deserialize_sentence(sentence.as_slice())?;
 
+        self.keep(value)
+    }
+
+    fn done(&mut self) -> Result<(), CallError> {
         if let Ok(mut call) = self.0.lock() {
-            if let Some(vec) = call.inner.as_mut() {
-                vec.push(value);
-            }
+            call.done()?;
+
             return Ok(());
         }
 
         Err(CallError::BadLock)
     }
 
-    fn done(&mut self) -> Result<(), CallError> {
+    fn fail(&mut self, error: Error) -> Result<(), CallError> {
         if let Ok(mut call) = self.0.lock() {
-            call.done()?;
+            return call.fail(error);
+        }
+
+        Err(CallError::BadLock)
+    }
 
+    fn push_strict_reply(&mut self, sentence: Vec<String>) -> Result<Vec<String>, CallError> {
+        let (value, unknown) = deserialize_sentence_strict(sentence.as_slice())?;
+
+        if unknown.is_empty() {
+            self.keep(value)?;
+        }
+
+        Ok(unknown)
+    }
+}
+
+impl<T> ArrayListCall<T> {
+    /// Adds `value` to the replies, unless the call failed
+    fn keep(&self, value: Response<T>) -> Result<(), CallError> {
+        if let Ok(mut call) = self.0.lock() {
+            if let Some(vec) = call.inner.as_mut() {
+                vec.push(value);
+            }
             return Ok(());
         }
 
@@ -60,19 +89,13 @@ impl<T: DeserializeOwned + Debug> AsyncCall for ArrayListCall<T> {
 }
 
 impl<T: Debug> Future for ArrayListCall<T> {
-    type Output = Vec<Response<T>>;
+    type Output = Result<Vec<Response<T>>, Error>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
-        if let Ok(mut call) = self.0.lock() {
-            if let Some(mut vec) = call.get_done().take() {
-                //remove !done response at the end
-                vec.pop();
-
-                return Poll::Ready(vec);
-            }
-        }
-
-        cx.waker().wake_by_ref();
-        Poll::Pending
+        lock_call(&self.0).poll_done(cx).map_ok(|mut vec| {
+            //remove !done response at the end
+            vec.pop();
+            vec
+        })
     }
 }
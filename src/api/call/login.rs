@@ -0,0 +1,80 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use crate::api::{de::deserialize_sentence, error::Error, Response};
+
+use super::{lock_call, AsyncCall, CallError, InnerCall, ThreadSafeInnerCall};
+
+/// Call to `/login`, whose `!done` may hold a `ret` attribute: the challenge sent by pre-6.43 routers.
+/// Resolves to `Reply(challenge)` when there is one, `Done` otherwise.
+pub struct LoginCall(ThreadSafeInnerCall<Response<String>>);
+
+impl LoginCall {
+    pub fn new() -> Self {
+        LoginCall(Arc::new(Mutex::new(InnerCall::new(None))))
+    }
+}
+
+impl Clone for LoginCall {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl AsyncCall for LoginCall {
+    fn push_reply(&mut self, sentence: Vec<String>) -> Result<(), CallError> {
+        let challenge = sentence
+            .iter()
+            .find_map(|word| word.strip_prefix("=ret="))
+            .map(str::to_owned);
+
+        let value = match deserialize_sentence::<()>(sentence.as_slice())? {
+            Response::Trap { category, message } => Response::Trap { category, message },
+
+            Response::Fatal => Response::Fatal,
+
+            _ => challenge.map(Response::Reply).unwrap_or(Response::Done),
+        };
+
+        if let Ok(mut call) = self.0.lock() {
+            if !matches!(call.inner, Some(Response::Trap { .. } | Response::Fatal)) {
+                let _ = call.inner.insert(value);
+            }
+            return Ok(());
+        }
+
+        Err(CallError::BadLock)
+    }
+
+    fn done(&mut self) -> Result<(), CallError> {
+        if let Ok(mut call) = self.0.lock() {
+            call.done()?;
+
+            return Ok(());
+        }
+
+        Err(CallError::BadLock)
+    }
+
+    fn fail(&mut self, error: Error) -> Result<(), CallError> {
+        if let Ok(mut call) = self.0.lock() {
+            return call.fail(error);
+        }
+
+        Err(CallError::BadLock)
+    }
+}
+
+impl Future for LoginCall {
+    type Output = Result<Response<String>, Error>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        lock_call(&self.0).poll_done(cx)
+    }
+}
@@ -1,19 +1,26 @@
 mod array;
+mod login;
 mod one_shot;
+mod raw;
 mod streaming;
 
 use std::{
     fmt::Debug,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Waker},
 };
 
 pub use array::ArrayListCall;
+pub use login::LoginCall;
 pub use one_shot::OneShotCall;
+pub use raw::RawArrayCall;
 pub use streaming::StreamingCall;
 
-use tokio::sync::OnceCell;
+pub(crate) use streaming::SequencedStreamingCall;
 
-use super::de::DeserializerError;
+use tokio::sync::{Notify, OnceCell};
+
+use super::{de::DeserializerError, error::Error, subscription::StreamLag};
 
 pub type EmptyCall = OneShotCall<()>;
 
@@ -27,6 +34,21 @@ pub enum CallError {
     BadSentence(DeserializerError),
 }
 
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use CallError::*;
+        match self {
+            DoneAlreadyHappened => f.write_str("call was already done"),
+
+            DoneWithoutReply => f.write_str("call is done but received no reply"),
+
+            BadLock => f.write_str("failed to lock call state"),
+
+            BadSentence(e) => write!(f, "failed to deserialize sentence: {}", e),
+        }
+    }
+}
+
 impl From<DeserializerError> for CallError {
     fn from(e: DeserializerError) -> Self {
         CallError::BadSentence(e)
@@ -37,11 +59,48 @@ pub trait AsyncCall {
     fn push_reply(&mut self, sentence: Vec<String>) -> Result<(), CallError>;
 
     fn done(&mut self) -> Result<(), CallError>;
+
+    /// Ends the call with `error` rather than the router's answer, for failures found on this side of the connection
+    fn fail(&mut self, error: Error) -> Result<(), CallError>;
+
+    /// Whether the call is a `listen` kind of call, expected to stay idle for long periods
+    fn is_streaming(&self) -> bool {
+        false
+    }
+
+    /// Size of the replies received but not consumed yet, for calls that hand them over one by one
+    fn buffered_bytes(&self) -> usize {
+        0
+    }
+
+    /// How far behind the consumer is, for calls that hand replies over one by one
+    fn lag(&self) -> Option<StreamLag> {
+        None
+    }
+
+    /// Notified when the consumer takes a reply, or may have gone away, for calls that hand replies over one by one
+    fn consumed(&self) -> Option<Arc<Notify>> {
+        None
+    }
+
+    /// Whether nobody is left to take the replies, for calls that hand them over one by one
+    fn is_abandoned(&self) -> bool {
+        false
+    }
+
+    /// Same as `push_reply` for a call with `CallOptions::strict`: the `!re` sentence `sentence` is only kept if the type
+    /// of the replies has a field for each of its properties, those it has none for being returned otherwise
+    fn push_strict_reply(&mut self, sentence: Vec<String>) -> Result<Vec<String>, CallError> {
+        self.push_reply(sentence).map(|_| vec![])
+    }
 }
 
 struct InnerCall<T> {
     inner: Option<T>,
-    done: OnceCell<T>,
+    done: OnceCell<Result<T, Error>>,
+
+    /// Task awaiting the call, woken once it is done
+    waker: Option<Waker>,
 }
 
 impl<T: Debug> InnerCall<T> {
@@ -49,22 +108,54 @@ impl<T: Debug> InnerCall<T> {
         Self {
             inner: value,
             done: OnceCell::new(),
+            waker: None,
         }
     }
 
     pub fn done(&mut self) -> Result<(), CallError> {
         if let Some(value) = self.inner.take() {
-            self.done
-                .set(value)
-                .map_err(|_| CallError::DoneAlreadyHappened)?;
-
-            return Ok(());
+            return self.set_done(Ok(value));
         }
 
         Err(CallError::DoneWithoutReply)
     }
 
-    pub fn get_done(&mut self) -> Option<T> {
-        self.done.take()
+    pub fn fail(&mut self, error: Error) -> Result<(), CallError> {
+        self.inner = None;
+
+        self.set_done(Err(error))
+    }
+
+    fn set_done(&mut self, result: Result<T, Error>) -> Result<(), CallError> {
+        self.done
+            .set(result)
+            .map_err(|_| CallError::DoneAlreadyHappened)?;
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+
+        Ok(())
+    }
+
+    /// The value once the call is done, otherwise the task is registered to be woken when it is
+    pub fn poll_done(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, Error>> {
+        match self.done.take() {
+            Some(value) => Poll::Ready(value),
+
+            None => {
+                match self.waker.as_mut() {
+                    Some(waker) => waker.clone_from(cx.waker()),
+                    None => self.waker = Some(cx.waker().clone()),
+                }
+
+                Poll::Pending
+            }
+        }
     }
 }
+
+/// Locks the state of a call for its future: a panic while it was held cannot leave it inconsistent
+fn lock_call<T>(call: &ThreadSafeInnerCall<T>) -> MutexGuard<'_, InnerCall<T>> {
+    call.lock().unwrap_or_else(PoisonError::into_inner)
+}
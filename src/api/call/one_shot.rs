@@ -3,14 +3,17 @@ use std::{
     future::Future,
     pin::Pin,
     sync::{Arc, Mutex},
-    task::Poll,
 };
 
 use serde::de::DeserializeOwned;
 
-use crate::api::{de::deserialize_sentence, Response};
+use crate::api::{
+    de::{deserialize_sentence, deserialize_sentence_strict},
+    error::Error,
+    Response,
+};
 
-use super::{AsyncCall, CallError, InnerCall, ThreadSafeInnerCall};
+use super::{lock_call, AsyncCall, CallError, InnerCall, ThreadSafeInnerCall};
 
 pub struct OneShotCall<T>(ThreadSafeInnerCall<Response<T>>);
 
@@ -34,20 +37,45 @@ impl<T: DeserializeOwned + Debug> AsyncCall for OneShotCall<T> {
     fn push_reply(&mut self, sentence: Vec<String>) -> Result<(), CallError> {
         let value = deserialize_sentence(sentence.as_slice())?;
 
+        self.keep(value)
+    }
+
+    fn done(&mut self) -> Result<(), CallError> {
         if let Ok(mut call) = self.0.lock() {
-            if call.inner.is_none() {
-                let _ = call.inner.insert(value);
-            }
+            call.done()?;
+
             return Ok(());
         }
 
         Err(CallError::BadLock)
     }
 
-    fn done(&mut self) -> Result<(), CallError> {
+    fn fail(&mut self, error: Error) -> Result<(), CallError> {
         if let Ok(mut call) = self.0.lock() {
-            call.done()?;
+            return call.fail(error);
+        }
+
+        Err(CallError::BadLock)
+    }
 
+    fn push_strict_reply(&mut self, sentence: Vec<String>) -> Result<Vec<String>, CallError> {
+        let (value, unknown) = deserialize_sentence_strict(sentence.as_slice())?;
+
+        if unknown.is_empty() {
+            self.keep(value)?;
+        }
+
+        Ok(unknown)
+    }
+}
+
+impl<T> OneShotCall<T> {
+    /// Keeps `value` as the answer, unless there is one already
+    fn keep(&self, value: Response<T>) -> Result<(), CallError> {
+        if let Ok(mut call) = self.0.lock() {
+            if call.inner.is_none() {
+                let _ = call.inner.insert(value);
+            }
             return Ok(());
         }
 
@@ -56,19 +84,73 @@ impl<T: DeserializeOwned + Debug> AsyncCall for OneShotCall<T> {
 }
 
 impl<T: Debug> Future for OneShotCall<T> {
-    type Output = Response<T>;
+    type Output = Result<Response<T>, Error>;
 
     fn poll(
         self: Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        if let Ok(mut call) = self.0.lock() {
-            if let Some(value) = call.get_done() {
-                return Poll::Ready(value);
-            }
+        lock_call(&self.0).poll_done(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
+
+    use futures::{
+        task::{waker, ArcWake},
+        Future,
+    };
+
+    use crate::api::{call::AsyncCall, Response};
+
+    use super::OneShotCall;
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl ArcWake for CountingWaker {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
         }
+    }
+
+    #[test]
+    fn test_woken_once_done() {
+        let wakes = Arc::new(CountingWaker::default());
+        let waker = waker(wakes.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut call = OneShotCall::<HashMap<String, String>>::new();
+        let mut future = call.clone();
+
+        // Pending polls no longer wake the task right away
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        assert_eq!(0, wakes.0.load(Ordering::SeqCst));
+
+        let sentence = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+
+        call.push_reply(sentence(&["!re", ".tag=1", "=name=ether1", ""]))
+            .unwrap();
+        call.push_reply(sentence(&["!done", ".tag=1", ""])).unwrap();
+        assert_eq!(0, wakes.0.load(Ordering::SeqCst));
+
+        call.done().unwrap();
+        assert_eq!(1, wakes.0.load(Ordering::SeqCst));
 
-        cx.waker().wake_by_ref();
-        Poll::Pending
+        assert!(matches!(
+            Pin::new(&mut future).poll(&mut cx),
+            Poll::Ready(Ok(Response::Reply(reply))) if reply["name"] == "ether1"
+        ));
     }
 }
@@ -0,0 +1,70 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+
+use crate::api::error::Error;
+
+use super::{lock_call, AsyncCall, CallError, InnerCall, ThreadSafeInnerCall};
+
+/// Call keeping its replies as received, to be deserialized later into types borrowing from them
+pub struct RawArrayCall(ThreadSafeInnerCall<Vec<Vec<String>>>);
+
+impl RawArrayCall {
+    pub fn new() -> Self {
+        let inner = InnerCall::new(Some(Vec::new()));
+
+        Self(Arc::new(Mutex::new(inner)))
+    }
+}
+
+impl Clone for RawArrayCall {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl AsyncCall for RawArrayCall {
+    fn push_reply(&mut self, sentence: Vec<String>) -> Result<(), CallError> {
+        if let Ok(mut call) = self.0.lock() {
+            if let Some(vec) = call.inner.as_mut() {
+                vec.push(sentence);
+            }
+            return Ok(());
+        }
+
+        Err(CallError::BadLock)
+    }
+
+    fn done(&mut self) -> Result<(), CallError> {
+        if let Ok(mut call) = self.0.lock() {
+            call.done()?;
+
+            return Ok(());
+        }
+
+        Err(CallError::BadLock)
+    }
+
+    fn fail(&mut self, error: Error) -> Result<(), CallError> {
+        if let Ok(mut call) = self.0.lock() {
+            return call.fail(error);
+        }
+
+        Err(CallError::BadLock)
+    }
+}
+
+impl Future for RawArrayCall {
+    type Output = Result<Vec<Vec<String>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        lock_call(&self.0).poll_done(cx).map_ok(|mut vec| {
+            //remove !done response at the end
+            vec.pop();
+            vec
+        })
+    }
+}
@@ -1,32 +1,124 @@
 use std::fmt::Debug;
 use std::{
+    collections::VecDeque,
     sync::{Arc, Mutex},
-    task::Poll,
+    task::{Poll, Waker},
+    time::Instant,
 };
 
 use futures::Stream;
+use log::warn;
 use serde::de::DeserializeOwned;
-use tokio::sync::{
-    mpsc::{self, UnboundedReceiver, UnboundedSender},
-    OnceCell,
-};
+use tokio::sync::{Notify, OnceCell};
 
-use crate::api::{de::deserialize_sentence, Response};
+use crate::api::{
+    de::{deserialize_sentence, deserialize_sentence_strict},
+    error::Error,
+    model::{StreamItem, FATAL},
+    options::{StreamBuffer, StreamOverflow},
+    subscription::{StreamFailure, StreamLag},
+    Response,
+};
 
 use super::{AsyncCall, CallError};
 pub struct StreamingCall<T> {
-    inner: Arc<Mutex<InnerStreamingCall<Response<T>>>>,
-    // sender: Arc<Mutex<Sender<Response<T>>>>
+    inner: Arc<Mutex<InnerStreamingCall<T>>>,
+
+    /// Notified when an item is taken out of the stream, or a handle to it dropped
+    consumed: Arc<Notify>,
 }
 
 struct InnerStreamingCall<T> {
-    receiver: UnboundedReceiver<T>,
-    sender: UnboundedSender<T>,
+    /// Items waiting to be consumed, in order, along with the size of their sentence
+    queue: VecDeque<(StreamItem<Response<T>>, usize)>,
+    buffer: StreamBuffer,
+
+    /// Task waiting for the next item
+    waker: Option<Waker>,
+
     cell: OnceCell<()>,
+    next_seq: u64,
+    failure: Option<StreamFailure>,
+    closed: bool,
+    finished: Arc<Notify>,
+
+    /// Whether the command may be sent again once the router ended it, see `StreamingCall::resumable`
+    resumable: bool,
+
+    /// Sum of the sizes of the items in `queue`
+    buffered: usize,
+
+    /// Replies dropped so far because the consumer fell behind
+    dropped: u64,
 }
 
 impl<T> InnerStreamingCall<T> {
+    /// Numbers `value` and queues it, making room for it as `buffer` says if the consumer is too far behind.
+    /// Dropped replies are numbered all the same, leaving a gap in the sequence numbers
+    fn send(&mut self, value: Response<T>, bytes: usize) {
+        // The end of the command is not the end of a resumable stream, which only ends once closed
+        if let (Response::Done, true, false) = (&value, self.resumable, self.closed) {
+            return;
+        }
+
+        let item = StreamItem {
+            seq: self.next_seq,
+            received_at: Instant::now(),
+            value,
+        };
+        self.next_seq += 1;
+
+        let is_reply = matches!(item.value, Response::Reply(_));
+
+        if is_reply && self.queue.len() >= self.buffer.capacity {
+            self.dropped += 1;
+
+            if self.dropped == 1 {
+                warn!(
+                    "stream: consumer {} items behind, replies are being dropped ({:?})",
+                    self.queue.len(),
+                    self.buffer.overflow
+                );
+            }
+
+            match self.buffer.overflow {
+                StreamOverflow::DropNewest => return,
+
+                StreamOverflow::DropOldest => {
+                    let oldest = self
+                        .queue
+                        .iter()
+                        .position(|(item, _)| matches!(item.value, Response::Reply(_)));
+
+                    if let Some((_, bytes)) = oldest.and_then(|index| self.queue.remove(index)) {
+                        self.buffered -= bytes;
+                    }
+                }
+            }
+        }
+
+        self.queue.push_back((item, bytes));
+        self.buffered += bytes;
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn lag(&self) -> StreamLag {
+        StreamLag {
+            depth: self.queue.len(),
+            oldest: self
+                .queue
+                .front()
+                .map(|(item, _)| item.received_at.elapsed()),
+            dropped: self.dropped,
+        }
+    }
+
     pub fn done(&mut self) -> Result<(), CallError> {
+        self.finished.notify_waiters();
+
         self.cell
             .set(())
             .map_err(|_| CallError::DoneAlreadyHappened)
@@ -35,24 +127,143 @@ impl<T> InnerStreamingCall<T> {
 
 impl<T> StreamingCall<T> {
     pub fn new() -> Self {
-        let (sender, receiver) = mpsc::unbounded_channel();
+        Self::with_buffer(StreamBuffer::default())
+    }
 
+    /// Stream holding at most `buffer.capacity` items for its consumer
+    pub fn with_buffer(buffer: StreamBuffer) -> Self {
         let inner = Arc::new(Mutex::new(InnerStreamingCall {
-            sender,
-            receiver,
+            queue: VecDeque::new(),
+            buffer,
+            waker: None,
             cell: OnceCell::new(),
+            next_seq: 0,
+            failure: None,
+            closed: false,
+            finished: Arc::new(Notify::new()),
+            resumable: false,
+            buffered: 0,
+            dropped: 0,
         }));
 
-        Self { inner }
+        Self {
+            inner,
+            consumed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Keeps the stream open when the router ends the command, with a `!done` or after a `!trap`, for the command to be
+    /// sent again with `reset`: the stream then only ends once closed
+    pub fn resumable(self) -> Self {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.resumable = true;
+        }
+
+        self
+    }
+
+    /// Ends the stream without the router having sent a `!done`
+    pub fn close(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner.closed {
+                return;
+            }
+
+            inner.closed = true;
+            inner.send(Response::Done, 0);
+
+            inner.finished.notify_waiters();
+        }
+    }
+
+    /// Whether the stream ended: `!done` received, `!trap`, or closed
+    pub fn is_finished(&self) -> bool {
+        match self.inner.lock() {
+            Ok(inner) => inner.closed || inner.failure.is_some() || inner.cell.initialized(),
+            Err(_) => true,
+        }
+    }
+
+    /// Notified once the stream ends, see `is_finished`
+    pub fn finished(&self) -> Arc<Notify> {
+        match self.inner.lock() {
+            Ok(inner) => inner.finished.clone(),
+            Err(_) => Arc::new(Notify::new()),
+        }
+    }
+
+    /// Items waiting to be consumed, and for how long the oldest has been waiting
+    pub fn lag(&self) -> StreamLag {
+        self.inner
+            .lock()
+            .map_or(StreamLag::default(), |inner| inner.lag())
+    }
+
+    /// Why the router ended the stream with a `!trap`, if it did
+    pub fn failure(&self) -> Option<StreamFailure> {
+        self.inner.lock().ok()?.failure.clone()
+    }
+
+    /// Forgets about a previous failure, so that the command can be sent again
+    pub fn reset(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.failure = None;
+            inner.closed = false;
+            inner.cell = OnceCell::new();
+        }
+    }
+
+    /// Sequence number the next item will get
+    pub(crate) fn next_seq(&self) -> u64 {
+        self.inner.lock().map_or(0, |inner| inner.next_seq)
+    }
+
+    /// Turns this call into a stream of items carrying their sequence number and receive time
+    pub fn sequenced(self) -> SequencedStreamingCall<T> {
+        SequencedStreamingCall(self)
+    }
+
+    fn poll_item(
+        &self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<StreamItem<Response<T>>>> {
+        if let Ok(mut inner) = self.inner.lock() {
+            return match inner.queue.pop_front() {
+                Some((item, bytes)) => {
+                    inner.buffered -= bytes;
+
+                    // Reads may be paused until there is room in the memory budget of the connection
+                    self.consumed.notify_one();
+
+                    match item.value {
+                        // A !done reply is our End Of Stream.
+                        Response::Done => Poll::Ready(None),
+                        _ => Poll::Ready(Some(item)),
+                    }
+                }
+
+                None => {
+                    inner.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            };
+        }
+
+        Poll::Pending
     }
 }
 
 impl<T: DeserializeOwned + Debug> AsyncCall for StreamingCall<T> {
     fn push_reply(&mut self, sentence: Vec<String>) -> Result<(), CallError> {
         let value = deserialize_sentence(sentence.as_slice())?;
+        let bytes = sentence.iter().map(String::len).sum();
 
-        if let Ok(inner) = self.inner.lock() {
-            let _ = inner.sender.send(value);
+        self.keep(value, bytes)
+    }
+
+    fn done(&mut self) -> Result<(), CallError> {
+        if let Ok(mut call) = self.inner.lock() {
+            call.done()?;
 
             return Ok(());
         }
@@ -60,9 +271,79 @@ impl<T: DeserializeOwned + Debug> AsyncCall for StreamingCall<T> {
         Err(CallError::BadLock)
     }
 
-    fn done(&mut self) -> Result<(), CallError> {
-        if let Ok(mut call) = self.inner.lock() {
-            call.done()?;
+    /// The stream ends, `error` being told as a `Response::Trap` without category
+    fn fail(&mut self, error: Error) -> Result<(), CallError> {
+        let trap = Response::Trap {
+            category: None,
+            message: error.to_string(),
+        };
+
+        self.keep(trap, 0)
+    }
+
+    fn push_strict_reply(&mut self, sentence: Vec<String>) -> Result<Vec<String>, CallError> {
+        let (value, unknown) = deserialize_sentence_strict(sentence.as_slice())?;
+        let bytes = sentence.iter().map(String::len).sum();
+
+        if unknown.is_empty() {
+            self.keep(value, bytes)?;
+        }
+
+        Ok(unknown)
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+
+    fn buffered_bytes(&self) -> usize {
+        self.inner.lock().map_or(0, |inner| inner.buffered)
+    }
+
+    fn lag(&self) -> Option<StreamLag> {
+        Some(StreamingCall::lag(self))
+    }
+
+    fn consumed(&self) -> Option<Arc<Notify>> {
+        Some(self.consumed.clone())
+    }
+
+    /// Only the event loop holds the call, the stream and the `StreamHandle` it came from being dropped
+    fn is_abandoned(&self) -> bool {
+        Arc::strong_count(&self.inner) == 1
+    }
+}
+
+impl<T> StreamingCall<T> {
+    /// Hands `value` over to the stream, a `!trap` or a `!fatal` ending it
+    fn keep(&self, value: Response<T>, bytes: usize) -> Result<(), CallError> {
+        if let Ok(mut inner) = self.inner.lock() {
+            // The stream already ended on a !trap, a !fatal or being closed, the !done following it is not news.
+            if inner.failure.is_some() || inner.closed {
+                return Ok(());
+            }
+
+            let failure = match &value {
+                Response::Trap { category, message } => Some(StreamFailure {
+                    category: *category,
+                    message: message.clone(),
+                }),
+                Response::Fatal => Some(StreamFailure {
+                    category: None,
+                    message: FATAL.to_owned(),
+                }),
+                _ => None,
+            };
+
+            inner.send(value, bytes);
+
+            // A !trap or a !fatal ends a subscription, whether or not the router bothers sending a !done.
+            if failure.is_some() {
+                inner.failure = failure;
+                inner.finished.notify_waiters();
+
+                inner.send(Response::Done, 0);
+            }
 
             return Ok(());
         }
@@ -75,10 +356,18 @@ impl<T> Clone for StreamingCall<T> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            consumed: self.consumed.clone(),
         }
     }
 }
 
+impl<T> Drop for StreamingCall<T> {
+    fn drop(&mut self) {
+        // The stream may be abandoned, see `is_abandoned`
+        self.consumed.notify_one();
+    }
+}
+
 impl<T> Stream for StreamingCall<T> {
     type Item = Response<T>;
 
@@ -86,17 +375,123 @@ impl<T> Stream for StreamingCall<T> {
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        if let Ok(mut inner) = self.inner.lock() {
-            let next_value = inner.receiver.poll_recv(cx);
+        self.poll_item(cx)
+            .map(|item| item.map(|StreamItem { value, .. }| value))
+    }
+}
 
-            if let Poll::Ready(Some(Response::Done)) = next_value {
-                // A !done reply is our End Of Stream.
-                return Poll::Ready(None);
-            }
+/// Same as `StreamingCall`, yielding `StreamItem`s
+pub struct SequencedStreamingCall<T>(StreamingCall<T>);
+
+impl<T> Stream for SequencedStreamingCall<T> {
+    type Item = StreamItem<Response<T>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0.poll_item(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
 
-            return next_value;
+    use futures::StreamExt;
+
+    use crate::api::{
+        call::AsyncCall,
+        model::Response,
+        options::{StreamBuffer, StreamOverflow},
+    };
+
+    use super::StreamingCall;
+
+    fn reply(name: &str) -> Vec<String> {
+        ["!re", ".tag=1", &format!("=name={}", name), ""]
+            .iter()
+            .map(|w| w.to_string())
+            .collect()
+    }
+
+    /// Pushes `count` replies to a stream of `capacity` items, then a `!trap`, returning what the consumer gets
+    async fn overflow(overflow: StreamOverflow, count: usize) -> Vec<(u64, Option<String>)> {
+        let buffer = StreamBuffer {
+            capacity: 3,
+            overflow,
+        };
+        let mut call = StreamingCall::<HashMap<String, String>>::with_buffer(buffer);
+
+        for i in 0..count {
+            call.push_reply(reply(&i.to_string())).unwrap();
         }
 
-        Poll::Pending
+        call.push_reply(
+            ["!trap", ".tag=1", "=message=interrupted", ""]
+                .iter()
+                .map(|w| w.to_string())
+                .collect(),
+        )
+        .unwrap();
+
+        assert_eq!(count.saturating_sub(3) as u64, call.lag().dropped);
+
+        call.sequenced()
+            .map(|item| match item.value {
+                Response::Reply(mut reply) => (item.seq, reply.remove("name")),
+                _ => (item.seq, None),
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest() {
+        let items = overflow(StreamOverflow::DropOldest, 5).await;
+
+        // Replies 0 and 1 made room for the last ones, the !trap being kept all the same
+        assert_eq!(
+            vec![
+                (2, Some("2".to_owned())),
+                (3, Some("3".to_owned())),
+                (4, Some("4".to_owned())),
+                (5, None),
+            ],
+            items
+        );
+    }
+
+    #[tokio::test]
+    async fn test_drop_newest() {
+        let items = overflow(StreamOverflow::DropNewest, 5).await;
+
+        assert_eq!(
+            vec![
+                (0, Some("0".to_owned())),
+                (1, Some("1".to_owned())),
+                (2, Some("2".to_owned())),
+                (5, None),
+            ],
+            items
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_item_numbered() {
+        let mut call = StreamingCall::<HashMap<String, String>>::new();
+        let mut stream = call.clone().sequenced();
+
+        call.push_reply(reply("ether1")).unwrap();
+        call.close();
+
+        assert_eq!(0, stream.next().await.unwrap().seq);
+        assert!(stream.next().await.is_none());
+
+        // The Done ending the stream took number 1, the stream carrying on after a reset
+        call.reset();
+        call.push_reply(reply("ether2")).unwrap();
+
+        assert_eq!(2, stream.next().await.unwrap().seq);
     }
 }
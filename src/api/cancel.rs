@@ -0,0 +1,242 @@
+use std::{
+    future::Future,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use futures::future::{self, select_all};
+use log::debug;
+use rand::distributions::{Distribution, Uniform};
+use tokio::{io::AsyncWriteExt, sync::Notify, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    call::EmptyCall, encode_sentence, error::Error, lock_tags, monitor::SharedMonitor, next_tag,
+    options::Priority, SharedTagMap, SharedWriter, TagMap, TaggedCall,
+};
+
+/// `/cancel` commands, sent by the event loop as soon as they are requested rather than along with the next command:
+/// streams stop on the router even when the connection is otherwise idle
+#[derive(Default)]
+pub(crate) struct Cancels {
+    state: Mutex<State>,
+    changed: Notify,
+}
+
+#[derive(Default)]
+struct State {
+    /// Tags to send `/cancel` for
+    queued: Vec<u16>,
+
+    /// Streams to end once their token fires or their deadline passes
+    watched: Vec<Watch>,
+
+    /// Tags to stop dispatching replies for
+    forgotten: Vec<u16>,
+}
+
+#[derive(Clone)]
+struct Watch {
+    tag: u16,
+    token: Option<CancellationToken>,
+    deadline: Option<Instant>,
+}
+
+impl Watch {
+    fn is_due(&self) -> bool {
+        self.token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+            || self
+                .deadline
+                .is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    async fn due(self) {
+        let cancelled = async {
+            match &self.token {
+                Some(token) => token.cancelled().await,
+                None => future::pending().await,
+            }
+        };
+
+        let deadline = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            _ = cancelled => (),
+            _ = deadline => (),
+        }
+    }
+}
+
+impl Cancels {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Has `/cancel` sent for `tag`, without waiting for the router's answer
+    pub fn cancel(&self, tag: u16) {
+        self.state().queued.push(tag);
+        self.changed.notify_one();
+    }
+
+    /// Ends the call of `tag` with `error`, forgetting about it: `/cancel` is sent for it right after
+    pub fn fail(&self, tags: &mut TagMap, tag: u16, error: Error) {
+        let Some(mut tagged) = tags.remove(&tag) else {
+            return;
+        };
+
+        debug!("cancels: failing tag {} ({})", tag, error);
+
+        let _ = tagged.call.fail(error);
+
+        self.unwatch(tag);
+        self.cancel(tag);
+    }
+
+    /// Has the event loop stop dispatching replies for `tag`, for owners of a call that cannot reach the tag map
+    pub fn forget(&self, tag: u16) {
+        self.state().forgotten.push(tag);
+        self.changed.notify_one();
+    }
+
+    /// Ends the stream of `tag` once `token` fires or `deadline` passes, `/cancel` being sent for it
+    pub fn watch(&self, tag: u16, token: Option<CancellationToken>, deadline: Option<Instant>) {
+        if token.is_none() && deadline.is_none() {
+            return;
+        }
+
+        self.state().watched.push(Watch {
+            tag,
+            token,
+            deadline,
+        });
+        self.changed.notify_one();
+    }
+
+    /// Stops watching `tag`, its call being over
+    pub fn unwatch(&self, tag: u16) {
+        self.state().watched.retain(|watch| watch.tag != tag);
+    }
+
+    /// Resolves once a `/cancel` is requested, a stream is watched, or one of the streams watched so far is due
+    pub fn changed(&self) -> impl Future<Output = ()> + '_ {
+        let watched = self.state().watched.clone();
+
+        async move {
+            let due = async {
+                match watched.is_empty() {
+                    true => future::pending().await,
+                    false => {
+                        select_all(watched.into_iter().map(|watch| Box::pin(watch.due()))).await;
+                    }
+                }
+            };
+
+            tokio::select! {
+                _ = self.changed.notified() => (),
+                _ = due => (),
+            }
+        }
+    }
+
+    /// Forgets the tags asked to, ends the watched streams that are due, then sends `/cancel` for them and for the tags queued.
+    /// Answers are not waited for, the `!done` of each `/cancel` going to a call nobody awaits
+    pub async fn send(
+        &self,
+        tags: &SharedTagMap,
+        monitor: &SharedMonitor,
+        writer: &SharedWriter,
+    ) -> Result<(), Error> {
+        let forgotten = std::mem::take(&mut self.state().forgotten);
+
+        for tag in forgotten {
+            lock_tags(tags).remove(&tag);
+
+            if let Ok(mut monitor) = monitor.lock() {
+                monitor.finished(tag);
+            }
+        }
+
+        let due: Vec<u16> = {
+            let mut state = self.state();
+
+            let (due, watched) = std::mem::take(&mut state.watched)
+                .into_iter()
+                .partition(Watch::is_due);
+
+            state.watched = watched;
+            due.into_iter().map(|watch| watch.tag).collect()
+        };
+
+        for tag in due {
+            let Some(mut tagged) = lock_tags(tags).remove(&tag) else {
+                continue;
+            };
+
+            debug!("cancels: ending stream with tag {}", tag);
+
+            let done = vec!["!done".to_owned(), String::new()];
+
+            let _ = tagged.call.push_reply(done);
+            let _ = tagged.call.done();
+
+            if let Ok(mut monitor) = monitor.lock() {
+                monitor.finished(tag);
+            }
+
+            self.state().queued.push(tag);
+        }
+
+        let queued = std::mem::take(&mut self.state().queued);
+
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        let mut bytes = vec![];
+
+        for tag in queued {
+            let cancel_tag = {
+                let mut map = lock_tags(tags);
+
+                let mut tag_iter = Uniform::from(1..u16::MAX).sample_iter(rand::thread_rng());
+                let cancel_tag = next_tag(&mut tag_iter, &map);
+
+                map.insert(
+                    cancel_tag,
+                    TaggedCall {
+                        call: Box::new(EmptyCall::new()),
+                        priority: Priority::Interactive,
+                        pre_filter: None,
+                        progress: None,
+                        received: 0,
+                        pending_bytes: 0,
+                        strict: false,
+                    },
+                );
+
+                cancel_tag
+            };
+
+            debug!("cancels: cancelling tag {}", tag);
+
+            bytes.extend(encode_sentence(&[
+                "/cancel".to_owned(),
+                format!(".tag={}", cancel_tag),
+                format!("=tag={}", tag),
+            ])?);
+        }
+
+        let mut writer = writer.lock().await;
+
+        writer.write_all(&bytes).await?;
+        writer.flush().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::{stream, Stream};
+
+use super::{de::DeserializerError, error::Error, model::SystemClock, Authenticated, MikrotikAPI};
+
+/// Difference between the clock of the router and the one of the host, see `MikrotikAPI::clock_drift`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockDrift {
+    /// Router clock minus host clock, in milliseconds: positive when the router is ahead.
+    /// The router only gives its time to the second, so anything below a second is noise
+    pub offset_ms: i64,
+
+    /// Time taken by `/system/clock/print`, half of which is assumed to have elapsed before the router read its clock
+    pub round_trip: Duration,
+}
+
+impl ClockDrift {
+    /// Absolute value of the drift
+    pub fn magnitude(&self) -> Duration {
+        Duration::from_millis(self.offset_ms.unsigned_abs())
+    }
+
+    fn between(router: SystemTime, host: SystemTime, round_trip: Duration) -> Self {
+        let offset_ms = match router.duration_since(host) {
+            Ok(ahead) => ahead.as_millis() as i64,
+            Err(behind) => -(behind.duration().as_millis() as i64),
+        };
+
+        Self {
+            offset_ms,
+            round_trip,
+        }
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Get the date, time and time zone of the router
+    pub async fn system_clock(&mut self) -> Result<SystemClock, Error> {
+        self.generic_oneshot_call("/system/clock/print", None).await
+    }
+
+    /// Compares the clock of the router with the one of the host, compensating for the round trip of the call
+    pub async fn clock_drift(&mut self) -> Result<ClockDrift, Error> {
+        let sent_at = SystemTime::now();
+        let start = Instant::now();
+
+        let clock = self.system_clock().await?;
+
+        let round_trip = start.elapsed();
+
+        let router = clock
+            .to_system_time()
+            .map_err(|e| DeserializerError::BadPrimitiveValue(Box::new(e)))?;
+
+        Ok(ClockDrift::between(
+            router,
+            sent_at + round_trip / 2,
+            round_trip,
+        ))
+    }
+
+    /// Measures the drift every `period`, only yielding the ones exceeding `threshold`.
+    /// Ends after the first failed measure, its error being the last item
+    pub fn clock_drift_alarms(
+        &mut self,
+        period: Duration,
+        threshold: Duration,
+    ) -> impl Stream<Item = Result<ClockDrift, Error>> + '_ {
+        let interval = tokio::time::interval(period);
+
+        stream::unfold(Some((self, interval)), move |state| async move {
+            let (api, mut interval) = state?;
+
+            loop {
+                interval.tick().await;
+
+                match api.clock_drift().await {
+                    Ok(drift) if drift.magnitude() > threshold => {
+                        return Some((Ok(drift), Some((api, interval))))
+                    }
+
+                    Ok(_) => continue,
+
+                    Err(e) => return Some((Err(e), None)),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::ClockDrift;
+
+    #[test]
+    fn test_drift() {
+        let host = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let rtt = Duration::from_millis(40);
+
+        let behind = ClockDrift::between(host - Duration::from_millis(2500), host, rtt);
+        assert_eq!(-2500, behind.offset_ms);
+        assert_eq!(Duration::from_millis(2500), behind.magnitude());
+
+        let ahead = ClockDrift::between(host + Duration::from_secs(3), host, rtt);
+        assert_eq!(3000, ahead.offset_ms);
+    }
+}
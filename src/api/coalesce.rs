@@ -0,0 +1,200 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{Future, Stream};
+use tokio::time::Sleep;
+
+use super::model::Response;
+
+/// Combinators for streams of `Response`, such as the ones returned by `generic_streaming_call` or `interface_changes`
+pub trait ResponseStreamExt<T>: Stream<Item = Response<T>> + Sized {
+    /// Holds replies for `window` after the first one of a burst, then emits only the last of them.
+    /// `Trap`, `Done` and `Fatal` markers are not delayed: held replies are emitted right before them
+    fn debounce(self, window: Duration) -> Coalesce<Self, T, (), fn(&T)> {
+        self.coalesce_by_key(window, |_| ())
+    }
+
+    /// Holds replies for `window` after the first one of a burst, then emits the last reply of every key,
+    /// in the order keys were first seen. Typically keyed by `.id`, so that an interface flapping
+    /// a hundred times a second is reported once per window with its latest state.
+    /// `Trap`, `Done` and `Fatal` markers are not delayed: held replies are emitted right before them
+    fn coalesce_by_key<K, F>(self, window: Duration, key: F) -> Coalesce<Self, T, K, F>
+    where
+        K: PartialEq,
+        F: FnMut(&T) -> K,
+    {
+        Coalesce {
+            inner: Box::pin(self),
+            key,
+            window,
+            held: vec![],
+            flush_at: None,
+            ready: VecDeque::new(),
+            finished: false,
+        }
+    }
+}
+
+impl<S, T> ResponseStreamExt<T> for S where S: Stream<Item = Response<T>> {}
+
+/// Stream returned by `ResponseStreamExt::coalesce_by_key` and `ResponseStreamExt::debounce`
+pub struct Coalesce<S, T, K, F> {
+    inner: Pin<Box<S>>,
+    key: F,
+    window: Duration,
+
+    /// Last reply of every key since the burst started
+    held: Vec<(K, T)>,
+    flush_at: Option<Pin<Box<Sleep>>>,
+
+    ready: VecDeque<Response<T>>,
+    finished: bool,
+}
+
+// The inner stream and the timer are boxed, nothing else is ever pinned
+impl<S, T, K, F> Unpin for Coalesce<S, T, K, F> {}
+
+impl<S, T, K, F> Coalesce<S, T, K, F> {
+    fn flush(&mut self) {
+        self.flush_at = None;
+
+        let held = self.held.drain(..).map(|(_, item)| Response::Reply(item));
+
+        self.ready.extend(held);
+    }
+}
+
+impl<S, T, K, F> Stream for Coalesce<S, T, K, F>
+where
+    S: Stream<Item = Response<T>>,
+    K: PartialEq,
+    F: FnMut(&T) -> K,
+{
+    type Item = Response<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(item) = this.ready.pop_front() {
+                return Poll::Ready(Some(item));
+            }
+
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            match this.inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Response::Reply(item))) => {
+                    let key = (this.key)(&item);
+
+                    match this.held.iter_mut().find(|(held, _)| *held == key) {
+                        Some((_, held)) => *held = item,
+                        None => this.held.push((key, item)),
+                    }
+
+                    if this.flush_at.is_none() {
+                        this.flush_at = Some(Box::pin(tokio::time::sleep(this.window)));
+                    }
+
+                    continue;
+                }
+
+                Poll::Ready(Some(marker)) => {
+                    this.flush();
+                    this.ready.push_back(marker);
+                    continue;
+                }
+
+                Poll::Ready(None) => {
+                    this.flush();
+                    this.finished = true;
+                    continue;
+                }
+
+                Poll::Pending => (),
+            }
+
+            if let Some(flush_at) = this.flush_at.as_mut() {
+                if flush_at.as_mut().poll(cx).is_ready() {
+                    this.flush();
+                    continue;
+                }
+            }
+
+            return Poll::Pending;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{stream, StreamExt};
+
+    use super::ResponseStreamExt;
+    use crate::api::model::Response;
+
+    fn replies(items: Vec<Response<(u8, &'static str)>>) -> Vec<(u8, &'static str)> {
+        items
+            .into_iter()
+            .filter_map(|item| match item {
+                Response::Reply(item) => Some(item),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_by_key() {
+        let burst = stream::iter(vec![
+            Response::Reply((1, "down")),
+            Response::Reply((2, "down")),
+            Response::Reply((1, "up")),
+            Response::Reply((1, "down")),
+        ]);
+
+        let items: Vec<_> = burst
+            .coalesce_by_key(Duration::from_millis(20), |(id, _)| *id)
+            .collect()
+            .await;
+
+        assert_eq!(vec![(1, "down"), (2, "down")], replies(items));
+    }
+
+    #[tokio::test]
+    async fn test_markers_flush_held_replies() {
+        let burst = stream::iter(vec![
+            Response::Reply((1, "down")),
+            Response::Reply((1, "up")),
+            Response::Trap {
+                category: None,
+                message: "interrupted".into(),
+            },
+            Response::Reply((1, "down")),
+        ])
+        .chain(stream::pending());
+
+        let mut coalesced = Box::pin(burst.debounce(Duration::from_millis(20)));
+
+        assert!(matches!(
+            coalesced.next().await,
+            Some(Response::Reply((1, "up")))
+        ));
+        assert!(matches!(
+            coalesced.next().await,
+            Some(Response::Trap { .. })
+        ));
+
+        // Held for the whole window
+        assert!(matches!(
+            coalesced.next().await,
+            Some(Response::Reply((1, "down")))
+        ));
+    }
+}
@@ -5,7 +5,7 @@ pub enum DeserializerError {
     MissingWord,
     MissingKey,
     MissingValue,
-    BadPrimitiveValue(Box<dyn std::error::Error>),
+    BadPrimitiveValue(Box<dyn std::error::Error + Send + Sync>),
     Custom(Cow<'static, str>),
 }
 
@@ -0,0 +1,203 @@
+use std::fmt;
+
+use serde::{
+    de::{self, DeserializeOwned, IntoDeserializer, MapAccess, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+
+use super::lenient::{DefaultValue, TOKEN};
+
+/// Fields of `T` that are required but not in `present`, typically the properties of a `.proplist`.
+///
+/// Found by deserializing `T` from a map holding default values for `present` only, again and again, serde reporting
+/// one missing field at a time. `None` when it cannot be told: `T` is not a struct, is `Lenient`, or has fields with no default value.
+pub(crate) fn missing_fields<T: DeserializeOwned>(present: &[&str]) -> Option<Vec<&'static str>> {
+    let mut keys: Vec<&str> = present.to_vec();
+    let mut missing = vec![];
+
+    loop {
+        let probe = Probe { keys: &keys };
+
+        match T::deserialize(probe) {
+            Ok(_) => return Some(missing),
+
+            Err(ProbeError::Missing(field)) if !keys.contains(&field) => {
+                missing.push(field);
+                keys.push(field);
+            }
+
+            Err(_) => return None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum ProbeError {
+    /// A field of the probed struct is missing, those of nested structs being reported as `Other`
+    Missing(&'static str),
+
+    Other,
+}
+
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeError::Missing(field) => write!(f, "missing field `{}`", field),
+            ProbeError::Other => f.write_str("cannot probe fields"),
+        }
+    }
+}
+
+impl std::error::Error for ProbeError {}
+
+impl de::Error for ProbeError {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        ProbeError::Other
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        ProbeError::Missing(field)
+    }
+}
+
+struct Probe<'a> {
+    keys: &'a [&'a str],
+}
+
+impl<'de, 'a> Deserializer<'de> for Probe<'a> {
+    type Error = ProbeError;
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(ProbeError::Other)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // `Lenient` defaults missing fields on its own
+        if name == TOKEN {
+            return Err(ProbeError::Other);
+        }
+
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(ProbeMap {
+            keys: self.keys.iter(),
+        })
+    }
+}
+
+struct ProbeMap<'a> {
+    keys: std::slice::Iter<'a, &'a str>,
+}
+
+impl<'de, 'a> MapAccess<'de> for ProbeMap<'a> {
+    type Error = ProbeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.keys.next() {
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let mut optional = false;
+
+        seed.deserialize(DefaultValue {
+            optional: &mut optional,
+        })
+        .map_err(|_| ProbeError::Other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use crate::{api::model::MacAddress, Lenient};
+
+    use super::missing_fields;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    #[allow(dead_code)]
+    struct Model {
+        #[serde(rename = ".id")]
+        id: String,
+        name: String,
+        running: bool,
+        mtu: u16,
+        comment: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        assert_eq!(
+            Some(vec![]),
+            missing_fields::<Model>(&[".id", "name", "running", "mtu"])
+        );
+
+        // Optional and defaulted fields are never missing, unknown properties are ignored
+        assert_eq!(
+            Some(vec![]),
+            missing_fields::<Model>(&["mtu", ".id", "l2mtu", "running", "name"])
+        );
+
+        assert_eq!(
+            Some(vec![".id", "running", "mtu"]),
+            missing_fields::<Model>(&["name", "comment"])
+        );
+    }
+
+    #[test]
+    fn test_missing_fields_unknown() {
+        assert_eq!(None, missing_fields::<HashMap<String, String>>(&["name"]));
+        assert_eq!(None, missing_fields::<Lenient<Model>>(&["name"]));
+
+        // No default value to probe with
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "kebab-case")]
+        #[allow(dead_code)]
+        struct Neighbor {
+            mac_address: MacAddress,
+            identity: String,
+        }
+
+        assert_eq!(None, missing_fields::<Neighbor>(&["mac-address"]));
+    }
+}
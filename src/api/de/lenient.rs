@@ -0,0 +1,402 @@
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{
+        self,
+        value::{BorrowedStrDeserializer, MapDeserializer, SeqDeserializer},
+        IntoDeserializer, MapAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+
+use super::{DeserializerError, Result, SentenceDeserializer};
+
+/// Name used to recognize `Lenient` in `SentenceDeserializer::deserialize_newtype_struct`
+pub(super) const TOKEN: &str = "$mikrotik_api::Lenient";
+
+/// Wrapper deserializing a reply into `T` while tolerating mismatches between `T` and the sentence:
+/// - properties unknown to `T` are skipped,
+/// - fields of `T` absent from the sentence are substituted with a default value (zero, false, empty string, ...) when possible.
+///
+/// Every tolerated mismatch is reported alongside the value. Useful when a single model has to deal with both v6 and v7 routers.
+#[derive(Debug)]
+pub struct Lenient<T> {
+    /// The deserialized value
+    pub value: T,
+
+    /// Properties sent by the router that are not fields of `T`
+    pub unknown_fields: Vec<String>,
+
+    /// Fields of `T` that were missing from the reply and were given a default value
+    pub defaulted_fields: Vec<String>,
+}
+
+impl<T> Lenient<T> {
+    /// Returns true if the reply matched `T` exactly
+    pub fn is_exact(&self) -> bool {
+        self.unknown_fields.is_empty() && self.defaulted_fields.is_empty()
+    }
+
+    /// Drops the report and returns the inner value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct LenientVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for LenientVisitor<T> {
+            type Value = Lenient<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sentence")
+            }
+
+            // Any other deserializer: no leniency, no report.
+            fn visit_newtype_struct<D>(
+                self,
+                deserializer: D,
+            ) -> std::result::Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Ok(Lenient {
+                    value: T::deserialize(deserializer)?,
+                    unknown_fields: vec![],
+                    defaulted_fields: vec![],
+                })
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut value = None;
+                let mut unknown_fields = vec![];
+                let mut defaulted_fields = vec![];
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "value" => value = Some(map.next_value()?),
+                        "unknown_fields" => unknown_fields = map.next_value()?,
+                        "defaulted_fields" => defaulted_fields = map.next_value()?,
+                        _ => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let value = value.ok_or_else(|| de::Error::missing_field("value"))?;
+
+                Ok(Lenient {
+                    value,
+                    unknown_fields,
+                    defaulted_fields,
+                })
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(TOKEN, LenientVisitor(PhantomData))
+    }
+}
+
+/// Mismatches collected while deserializing in lenient mode
+#[derive(Debug, Default)]
+pub(super) struct LenientReport {
+    pub unknown_fields: Vec<String>,
+    pub defaulted_fields: Vec<String>,
+}
+
+/// Lenient bookkeeping for a single struct
+pub(super) struct LenientFields {
+    pub expected: &'static [&'static str],
+    pub missing: Vec<&'static str>,
+    pub finished: bool,
+}
+
+impl LenientFields {
+    pub fn new(expected: &'static [&'static str]) -> Self {
+        Self {
+            expected,
+            missing: expected.to_vec(),
+            finished: false,
+        }
+    }
+
+    /// Marks `key` as seen, returns false if `T` has no such field
+    pub fn accept(&mut self, key: &str) -> bool {
+        self.missing.retain(|field| *field != key);
+
+        self.expected.contains(&key)
+    }
+}
+
+/// Feeds the value and the report collected by the deserializer to `Lenient`'s visitor
+pub(super) fn visit_lenient<'de, V>(
+    de: &mut SentenceDeserializer<'de>,
+    visitor: V,
+) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    de.lenient = Some(LenientReport::default());
+
+    visitor.visit_map(LenientAccess {
+        de,
+        report: None,
+        step: 0,
+    })
+}
+
+struct LenientAccess<'v, 'de: 'v> {
+    de: &'v mut SentenceDeserializer<'de>,
+    report: Option<LenientReport>,
+    step: u8,
+}
+
+impl<'de, 'v> MapAccess<'de> for LenientAccess<'v, 'de> {
+    type Error = DeserializerError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        let key = match self.step {
+            0 => "value",
+            1 => "unknown_fields",
+            2 => "defaulted_fields",
+            _ => return Ok(None),
+        };
+
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        self.step += 1;
+
+        match self.step {
+            1 => {
+                let value = seed.deserialize(&mut *self.de)?;
+                self.report = self.de.lenient.take();
+                Ok(value)
+            }
+
+            2 => {
+                let fields = self
+                    .report
+                    .as_mut()
+                    .map(|r| std::mem::take(&mut r.unknown_fields));
+                seed.deserialize(fields.unwrap_or_default().into_deserializer())
+            }
+
+            _ => {
+                let fields = self
+                    .report
+                    .as_mut()
+                    .map(|r| std::mem::take(&mut r.defaulted_fields));
+                seed.deserialize(fields.unwrap_or_default().into_deserializer())
+            }
+        }
+    }
+}
+
+/// Deserializer producing the default value of the requested type, when there is one.
+pub(super) struct DefaultValue<'a> {
+    /// Set when the requested type is an `Option`, which serde would have defaulted on its own
+    pub optional: &'a mut bool,
+}
+
+macro_rules! default_primitive {
+    ($($method:ident => $visit:ident($($value:expr)?),)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for DefaultValue<'a> {
+    type Error = DeserializerError;
+
+    forward_to_deserialize_any! {
+        char identifier enum
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(DeserializerError::custom(
+            "no default value for missing field",
+        ))
+    }
+
+    default_primitive! {
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0),
+        deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_i128 => visit_i128(0),
+        deserialize_u8 => visit_u8(0),
+        deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u128(0),
+        deserialize_f32 => visit_f32(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_str => visit_borrowed_str(""),
+        deserialize_string => visit_borrowed_str(""),
+        deserialize_bytes => visit_borrowed_bytes(&[]),
+        deserialize_byte_buf => visit_borrowed_bytes(&[]),
+        deserialize_unit => visit_unit(),
+        deserialize_ignored_any => visit_unit(),
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.optional = true;
+
+        visitor.visit_none()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqDeserializer::new(std::iter::empty::<u8>()))
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(MapDeserializer::new(std::iter::empty::<(u8, u8)>()))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::api::{de::deserialize_sentence, Response};
+
+    use super::Lenient;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Model {
+        name: String,
+        running: bool,
+        mtu: u16,
+        comment: Option<String>,
+    }
+
+    fn sentence(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_lenient_exact() {
+        let words = sentence(&["!re", "=name=ether1", "=running=true", "=mtu=1500", ""]);
+
+        let response = deserialize_sentence::<Lenient<Model>>(&words).unwrap();
+
+        let Response::Reply(lenient) = response else {
+            panic!("expected a reply");
+        };
+
+        assert!(lenient.is_exact());
+        assert_eq!("ether1", lenient.value.name);
+        assert_eq!(1500, lenient.value.mtu);
+    }
+
+    #[test]
+    fn test_lenient_unknown_and_missing() {
+        let words = sentence(&["!re", ".tag=42", "=name=ether1", "=l2mtu=1598", ""]);
+
+        let response = deserialize_sentence::<Lenient<Model>>(&words).unwrap();
+
+        let Response::Reply(lenient) = response else {
+            panic!("expected a reply");
+        };
+
+        assert_eq!(vec!["l2mtu"], lenient.unknown_fields);
+
+        let mut defaulted = lenient.defaulted_fields.clone();
+        defaulted.sort();
+        assert_eq!(vec!["mtu", "running"], defaulted);
+
+        assert_eq!("ether1", lenient.value.name);
+        assert!(!lenient.value.running);
+        assert_eq!(0, lenient.value.mtu);
+        assert!(lenient.value.comment.is_none());
+    }
+
+    #[test]
+    fn test_strict_missing_field() {
+        let words = sentence(&["!re", "=name=ether1", ""]);
+
+        assert!(deserialize_sentence::<Model>(&words).is_err());
+    }
+}
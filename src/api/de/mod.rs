@@ -1,46 +1,94 @@
 use std::{slice::Iter, str::FromStr};
 
 use serde::{
-    de::{self, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor},
+    de::{
+        self,
+        value::{BorrowedStrDeserializer, SeqDeserializer},
+        EnumAccess, IntoDeserializer, MapAccess, VariantAccess, Visitor,
+    },
     forward_to_deserialize_any, Deserializer,
 };
 
 use super::Response;
 
 mod error;
+mod fields;
+mod lenient;
+mod value;
 
 pub use error::DeserializerError;
+pub(crate) use fields::missing_fields;
+pub use lenient::Lenient;
+
+use lenient::{DefaultValue, LenientFields, LenientReport};
+use value::ValueDeserializer;
 
 type Result<T> = std::result::Result<T, error::DeserializerError>;
 
+/// `text` without its first character, normally the `=` of an attribute
+fn skip_first_char(text: &str) -> &str {
+    let mut chars = text.chars();
+    chars.next();
+    chars.as_str()
+}
+
 pub fn deserialize_sentence<T: de::DeserializeOwned>(sentence: &[String]) -> Result<Response<T>> {
-    let mut iterator = sentence.iter();
+    deserialize_sentence_borrowed(sentence)
+}
 
-    let mut deserializer = SentenceDeserializer::new(&mut iterator);
+/// Same as `deserialize_sentence`, along with the properties of `sentence` that `T` has no field for, and silently skipped.
+/// API attributes such as `.id` or `.section` are left out, as are properties of replies that are not structs
+pub(crate) fn deserialize_sentence_strict<T: de::DeserializeOwned>(
+    sentence: &[String],
+) -> Result<(Response<T>, Vec<String>)> {
+    let mut deserializer = SentenceDeserializer::new(sentence.iter());
+    deserializer.strict = Some(vec![]);
 
-    use serde::Deserialize;
+    let response = <Response<T> as de::Deserialize>::deserialize(&mut deserializer)?;
 
-    <Response<T>>::deserialize(&mut deserializer)
+    Ok((response, deserializer.strict.unwrap_or_default()))
+}
+
+/// Same as `deserialize_sentence`, for types borrowing from the sentence
+pub fn deserialize_sentence_borrowed<'de, T: de::Deserialize<'de>>(
+    sentence: &'de [String],
+) -> Result<Response<T>> {
+    let mut deserializer = SentenceDeserializer::new(sentence.iter());
+
+    <Response<T> as de::Deserialize>::deserialize(&mut deserializer)
 }
 
 pub struct SentenceDeserializer<'de> {
-    cursor: &'de mut Iter<'de, String>,
+    cursor: Iter<'de, String>,
 
     current_word: Option<&'de str>,
+
+    /// Whether the key of `current_word` was read, its value being next
+    value_pending: bool,
+
+    lenient: Option<LenientReport>,
+
+    /// Strict mode: properties skipped so far, and the key of the current word
+    strict: Option<Vec<String>>,
+    current_key: Option<&'de str>,
 }
 
 impl<'de> SentenceDeserializer<'de> {
-    pub fn new(iter: &'de mut Iter<'de, String>) -> Self {
+    pub fn new(iter: Iter<'de, String>) -> Self {
         //println!("{:?}", iter);
 
         SentenceDeserializer {
             cursor: iter,
             current_word: None,
+            value_pending: false,
+            lenient: None,
+            strict: None,
+            current_key: None,
         }
     }
 
-    pub fn inner(&self) -> &Iter<String> {
-        self.cursor
+    pub fn inner(&self) -> &Iter<'_, String> {
+        &self.cursor
     }
 }
 
@@ -55,72 +103,106 @@ impl<'de> SentenceDeserializer<'de> {
         Ok(next)
     }
 
+    /// Next part of the current word `=key=value`: the key, then the value, which may hold `=` itself
     fn word_part(&mut self) -> Result<&'de str> {
-        if let Some(text) = self.current_word.as_mut() {
-            let mut matches = text.match_indices('=');
+        let value_pending = std::mem::take(&mut self.value_pending);
 
-            let first = matches.next();
-            let second = matches.next();
+        let text = self
+            .current_word
+            .as_mut()
+            .ok_or(DeserializerError::MissingWord)?;
 
-            if let Some((i, _)) = second {
-                let split = text.split_at(i);
+        let mut matches = text.match_indices('=');
 
-                *text = split.1;
+        match (matches.next(), matches.next()) {
+            (Some(_), Some((i, _))) if !value_pending => {
+                let (key, value) = text.split_at(i);
 
-                Ok(&split.0[1..])
-            } else if first.is_some() {
-                let (empty, value) = text.split_at(0);
+                *text = value;
+                self.value_pending = true;
 
-                *text = empty;
-
-                Ok(&value[1..])
-            } else {
-                Err(DeserializerError::MissingWord)
+                Ok(skip_first_char(key))
             }
-        } else {
-            Err(DeserializerError::MissingWord)
+
+            (Some(_), _) => Ok(skip_first_char(std::mem::take(text))),
+
+            (None, _) => Err(DeserializerError::MissingWord),
         }
     }
 
-    fn parse_unsigned<T>(&mut self) -> Result<T>
+    /// Key of the current word, without consuming it
+    fn peek_key(&self) -> Result<&'de str> {
+        self.current_word
+            .and_then(|word| word.strip_prefix('='))
+            .and_then(|word| word.split('=').next())
+            .ok_or(DeserializerError::MissingKey)
+    }
+
+    fn parse_integer<T>(&mut self) -> Result<T>
     where
-        T: FromStr + From<u8>,
-        T::Err: std::error::Error + 'static,
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
     {
         let text = self.word_part()?;
 
         text.parse().map_err(|e| {
-            DeserializerError::BadPrimitiveValue(Box::<dyn std::error::Error>::from(e))
+            DeserializerError::BadPrimitiveValue(Box::<dyn std::error::Error + Send + Sync>::from(
+                e,
+            ))
         })
     }
 }
 
-impl<'de, 'api> Deserializer<'de> for &'api mut SentenceDeserializer<'de> {
+impl<'de> Deserializer<'de> for &mut SentenceDeserializer<'de> {
     type Error = DeserializerError;
 
     forward_to_deserialize_any! {
-        i8 i16 i32 i64 i128 u128 f32 f64 char
-        bytes byte_buf unit_struct newtype_struct tuple
-        tuple_struct seq
+        i8 i128 u128 f32 f64 char
+        bytes byte_buf unit_struct tuple
+        tuple_struct
     }
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    /// Without type hints, a whole sentence is a map and anything within is a string:
+    /// the router does not tell numbers or booleans apart from text.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        unimplemented!("deserialize_any");
+        match self.current_word {
+            // Value of an attribute, the key having been consumed already
+            Some(word) if word.starts_with('=') => self.deserialize_str(visitor),
+
+            _ => self.deserialize_map(visitor),
+        }
     }
 
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_map(StructVisitor { de: self })
+        let lenient = self.lenient.is_some().then(|| LenientFields::new(fields));
+
+        visitor.visit_map(StructVisitor {
+            de: self,
+            lenient,
+            defaulting: None,
+        })
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == lenient::TOKEN {
+            return lenient::visit_lenient(self, visitor);
+        }
+
+        visitor.visit_newtype_struct(self)
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -161,6 +243,7 @@ impl<'de, 'api> Deserializer<'de> for &'api mut SentenceDeserializer<'de> {
         V: Visitor<'de>,
     {
         self.current_word = Some(self.read_word()?);
+        self.value_pending = false;
 
         match self.current_word {
             Some("!done") => visitor.visit_enum("Done".into_deserializer()),
@@ -195,35 +278,63 @@ impl<'de, 'api> Deserializer<'de> for &'api mut SentenceDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        let skipped = self.current_key.take().filter(|key| !key.starts_with('.'));
+
+        if let (Some(unknown), Some(key)) = (self.strict.as_mut(), skipped) {
+            unknown.push(key.to_owned());
+        }
+
         self.deserialize_unit(visitor)
     }
 
+    /// Signal strengths, noise floors, time zone offsets, ...
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i16(self.parse_integer()?)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i32(self.parse_integer()?)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.parse_integer()?)
+    }
+
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.parse_unsigned()?)
+        visitor.visit_u64(self.parse_integer()?)
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u8(self.parse_unsigned()?)
+        visitor.visit_u8(self.parse_integer()?)
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u16(self.parse_unsigned()?)
+        visitor.visit_u16(self.parse_integer()?)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u32(self.parse_unsigned()?)
+        visitor.visit_u32(self.parse_integer()?)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
@@ -233,11 +344,30 @@ impl<'de, 'api> Deserializer<'de> for &'api mut SentenceDeserializer<'de> {
         visitor.visit_some(self)
     }
 
+    /// List values are sent by the router as comma-separated items, ie `=ports=8080,8443`
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let text = self.word_part()?;
+
+        let items = text
+            .split(',')
+            .filter(|item| !item.is_empty())
+            .map(ValueDeserializer);
+
+        visitor.visit_seq(SeqDeserializer::new(items))
+    }
+
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_map(StructVisitor { de: self })
+        visitor.visit_map(StructVisitor {
+            de: self,
+            lenient: None,
+            defaulting: None,
+        })
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
@@ -248,37 +378,38 @@ impl<'de, 'api> Deserializer<'de> for &'api mut SentenceDeserializer<'de> {
             "true" => visitor.visit_bool(true),
             "false" => visitor.visit_bool(false),
             e => Err(DeserializerError::BadPrimitiveValue(Box::<
-                dyn std::error::Error,
+                dyn std::error::Error + Send + Sync,
             >::from(e))),
         }
     }
 }
 
-struct SeqVisitor<'v, 'de: 'v> {
+struct StructVisitor<'v, 'de: 'v> {
     pub de: &'v mut SentenceDeserializer<'de>,
-}
 
-impl<'de, 'v> SeqAccess<'de> for SeqVisitor<'v, 'de> {
-    type Error = DeserializerError;
+    lenient: Option<LenientFields>,
+    defaulting: Option<&'static str>,
+}
 
-    fn next_element_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>>
+impl<'v, 'de: 'v> StructVisitor<'v, 'de> {
+    /// Lenient mode: once the sentence is over, yield fields that were not seen so they get a default value.
+    fn next_missing_key<K>(&mut self, seed: K) -> Result<Option<K::Value>>
     where
-        S: serde::de::DeserializeSeed<'de>,
+        K: serde::de::DeserializeSeed<'de>,
     {
-        self.de.current_word = Some(self.de.read_word()?);
+        let field = self.lenient.as_mut().and_then(|l| l.missing.pop());
 
-        if let Some("") = self.de.current_word {
-            return Ok(None);
-        }
+        self.defaulting = field;
 
-        seed.deserialize(&mut *self.de).map(Some)
+        match field {
+            Some(field) => seed
+                .deserialize(BorrowedStrDeserializer::new(field))
+                .map(Some),
+            None => Ok(None),
+        }
     }
 }
 
-struct StructVisitor<'v, 'de: 'v> {
-    pub de: &'v mut SentenceDeserializer<'de>,
-}
-
 impl<'de, 'v> MapAccess<'de> for StructVisitor<'v, 'de> {
     type Error = DeserializerError;
 
@@ -286,19 +417,60 @@ impl<'de, 'v> MapAccess<'de> for StructVisitor<'v, 'de> {
     where
         K: serde::de::DeserializeSeed<'de>,
     {
-        self.de.current_word = Some(self.de.read_word()?);
-
-        if let Some("") = self.de.current_word {
-            return Ok(None);
+        if let Some(LenientFields { finished: true, .. }) = self.lenient {
+            return self.next_missing_key(seed);
         }
 
-        seed.deserialize(&mut *self.de).map(Some)
+        loop {
+            self.de.current_word = Some(self.de.read_word()?);
+            self.de.value_pending = false;
+
+            if let Some("") = self.de.current_word {
+                if let Some(lenient) = self.lenient.as_mut() {
+                    lenient.finished = true;
+                    return self.next_missing_key(seed);
+                }
+
+                return Ok(None);
+            }
+
+            if let Some(lenient) = self.lenient.as_mut() {
+                let key = self.de.peek_key()?;
+
+                if !lenient.accept(key) {
+                    if let Some(report) = self.de.lenient.as_mut() {
+                        report.unknown_fields.push(key.to_string());
+                    }
+                    continue;
+                }
+            }
+
+            if self.de.strict.is_some() {
+                self.de.current_key = self.de.peek_key().ok();
+            }
+
+            return seed.deserialize(&mut *self.de).map(Some);
+        }
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: serde::de::DeserializeSeed<'de>,
     {
+        if let Some(field) = self.defaulting.take() {
+            let mut optional = false;
+
+            let value = seed.deserialize(DefaultValue {
+                optional: &mut optional,
+            })?;
+
+            if let (false, Some(report)) = (optional, self.de.lenient.as_mut()) {
+                report.defaulted_fields.push(field.to_string());
+            }
+
+            return Ok(value);
+        }
+
         seed.deserialize(&mut *self.de)
     }
 }
@@ -330,8 +502,9 @@ impl<'de, 'v> EnumAccess<'de> for EnumVisitor<'v, 'de> {
 impl<'de, 'v> VariantAccess<'de> for EnumVisitor<'v, 'de> {
     type Error = DeserializerError;
 
+    /// `!fatal`, whose message is not kept
     fn unit_variant(self) -> Result<()> {
-        todo!("EnumVisitor::unit_variant")
+        Ok(())
     }
 
     fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
@@ -355,3 +528,132 @@ impl<'de, 'v> VariantAccess<'de> for EnumVisitor<'v, 'de> {
         de::Deserializer::deserialize_struct(self.de, "", fields, visitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::{deserialize_sentence, deserialize_sentence_strict};
+    use crate::api::Response;
+
+    #[derive(Debug, Deserialize)]
+    struct Service {
+        name: String,
+        ports: Vec<u16>,
+        addresses: Option<Vec<String>>,
+    }
+
+    fn sentence(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_comma_separated_list() {
+        let words = sentence(&[
+            "!re",
+            "=name=www",
+            "=ports=8080,8443",
+            "=addresses=10.0.0.0/8,192.168.0.0/16",
+            "",
+        ]);
+
+        let Response::Reply(service) = deserialize_sentence::<Service>(&words).unwrap() else {
+            panic!("expected a reply");
+        };
+
+        assert_eq!("www", service.name);
+        assert_eq!(vec![8080, 8443], service.ports);
+        assert_eq!(
+            Some(vec!["10.0.0.0/8".to_string(), "192.168.0.0/16".to_string()]),
+            service.addresses
+        );
+    }
+
+    #[test]
+    fn test_empty_list() {
+        let words = sentence(&["!re", "=name=www", "=ports=", ""]);
+
+        let Response::Reply(service) = deserialize_sentence::<Service>(&words).unwrap() else {
+            panic!("expected a reply");
+        };
+
+        assert!(service.ports.is_empty());
+        assert!(service.addresses.is_none());
+    }
+
+    #[test]
+    fn test_json_value() {
+        let words = sentence(&["!re", ".tag=12", "=.id=*1", "=name=www", "=disabled=", ""]);
+
+        let Response::Reply(value) = deserialize_sentence::<serde_json::Value>(&words).unwrap()
+        else {
+            panic!("expected a reply");
+        };
+
+        assert_eq!(
+            serde_json::json!({ ".id": "*1", "name": "www", "disabled": "" }),
+            value
+        );
+    }
+
+    #[test]
+    fn test_value_holding_equal_sign() {
+        let words = sentence(&["!re", "=name=a=b", "=ports=80", "=addresses==,=", ""]);
+
+        let Response::Reply(service) = deserialize_sentence::<Service>(&words).unwrap() else {
+            panic!("expected a reply");
+        };
+
+        assert_eq!("a=b", service.name);
+        assert_eq!(
+            Some(vec!["=".to_string(), "=".to_string()]),
+            service.addresses
+        );
+    }
+
+    #[test]
+    fn test_multibyte_first_char() {
+        // Found by the `deserialize` fuzz target: words not starting with `=` used to be sliced mid-character
+        let words = sentence(&["!re", "é=name=www", "é=x", ""]);
+
+        // Garbage in, but an error rather than a panic
+        let _ = deserialize_sentence::<serde_json::Value>(&words);
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "kebab-case")]
+        #[allow(dead_code)]
+        struct Port {
+            name: String,
+            #[serde(alias = "routing-mark")]
+            routing_table: Option<String>,
+        }
+
+        let words = sentence(&[
+            "!re",
+            ".tag=2",
+            "=.id=*1",
+            "=name=ether1",
+            "=routing-mark=main",
+            "=l2mtu=1598",
+            "=fast-path=true",
+            "",
+        ]);
+
+        let unknown_fields =
+            |words: &[String]| deserialize_sentence_strict::<Port>(words).unwrap().1;
+
+        assert_eq!(vec!["l2mtu", "fast-path"], unknown_fields(&words));
+        assert!(unknown_fields(&sentence(&["!re", "=name=ether1", ""])).is_empty());
+
+        // Maps take every property
+        let (reply, unknown) =
+            deserialize_sentence_strict::<HashMap<String, String>>(&words).unwrap();
+        assert!(unknown.is_empty());
+        assert!(matches!(reply, Response::Reply(map) if map["l2mtu"] == "1598"));
+    }
+}
@@ -0,0 +1,74 @@
+use super::{
+    call::EmptyCall, entity::done_or_trap, error::Error, model::DhcpClient, Authenticated,
+    MikrotikAPI,
+};
+
+impl MikrotikAPI<Authenticated> {
+    /// List the DHCP clients of the router along with their lease: status, address, gateway, time left, ...
+    pub async fn dhcp_clients(&mut self) -> Result<Vec<DhcpClient>, Error> {
+        self.list().await
+    }
+
+    /// Get the DHCP client running on `interface`, if any
+    pub async fn dhcp_client(&mut self, interface: &str) -> Result<Option<DhcpClient>, Error> {
+        Ok(self
+            .list_where::<DhcpClient>(&[("interface", interface)])
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Releases the lease of the DHCP client `id` and asks for a new one, ie to bounce a WAN uplink
+    pub async fn renew_dhcp_client(&mut self, id: &str) -> Result<(), Error> {
+        let response = self
+            .do_call(
+                "/ip/dhcp-client/renew",
+                Some(&[("numbers", id)]),
+                EmptyCall::new(),
+                None,
+            )
+            .await?
+            .await?;
+
+        done_or_trap(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{de::deserialize_sentence, model::Response};
+
+    use super::DhcpClient;
+
+    #[test]
+    fn test_dhcp_client() {
+        let words: Vec<String> = [
+            "!re",
+            "=.id=*1",
+            "=interface=ether1",
+            "=add-default-route=yes",
+            "=use-peer-dns=true",
+            "=status=bound",
+            "=address=203.0.113.17/24",
+            "=gateway=203.0.113.1",
+            "=dhcp-server=203.0.113.1",
+            "=expires-after=23h59m12s",
+            "=invalid=false",
+            "=dynamic=false",
+            "=disabled=false",
+            "",
+        ]
+        .iter()
+        .map(|w| w.to_string())
+        .collect();
+
+        let client = match deserialize_sentence::<DhcpClient>(&words).unwrap() {
+            Response::Reply(client) => client,
+            other => panic!("expected a reply, got {:?}", other),
+        };
+
+        assert!(client.is_bound());
+        assert_eq!(Some("203.0.113.1"), client.gateway.as_deref());
+        assert_eq!(Some("23h59m12s"), client.expires_after.as_deref());
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use super::{error::Error, Authenticated, MikrotikAPI};
+
+/// Declarative description of the expected state of a router, checked by `MikrotikAPI::drift`.
+///
+/// Maps menu paths to the items they should hold, ie in JSON:
+/// `{"/ip/address": {"key": "address", "items": [{"address": "192.168.88.1/24", "interface": "bridge"}]}}`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct DriftSpec {
+    /// Expected items, by menu path
+    pub tables: BTreeMap<String, TableSpec>,
+}
+
+/// Expected items of a single menu
+#[derive(Debug, Clone, Deserialize)]
+pub struct TableSpec {
+    /// Property identifying an item, `name` by default
+    #[serde(default = "default_key")]
+    pub key: String,
+
+    /// Whether items of the router missing from the spec are reported, dynamic items excepted
+    #[serde(default)]
+    pub exclusive: bool,
+
+    /// Expected items. Only the properties listed are compared, values being strings, numbers or booleans
+    #[serde(default)]
+    pub items: Vec<BTreeMap<String, SpecValue>>,
+}
+
+fn default_key() -> String {
+    "name".to_owned()
+}
+
+/// Value of a property in a `DriftSpec`, compared to the router's as a string
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct SpecValue(pub String);
+
+impl<'de> Deserialize<'de> for SpecValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ScalarVisitor;
+
+        impl<'de> de::Visitor<'de> for ScalarVisitor {
+            type Value = SpecValue;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a string, a number or a boolean")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(SpecValue(v.to_owned()))
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+                Ok(SpecValue(v.to_string()))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(SpecValue(v.to_string()))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(SpecValue(v.to_string()))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                Ok(SpecValue(v.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(ScalarVisitor)
+    }
+}
+
+/// Differences between a router and a `DriftSpec`, see `MikrotikAPI::drift`
+#[derive(Debug, Default, Serialize)]
+pub struct DriftReport {
+    /// Every difference found, by menu path then key
+    pub drifts: Vec<Drift>,
+}
+
+impl DriftReport {
+    /// Returns true if the router matches the spec
+    pub fn is_clean(&self) -> bool {
+        self.drifts.is_empty()
+    }
+}
+
+/// A difference between an item of the router and the spec
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Drift {
+    /// Menu path, ie `/ip/address`
+    pub path: String,
+
+    /// Value of the identifying property of the item
+    pub key: String,
+
+    /// What differs
+    pub kind: DriftKind,
+}
+
+/// Kinds of `Drift`
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum DriftKind {
+    /// The item is in the spec but not on the router
+    Missing,
+
+    /// The item is on the router but not in the spec, only reported for exclusive tables
+    Extra,
+
+    /// The item exists on both sides with different properties
+    Mismatched {
+        /// Properties whose value differs
+        attributes: Vec<AttributeDrift>,
+    },
+}
+
+/// A property whose value differs from the spec
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct AttributeDrift {
+    /// Property name
+    pub name: String,
+
+    /// Value in the spec
+    pub expected: String,
+
+    /// Value on the router, `None` if it is unset
+    pub actual: Option<String>,
+}
+
+type Item = HashMap<String, String>;
+
+fn compare(path: &str, spec: &TableSpec, actual: &[Item]) -> Vec<Drift> {
+    let mut drifts = vec![];
+
+    let drift = |key: &str, kind| Drift {
+        path: path.to_owned(),
+        key: key.to_owned(),
+        kind,
+    };
+
+    for expected in &spec.items {
+        let key = match expected.get(&spec.key) {
+            Some(SpecValue(key)) => key,
+            None => continue,
+        };
+
+        let item = match actual.iter().find(|item| item.get(&spec.key) == Some(key)) {
+            Some(item) => item,
+            None => {
+                drifts.push(drift(key, DriftKind::Missing));
+                continue;
+            }
+        };
+
+        let attributes: Vec<_> = expected
+            .iter()
+            .filter(|(name, SpecValue(value))| item.get(*name) != Some(value))
+            .map(|(name, SpecValue(value))| AttributeDrift {
+                name: name.clone(),
+                expected: value.clone(),
+                actual: item.get(name).cloned(),
+            })
+            .collect();
+
+        if !attributes.is_empty() {
+            drifts.push(drift(key, DriftKind::Mismatched { attributes }));
+        }
+    }
+
+    if spec.exclusive {
+        for item in actual {
+            let key = match item.get(&spec.key) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let dynamic = item.get("dynamic").map(String::as_str) == Some("true");
+
+            let known = spec
+                .items
+                .iter()
+                .any(|expected| expected.get(&spec.key).map(|v| &v.0) == Some(key));
+
+            if !dynamic && !known {
+                drifts.push(drift(key, DriftKind::Extra));
+            }
+        }
+    }
+
+    drifts
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Compares the state of the router with `spec`: missing, extra and mismatched items of every menu it lists.
+    /// Only reads from the router, nothing is changed.
+    pub async fn drift(&mut self, spec: &DriftSpec) -> Result<DriftReport, Error> {
+        let mut drifts = vec![];
+
+        for (path, table) in &spec.tables {
+            let mut proplist: Vec<&str> = table
+                .items
+                .iter()
+                .flat_map(|item| item.keys().map(String::as_str))
+                .chain([table.key.as_str(), "dynamic"])
+                .collect();
+
+            proplist.sort_unstable();
+            proplist.dedup();
+
+            let proplist = proplist.join(",");
+
+            let actual = self
+                .generic_array_call::<Item>(
+                    &format!("{}/print", path),
+                    Some(&[("=.proplist", &proplist)]),
+                )
+                .await?;
+
+            drifts.extend(compare(path, table, &actual));
+        }
+
+        Ok(DriftReport { drifts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{compare, AttributeDrift, DriftKind, DriftSpec};
+
+    fn item(properties: &[(&str, &str)]) -> HashMap<String, String> {
+        properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_compare() {
+        let spec: DriftSpec = serde_json::from_str(
+            r#"{
+                "/interface/vlan": {
+                    "exclusive": true,
+                    "items": [
+                        {"name": "vlan10", "vlan-id": 10, "interface": "bridge"},
+                        {"name": "vlan20", "vlan-id": 20, "disabled": false}
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let actual = [
+            item(&[
+                ("name", "vlan10"),
+                ("vlan-id", "11"),
+                ("interface", "bridge"),
+            ]),
+            item(&[("name", "vlan30"), ("vlan-id", "30"), ("dynamic", "false")]),
+            item(&[("name", "vlan40"), ("vlan-id", "40"), ("dynamic", "true")]),
+        ];
+
+        let table = &spec.tables["/interface/vlan"];
+        let drifts = compare("/interface/vlan", table, &actual);
+
+        let kinds: Vec<_> = drifts.iter().map(|d| (d.key.as_str(), &d.kind)).collect();
+
+        assert_eq!(
+            vec![
+                (
+                    "vlan10",
+                    &DriftKind::Mismatched {
+                        attributes: vec![AttributeDrift {
+                            name: "vlan-id".into(),
+                            expected: "10".into(),
+                            actual: Some("11".into()),
+                        }]
+                    }
+                ),
+                ("vlan20", &DriftKind::Missing),
+                ("vlan30", &DriftKind::Extra),
+            ],
+            kinds
+        );
+    }
+}
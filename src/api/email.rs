@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use super::{
+    call::EmptyCall,
+    error::Error,
+    model::{EmailServer, EmailSettings, Response, TrapCategory, FATAL},
+    ser, Authenticated, MikrotikAPI,
+};
+
+/// Properties renamed by RouterOS 7.12, as `(new name, old name)`
+const RENAMED: [(&str, &str); 2] = [("server", "address"), ("tls", "start-tls")];
+
+/// Error for the reply to `/tool/e-mail/send`: a `!trap` about the command itself stays an `Error::Remote`,
+/// any other one being the router failing to deliver the e-mail
+fn sent_or_trap(response: Response<()>) -> Result<(), Error> {
+    match response {
+        Response::Done | Response::Reply(_) => Ok(()),
+
+        Response::Trap {
+            category:
+                Some(TrapCategory::MissingItemOrCommand) | Some(TrapCategory::ArgumentValueFailure),
+            message,
+        } => Err(Error::Remote(message)),
+
+        Response::Trap { message, .. } => Err(Error::Email(message)),
+
+        Response::Fatal => Err(Error::ConnectionLost(FATAL.to_owned())),
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Get the SMTP server the router sends mail through, see `send_email`
+    pub async fn email_server(&mut self) -> Result<EmailServer, Error> {
+        self.generic_oneshot_call("/tool/e-mail/print", None).await
+    }
+
+    /// Change the SMTP server the router sends mail through, whatever names the version of RouterOS gives its properties
+    pub async fn set_email_server(&mut self, settings: &EmailSettings) -> Result<(), Error> {
+        let current = self
+            .generic_oneshot_call::<HashMap<String, String>>("/tool/e-mail/print", None)
+            .await?;
+
+        let mut attributes = ser::to_attributes(settings)?;
+
+        for (key, _) in attributes.iter_mut() {
+            let old_name = RENAMED
+                .iter()
+                .find(|(new, old)| key == new && current.contains_key(*old));
+
+            if let Some((_, old)) = old_name {
+                *key = old.to_string();
+            }
+        }
+
+        let attributes: Vec<(&str, &str)> = attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+
+        self.empty_call("/tool/e-mail/set", Some(&attributes)).await
+    }
+
+    /// Has the router send an e-mail through its SMTP server, ie for alerts raised by a monitoring workflow.
+    /// Fails with `Error::Email` when the router cannot deliver it
+    pub async fn send_email(&mut self, to: &str, subject: &str, body: &str) -> Result<(), Error> {
+        let response = self
+            .do_call(
+                "/tool/e-mail/send",
+                Some(&[("to", to), ("subject", subject), ("body", body)]),
+                EmptyCall::new(),
+                None,
+            )
+            .await?
+            .await?;
+
+        sent_or_trap(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::{error::Error, model::EmailSettings},
+        mock::{MockReply, MockRouter},
+    };
+
+    #[tokio::test]
+    async fn test_send_email() {
+        let router = MockRouter::new()
+            .on_query(
+                "/tool/e-mail/send",
+                &["=to=noc@example.com", "=subject=ether1 down"],
+                MockReply::new(),
+            )
+            .on_query(
+                "/tool/e-mail/send",
+                &["=to=unreachable@example.com"],
+                MockReply::new().trap_with_category(4, "could not connect to server"),
+            )
+            .on_query(
+                "/tool/e-mail/send",
+                &["=to=nobody"],
+                MockReply::new().trap_with_category(1, "invalid value for argument to"),
+            );
+
+        let mut api = router.connect().await.unwrap();
+
+        api.send_email("noc@example.com", "ether1 down", "since 14:03")
+            .await
+            .unwrap();
+
+        let smtp = api.send_email("unreachable@example.com", "test", "").await;
+        assert!(matches!(smtp, Err(Error::Email(msg)) if msg == "could not connect to server"));
+
+        let argument = api.send_email("nobody", "test", "").await;
+        assert!(matches!(argument, Err(Error::Remote(_))));
+    }
+
+    #[tokio::test]
+    async fn test_set_email_server_v6() {
+        let print = MockReply::new().re(&[
+            ("address", "0.0.0.0"),
+            ("port", "25"),
+            ("start-tls", "no"),
+            ("from", "<>"),
+        ]);
+
+        let router = MockRouter::new()
+            .on("/tool/e-mail/print", print)
+            .on(
+                "/tool/e-mail/set",
+                MockReply::new().trap_with_category(1, "unknown parameter"),
+            )
+            .on_query(
+                "/tool/e-mail/set",
+                &["=address=smtp.example.com", "=port=587", "=start-tls=yes"],
+                MockReply::new(),
+            );
+
+        let mut api = router.connect().await.unwrap();
+
+        let server = api.email_server().await.unwrap();
+        assert_eq!(Some("0.0.0.0"), server.server.as_deref());
+        assert_eq!(Some("no"), server.tls.as_deref());
+
+        let settings = EmailSettings {
+            server: Some("smtp.example.com".into()),
+            port: Some(587),
+            tls: Some("yes".into()),
+            password: Some("hunter2".into()),
+            ..Default::default()
+        };
+
+        api.set_email_server(&settings).await.unwrap();
+
+        assert!(!format!("{:?}", settings).contains("hunter2"));
+    }
+}
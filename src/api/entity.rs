@@ -0,0 +1,336 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(feature = "ethernet")]
+use super::model::Bonding;
+#[cfg(feature = "wireless")]
+use super::model::WifiInterface;
+#[cfg(feature = "dhcp")]
+use super::model::{DhcpClient, DhcpLease};
+
+use super::{
+    call::EmptyCall,
+    error::Error,
+    model::{ArpEntry, BridgePort, IpAddress, IpService, Response, Route, Vlan, FATAL},
+    ser, Authenticated, MikrotikAPI,
+};
+
+/// A type mapped onto a RouterOS table (a menu supporting `print`, `add`, `remove`, ...).
+///
+/// Implementing this trait makes the type usable with the generic `list`, `add` and `remove` helpers.
+pub trait RosEntity: DeserializeOwned + Serialize + Debug + Send + Sync + 'static {
+    /// Menu path, ie `/ip/address`
+    const PATH: &'static str;
+
+    /// Properties to request with `.proplist` when listing, `None` to get them all
+    const PROPLIST: Option<&'static [&'static str]> = None;
+
+    /// Commands the menu supports, reported by `entity_registry`
+    const VERBS: &'static [&'static str] = &["print", "add", "set", "remove"];
+
+    /// Oldest RouterOS version having the menu, ie `7.1`, `None` if it is found in every supported version
+    const MIN_VERSION: Option<&'static str> = None;
+
+    /// `.id` of the item, `None` if it is yet to be added
+    fn id(&self) -> Option<String>;
+}
+
+/// Description of a typed endpoint, as listed by `entity_registry`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EntityInfo {
+    /// Menu path, ie `/ip/address`
+    pub path: &'static str,
+
+    /// Commands the menu supports, ie `print` and `set`
+    pub verbs: &'static [&'static str],
+
+    /// Name of the model type, ie `IpAddress`
+    pub model: &'static str,
+
+    /// Oldest RouterOS version having the menu, `None` if it is found in every supported version
+    pub min_version: Option<&'static str>,
+}
+
+impl EntityInfo {
+    /// Description of the endpoint `T` is mapped onto
+    pub fn of<T: RosEntity>() -> Self {
+        let model = std::any::type_name::<T>();
+
+        EntityInfo {
+            path: T::PATH,
+            verbs: T::VERBS,
+            model: model.rsplit("::").next().unwrap_or(model),
+            min_version: T::MIN_VERSION,
+        }
+    }
+}
+
+/// Typed endpoints compiled into the crate, depending on the enabled features, ie for a GUI to build its menus from
+pub fn entity_registry() -> Vec<EntityInfo> {
+    let mut registry = Vec::new();
+
+    registry.extend([
+        EntityInfo::of::<IpAddress>(),
+        EntityInfo::of::<Route>(),
+        EntityInfo::of::<IpService>(),
+        EntityInfo::of::<ArpEntry>(),
+        EntityInfo::of::<BridgePort>(),
+        EntityInfo::of::<Vlan>(),
+    ]);
+
+    #[cfg(feature = "dhcp")]
+    registry.extend([
+        EntityInfo::of::<DhcpLease>(),
+        EntityInfo::of::<DhcpClient>(),
+    ]);
+
+    #[cfg(feature = "ethernet")]
+    registry.push(EntityInfo::of::<Bonding>());
+
+    #[cfg(feature = "wireless")]
+    registry.push(EntityInfo::of::<WifiInterface>());
+
+    registry
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Lists all items of the table `T` is mapped onto
+    pub async fn list<T: RosEntity>(&mut self) -> Result<Vec<T>, Error> {
+        self.list_where(&[]).await
+    }
+
+    /// Lists the items of the table `T` is mapped onto whose properties equal the given values, ie `&[("server", "dhcp1")]`.
+    /// Filtering is done by the router, through query words.
+    pub async fn list_where<T: RosEntity>(
+        &mut self,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>, Error> {
+        let command = format!("{}/print", T::PATH);
+
+        let proplist = T::PROPLIST.map(|props| props.join(","));
+
+        let query: Vec<(String, &str)> = query
+            .iter()
+            .map(|(key, value)| (format!("?{}", key), *value))
+            .collect();
+
+        let mut attributes: Vec<(&str, &str)> = query
+            .iter()
+            .map(|(key, value)| (key.as_str(), *value))
+            .collect();
+
+        if let Some(list) = proplist.as_deref() {
+            attributes.push(("=.proplist", list));
+        }
+
+        self.generic_array_call(&command, Some(&attributes)).await
+    }
+
+    /// Fetches the properties `props` of the items `ids` of the menu `path` (ie `/interface`) in a single `print`,
+    /// by id. Items the router does not know about are missing from the result.
+    pub async fn get_props(
+        &mut self,
+        path: &str,
+        ids: &[&str],
+        props: &[&str],
+    ) -> Result<HashMap<String, HashMap<String, String>>, Error> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let command = format!("{}/print", path);
+
+        let proplist = props
+            .iter()
+            .copied()
+            .chain(Some(".id").filter(|id| !props.contains(id)))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut attributes = id_set_query(ids);
+        attributes.push(("=.proplist", &proplist));
+
+        let items = self
+            .generic_array_call::<HashMap<String, String>>(&command, Some(&attributes))
+            .await?;
+
+        let keep_id = props.contains(&".id");
+
+        Ok(items
+            .into_iter()
+            .filter_map(|mut item| {
+                let id = match keep_id {
+                    true => item.get(".id").cloned(),
+                    false => item.remove(".id"),
+                }?;
+
+                Some((id, item))
+            })
+            .collect())
+    }
+
+    /// Adds `item` to the table `T` is mapped onto
+    pub async fn add<T: RosEntity>(&mut self, item: &T) -> Result<(), Error> {
+        let command = format!("{}/add", T::PATH);
+
+        let attributes = ser::to_attributes(item)?;
+
+        let attributes: Vec<(&str, &str)> = attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+
+        let response = self
+            .do_call(&command, Some(&attributes), EmptyCall::new(), None)
+            .await?
+            .await?;
+
+        done_or_trap(response)
+    }
+
+    /// Applies `changes` to the item identified by `id` in the table `T` is mapped onto.
+    /// `changes` may be a `T` or any struct holding a subset of its properties.
+    pub async fn set<T: RosEntity, C: Serialize>(
+        &mut self,
+        id: &str,
+        changes: &C,
+    ) -> Result<(), Error> {
+        let command = format!("{}/set", T::PATH);
+
+        let mut attributes = ser::to_attributes(changes)?;
+
+        attributes.insert(0, ("numbers".to_owned(), id.to_owned()));
+
+        let attributes: Vec<(&str, &str)> = attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+
+        let response = self
+            .do_call(&command, Some(&attributes), EmptyCall::new(), None)
+            .await?
+            .await?;
+
+        done_or_trap(response)
+    }
+
+    /// Removes the item identified by `id` from the table `T` is mapped onto
+    pub async fn remove<T: RosEntity>(&mut self, id: &str) -> Result<(), Error> {
+        let command = format!("{}/remove", T::PATH);
+
+        let response = self
+            .do_call(&command, Some(&[("numbers", id)]), EmptyCall::new(), None)
+            .await?
+            .await?;
+
+        done_or_trap(response)
+    }
+}
+
+pub(crate) fn done_or_trap(response: Response<()>) -> Result<(), Error> {
+    use Response::*;
+    match response {
+        Done | Reply(_) => Ok(()),
+
+        Trap { message, .. } => Err(Error::Remote(message)),
+
+        Fatal => Err(Error::ConnectionLost(FATAL.to_owned())),
+    }
+}
+
+/// Query words matching any of `ids`: n `?.id` words need n - 1 `or` operations
+fn id_set_query<'a>(ids: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    let mut query: Vec<(&str, &str)> = ids.iter().map(|id| ("?.id", *id)).collect();
+
+    for _ in 1..ids.len() {
+        query.push(("?#|", ""));
+    }
+
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{entity_registry, id_set_query, EntityInfo};
+    use crate::{
+        api::model::{IpAddress, IpService},
+        mock::{MockReply, MockRouter},
+    };
+
+    #[test]
+    fn test_entity_registry() {
+        let registry = entity_registry();
+
+        let address = registry
+            .iter()
+            .find(|entity| entity.path == "/ip/address")
+            .unwrap();
+
+        assert_eq!("IpAddress", address.model);
+        assert_eq!(&["print", "add", "set", "remove"], address.verbs);
+
+        assert_eq!(&["print", "set"], EntityInfo::of::<IpService>().verbs);
+
+        assert_eq!(None, address.min_version);
+
+        #[cfg(feature = "wireless")]
+        assert_eq!(
+            Some("7.13"),
+            EntityInfo::of::<crate::api::model::WifiInterface>().min_version
+        );
+
+        let paths: HashSet<_> = registry.iter().map(|entity| entity.path).collect();
+        assert_eq!(registry.len(), paths.len());
+    }
+
+    #[test]
+    fn test_id_set_query() {
+        assert_eq!(vec![("?.id", "*1")], id_set_query(&["*1"]));
+
+        assert_eq!(
+            vec![
+                ("?.id", "*1"),
+                ("?.id", "*2"),
+                ("?.id", "*A"),
+                ("?#|", ""),
+                ("?#|", "")
+            ],
+            id_set_query(&["*1", "*2", "*A"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_without_id() {
+        let router = MockRouter::new().on("/ip/address/add", MockReply::new().ret("*A"));
+        let received = router.received();
+
+        let mut api = router.connect().await.unwrap();
+
+        let address = IpAddress {
+            id: None,
+            address: "192.168.88.1/24".into(),
+            network: None,
+            interface: "bridge".into(),
+            actual_interface: None,
+            invalid: false,
+            dynamic: false,
+            disabled: false,
+            comment: None,
+        };
+
+        api.add(&address).await.unwrap();
+
+        let add = received
+            .sentences()
+            .into_iter()
+            .find(|sentence| sentence[0] == "/ip/address/add")
+            .unwrap();
+
+        assert!(add.contains(&"=address=192.168.88.1/24".to_owned()));
+        assert!(!add.iter().any(|word| word.starts_with("=.id=")));
+    }
+}
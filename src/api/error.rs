@@ -1,12 +1,74 @@
 use std::fmt::Debug;
 use std::io;
 
+use super::{de::DeserializerError, ser::SerializerError};
+
+/// Errors returned by the library
 #[derive(Debug)]
 pub enum Error {
+    /// Not enough data to read a full sentence
     Incomplete,
+
+    /// Connection was closed by the router
     EndOfStream,
+
+    /// The router sent bytes that do not form a valid sentence, holding what is wrong with them
+    Malformed(String),
+
+    /// The connection is unusable, calls still waiting for a reply having been failed, holding the reason
+    ConnectionLost(String),
+
+    /// The router refused the session, its limit of API connections being reached, holding its message
+    ConnectionLimit(String),
+
+    /// Router replied with a `!trap`, holding its message
     Remote(String),
+
+    /// Call was cancelled because its deadline passed
+    DeadlineExceeded,
+
+    /// Call was cancelled through the `CancellationToken` of its `CallOptions`
+    Cancelled,
+
+    /// Command was not sent because the `Policy` of the connection denies it, holding the command
+    Denied(String),
+
+    /// Command was not sent because one of its arguments is invalid, holding the reason
+    InvalidArgument(String),
+
+    /// No credentials were found for a router, holding its id, see `SecretsSource`
+    MissingCredentials(String),
+
+    /// A snapshot file could not be loaded, holding the reason, see `SnapshotFile`
+    BadSnapshot(String),
+
+    /// Command was not sent because its `.proplist` leaves out fields the reply type requires
+    ProplistMismatch {
+        /// Required fields absent from the `.proplist`
+        missing: Vec<String>,
+    },
+
+    /// A reply of a strict call holds properties its type has no field for, see `CallOptions::strict`
+    UnknownFields {
+        /// Properties of the reply unknown to its type
+        unknown: Vec<String>,
+    },
+
+    /// Call was failed because the replies buffered by the connection exceeded its `MemoryBudget`
+    MemoryBudgetExceeded,
+
+    /// The router could not send an e-mail: SMTP server unreachable, authentication or TLS failure, ...
+    /// Holding its message
+    Email(String),
+
+    /// I/O error on the connection
     Io(io::Error),
+
+    /// Failed to turn a value into command attributes
+    Serialize(SerializerError),
+
+    /// Failed to turn a reply into a value
+    Deserialize(DeserializerError),
 }
 
 impl From<io::Error> for Error {
@@ -15,6 +77,18 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<SerializerError> for Error {
+    fn from(e: SerializerError) -> Self {
+        Error::Serialize(e)
+    }
+}
+
+impl From<DeserializerError> for Error {
+    fn from(e: DeserializerError) -> Self {
+        Error::Deserialize(e)
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use Error::*;
@@ -23,9 +97,45 @@ impl std::fmt::Display for Error {
 
             EndOfStream => f.write_str("reached EOF"),
 
+            Malformed(reason) => write!(f, "malformed sentence: {}", reason),
+
+            ConnectionLost(reason) => write!(f, "connection lost: {}", reason),
+
+            ConnectionLimit(msg) => write!(f, "connection limit reached: {}", msg),
+
             Remote(msg) => write!(f, "error from router: {}", msg),
 
+            DeadlineExceeded => f.write_str("deadline exceeded, command was cancelled"),
+
+            Cancelled => f.write_str("command was cancelled"),
+
+            Denied(command) => write!(f, "command denied by policy: {}", command),
+
+            InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+
+            MissingCredentials(router_id) => write!(f, "no credentials for router: {}", router_id),
+
+            BadSnapshot(reason) => write!(f, "bad snapshot file: {}", reason),
+
+            ProplistMismatch { missing } => write!(
+                f,
+                ".proplist lacks the required fields: {}",
+                missing.join(", ")
+            ),
+
+            UnknownFields { unknown } => {
+                write!(f, "reply holds unknown properties: {}", unknown.join(", "))
+            }
+
+            MemoryBudgetExceeded => f.write_str("memory budget of the connection exceeded"),
+
+            Email(msg) => write!(f, "e-mail not sent: {}", msg),
+
             Io(e) => std::fmt::Display::fmt(&e, f),
+
+            Serialize(e) => write!(f, "failed to serialize attributes: {}", e),
+
+            Deserialize(e) => write!(f, "failed to deserialize reply: {}", e),
         }
     }
 }
@@ -0,0 +1,98 @@
+use std::borrow::Cow;
+
+/// Decodes the escapes RouterOS uses in exports, scripts and some log messages, ie `\"` and `\\`, or `\D0\9F` for
+/// the bytes of non-ASCII characters, so that values such as comments display as typed.
+///
+/// Values sent over the API are not escaped: only use it on values known to hold escapes. Bytes that do not form
+/// valid UTF-8 are decoded as Latin-1, like words of replies are, and unknown escapes are left as is.
+pub fn unescape(value: &str) -> Cow<'_, str> {
+    if !value.contains('\\') {
+        return Cow::Borrowed(value);
+    }
+
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('\\') {
+        bytes.extend_from_slice(&rest.as_bytes()[..start]);
+        rest = &rest[start + 1..];
+
+        let hex = rest
+            .get(..2)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+
+        if let Some(byte) = hex {
+            bytes.push(byte);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let mut chars = rest.chars();
+
+        let byte = match chars.next() {
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('a') => 0x07,
+            Some('b') => 0x08,
+            Some('f') => 0x0C,
+            Some('v') => 0x0B,
+            Some('_') => b' ',
+            Some(c @ ('"' | '\\' | '$' | '?')) => c as u8,
+
+            // Unknown escape or trailing backslash
+            _ => {
+                bytes.push(b'\\');
+                continue;
+            }
+        };
+
+        bytes.push(byte);
+        rest = chars.as_str();
+    }
+
+    bytes.extend_from_slice(rest.as_bytes());
+
+    match String::from_utf8(bytes) {
+        Ok(value) => Cow::Owned(value),
+        Err(e) => Cow::Owned(e.into_bytes().into_iter().map(char::from).collect()),
+    }
+}
+
+/// Bytes of a value that was not valid UTF-8 and got decoded as Latin-1, ie to decode it with the code page it was
+/// typed in. `None` if the value holds chars above U+00FF, so could not come from such a decoding
+pub fn latin1_bytes(value: &str) -> Option<Vec<u8>> {
+    value.chars().map(|c| u8::try_from(c).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{latin1_bytes, unescape};
+
+    #[test]
+    fn test_unescape() {
+        assert!(matches!(unescape("uplink"), Cow::Borrowed("uplink")));
+
+        assert_eq!("say \"hi\"\tto C:\\", unescape(r#"say \"hi\"\tto C:\\"#));
+        assert_eq!(
+            "Роутер office",
+            unescape(r"\D0\A0\D0\BE\D1\83\D1\82\D0\B5\D1\80\_office")
+        );
+        assert_eq!("cost $5?", unescape(r"cost \$5\?"));
+
+        // Not UTF-8: kept as Latin-1
+        assert_eq!("caf\u{e9}", unescape(r"caf\E9"));
+
+        // Unknown escapes and a trailing backslash are left as is
+        assert_eq!(r"\q\", unescape(r"\q\"));
+    }
+
+    #[test]
+    fn test_latin1_bytes() {
+        assert_eq!(Some(vec![0xD0, 0xEE]), latin1_bytes("\u{d0}\u{ee}"));
+        assert_eq!(None, latin1_bytes("Роутер"));
+    }
+}
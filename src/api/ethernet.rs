@@ -0,0 +1,54 @@
+use futures::Stream;
+
+use super::{
+    call::StreamingCall,
+    error::Error,
+    model::{BondingMonitor, BondingSlave, BondingStatus, CableTest, EthernetMonitor, Response},
+    Authenticated, MikrotikAPI,
+};
+
+impl MikrotikAPI<Authenticated> {
+    /// Get the state of the bonding interface `name`: active slave(s), LACP partner and per-slave details
+    pub async fn bonding_status(&mut self, name: &str) -> Result<BondingStatus, Error> {
+        let monitor = self
+            .generic_oneshot_call::<BondingMonitor>(
+                "/interface/bonding/monitor",
+                Some(&[("numbers", name), ("once", "")]),
+            )
+            .await?;
+
+        let slaves = self
+            .generic_array_call::<BondingSlave>(
+                "/interface/bonding/monitor-slaves",
+                Some(&[("bond", name), ("once", "")]),
+            )
+            .await?;
+
+        Ok(BondingStatus { monitor, slaves })
+    }
+
+    /// Runs a cable test on the ethernet interface `name`, the router reporting progress until the test is cancelled
+    /// through `tag`
+    pub async fn cable_test(
+        &mut self,
+        name: &str,
+        tag: &mut u16,
+    ) -> Result<impl Stream<Item = Response<CableTest>>, Error> {
+        self.do_call(
+            "/interface/ethernet/cable-test",
+            Some(&[("numbers", name)]),
+            StreamingCall::new(),
+            Some(tag),
+        )
+        .await
+    }
+
+    /// Get the link state of the ethernet interface `name`, along with the diagnostics of its SFP module if any
+    pub async fn ethernet_monitor(&mut self, name: &str) -> Result<EthernetMonitor, Error> {
+        self.generic_oneshot_call::<EthernetMonitor>(
+            "/interface/ethernet/monitor",
+            Some(&[("numbers", name), ("once", "")]),
+        )
+        .await
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::{error::Error, Authenticated, MikrotikAPI};
+
+/// Properties identifying an item, in order of preference. Items having none of them are identified by all their properties
+const KEY_PROPERTIES: [&str; 5] = ["name", "address", "dst-address", "mac-address", "list"];
+
+/// An item of a `ConfigSnapshot`, properties being sorted by name
+pub type SnapshotItem = BTreeMap<String, String>;
+
+/// Configuration of some menus of a router, as returned by `MikrotikAPI::config_snapshot`.
+///
+/// It is normalized so that snapshots of different routers can be compared: `.id`s are removed,
+/// dynamic items are skipped and items are sorted. Saved as JSON, it can stand in for a router in `diff_export`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConfigSnapshot {
+    /// Items, by menu path
+    pub tables: BTreeMap<String, Vec<SnapshotItem>>,
+}
+
+impl ConfigSnapshot {
+    /// Differences between the menus `paths` of this snapshot, side `a`, and `other`, side `b`.
+    /// Fails if either snapshot lacks one of the paths.
+    pub fn diff(&self, other: &ConfigSnapshot, paths: &[&str]) -> Result<ExportDiff, Error> {
+        let mut diffs = vec![];
+
+        for path in paths {
+            let a = self.table(path)?;
+            let b = other.table(path)?;
+
+            let diff = diff_table(path, a, b);
+
+            if !diff.is_identical() {
+                diffs.push(diff);
+            }
+        }
+
+        Ok(ExportDiff { paths: diffs })
+    }
+
+    fn table(&self, path: &str) -> Result<&[SnapshotItem], Error> {
+        self.tables
+            .get(path)
+            .map(Vec::as_slice)
+            .ok_or_else(|| Error::InvalidArgument(format!("snapshot holds no {}", path)))
+    }
+}
+
+/// One side of `diff_export`: a live router or a snapshot saved earlier
+pub enum ExportSource<'a> {
+    /// Snapshot taken from the router when diffing
+    Router(&'a mut MikrotikAPI<Authenticated>),
+
+    /// Snapshot loaded from a file
+    Snapshot(&'a ConfigSnapshot),
+}
+
+impl<'a> From<&'a mut MikrotikAPI<Authenticated>> for ExportSource<'a> {
+    fn from(api: &'a mut MikrotikAPI<Authenticated>) -> Self {
+        ExportSource::Router(api)
+    }
+}
+
+impl<'a> From<&'a ConfigSnapshot> for ExportSource<'a> {
+    fn from(snapshot: &'a ConfigSnapshot) -> Self {
+        ExportSource::Snapshot(snapshot)
+    }
+}
+
+impl ExportSource<'_> {
+    async fn snapshot(self, paths: &[&str]) -> Result<ConfigSnapshot, Error> {
+        match self {
+            ExportSource::Router(api) => api.config_snapshot(paths).await,
+            ExportSource::Snapshot(snapshot) => Ok(snapshot.clone()),
+        }
+    }
+}
+
+/// Differences between the configuration of two routers, as returned by `diff_export`
+#[derive(Debug, Default, Serialize)]
+pub struct ExportDiff {
+    /// Menus that differ, in the order they were given. Identical menus are left out
+    pub paths: Vec<PathDiff>,
+}
+
+impl ExportDiff {
+    /// Returns true if both sides hold the same configuration
+    pub fn is_identical(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+/// Differences between the items of a single menu
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PathDiff {
+    /// Menu path, ie `/ip/address`
+    pub path: String,
+
+    /// Keys of the items only found on side `a`
+    pub only_in_a: Vec<String>,
+
+    /// Keys of the items only found on side `b`
+    pub only_in_b: Vec<String>,
+
+    /// Items found on both sides with different properties
+    pub changed: Vec<ItemDiff>,
+}
+
+impl PathDiff {
+    /// Returns true if both sides hold the same items
+    pub fn is_identical(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// An item found on both sides with different properties
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ItemDiff {
+    /// Value of the identifying property of the item
+    pub key: String,
+
+    /// Properties whose value differs
+    pub properties: Vec<PropertyDiff>,
+}
+
+/// A property whose value differs between both sides
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PropertyDiff {
+    /// Property name
+    pub name: String,
+
+    /// Value on side `a`, `None` if it is unset
+    pub a: Option<String>,
+
+    /// Value on side `b`, `None` if it is unset
+    pub b: Option<String>,
+}
+
+fn item_key(item: &SnapshotItem) -> String {
+    KEY_PROPERTIES
+        .iter()
+        .find_map(|property| item.get(*property).cloned())
+        .unwrap_or_else(|| {
+            item.iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+}
+
+fn diff_table(path: &str, a: &[SnapshotItem], b: &[SnapshotItem]) -> PathDiff {
+    let a: BTreeMap<_, _> = a.iter().map(|item| (item_key(item), item)).collect();
+    let b: BTreeMap<_, _> = b.iter().map(|item| (item_key(item), item)).collect();
+
+    let mut diff = PathDiff {
+        path: path.to_owned(),
+        ..Default::default()
+    };
+
+    for (key, item_a) in &a {
+        let item_b = match b.get(key) {
+            Some(item_b) => item_b,
+            None => {
+                diff.only_in_a.push(key.clone());
+                continue;
+            }
+        };
+
+        let names: BTreeSet<_> = item_a.keys().chain(item_b.keys()).collect();
+
+        let properties: Vec<_> = names
+            .into_iter()
+            .filter(|name| item_a.get(*name) != item_b.get(*name))
+            .map(|name| PropertyDiff {
+                name: name.clone(),
+                a: item_a.get(name).cloned(),
+                b: item_b.get(name).cloned(),
+            })
+            .collect();
+
+        if !properties.is_empty() {
+            diff.changed.push(ItemDiff {
+                key: key.clone(),
+                properties,
+            });
+        }
+    }
+
+    diff.only_in_b = b
+        .keys()
+        .filter(|key| !a.contains_key(*key))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Takes a normalized snapshot of the menus `paths`, ie `/ip/address`, to be compared or saved
+    pub async fn config_snapshot(&mut self, paths: &[&str]) -> Result<ConfigSnapshot, Error> {
+        let mut snapshot = ConfigSnapshot::default();
+
+        for path in paths {
+            let items = self
+                .generic_array_call::<HashMap<String, String>>(&format!("{}/print", path), None)
+                .await?;
+
+            let mut items: Vec<SnapshotItem> = items
+                .into_iter()
+                .filter(|item| item.get("dynamic").map(String::as_str) != Some("true"))
+                .map(|item| item.into_iter().filter(|(name, _)| name != ".id").collect())
+                .collect();
+
+            items.sort();
+
+            snapshot.tables.insert(path.to_string(), items);
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// Compares the menus `paths` of two routers, or of a router and a saved `ConfigSnapshot`, ie both routers of an HA pair,
+/// or a router and the template it was configured from. Items are matched by name, address or MAC address.
+pub async fn diff_export<'a, 'b>(
+    a: impl Into<ExportSource<'a>>,
+    b: impl Into<ExportSource<'b>>,
+    paths: &[&str],
+) -> Result<ExportDiff, Error> {
+    let a = a.into().snapshot(paths).await?;
+    let b = b.into().snapshot(paths).await?;
+
+    a.diff(&b, paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigSnapshot, ItemDiff, PropertyDiff};
+
+    #[test]
+    fn test_diff() {
+        let a: ConfigSnapshot = serde_json::from_str(
+            r#"{
+                "/ip/address": [
+                    {"address": "10.0.0.1/24", "interface": "bridge"},
+                    {"address": "10.0.1.1/24", "interface": "ether2"}
+                ],
+                "/interface/vlan": [
+                    {"name": "vlan10", "vlan-id": "10", "interface": "bridge"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let b: ConfigSnapshot = serde_json::from_str(
+            r#"{
+                "/ip/address": [
+                    {"address": "10.0.0.1/24", "interface": "bridge", "comment": "lan"},
+                    {"address": "10.0.2.1/24", "interface": "ether3"}
+                ],
+                "/interface/vlan": [
+                    {"name": "vlan10", "vlan-id": "10", "interface": "bridge"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let diff = a.diff(&b, &["/ip/address", "/interface/vlan"]).unwrap();
+
+        assert_eq!(1, diff.paths.len());
+
+        let addresses = &diff.paths[0];
+        assert_eq!("/ip/address", addresses.path);
+        assert_eq!(vec!["10.0.1.1/24"], addresses.only_in_a);
+        assert_eq!(vec!["10.0.2.1/24"], addresses.only_in_b);
+        assert_eq!(
+            vec![ItemDiff {
+                key: "10.0.0.1/24".into(),
+                properties: vec![PropertyDiff {
+                    name: "comment".into(),
+                    a: None,
+                    b: Some("lan".into()),
+                }],
+            }],
+            addresses.changed
+        );
+
+        assert!(a.diff(&a, &["/ip/address"]).unwrap().is_identical());
+        assert!(a.diff(&b, &["/ip/route"]).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+
+use futures::future;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    de::DeserializerError,
+    error::Error,
+    model::{License, Routerboard, SystemResources},
+    reconnect::ReconnectPolicy,
+    secrets::SecretsSource,
+    topology::collect,
+    Authenticated, MikrotikAPI,
+};
+
+/// Hardware and software of a router, as returned by `MikrotikAPI::inventory`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inventory {
+    /// Name set in `/system/identity`
+    pub identity: String,
+
+    /// Board name, ie `RB4011iGS+` or `CHR`
+    pub board_name: String,
+
+    /// RouterBOARD model, `None` on x86 and CHR
+    pub model: Option<String>,
+
+    /// RouterBOARD serial number, `None` on x86 and CHR
+    pub serial_number: Option<String>,
+
+    /// RouterOS version, ie `7.16.1 (stable)`
+    pub version: String,
+
+    /// `arm`, `arm64`, `mipsbe`, `x86_64`, ...
+    pub architecture: String,
+
+    /// Installed packages, including disabled ones
+    pub packages: Vec<InstalledPackage>,
+
+    /// License level: `4`, `6` on RouterBOARDs, `free`, `p1`, `unlimited`, ... on CHR
+    pub license_level: Option<String>,
+}
+
+/// An entry of `/system/package`
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identity {
+    name: String,
+}
+
+/// Inventories of several routers, as returned by `fleet_inventory`
+#[derive(Debug, Default, Serialize)]
+pub struct FleetInventory {
+    /// One entry per router, in the order they were given
+    pub routers: Vec<RouterInventory>,
+}
+
+/// Outcome of the inventory of one router of a `FleetInventory`
+#[derive(Debug, Serialize)]
+pub struct RouterInventory {
+    /// Address of the router, also used as its id for the secrets source
+    pub router: String,
+
+    /// Inventory of the router, `None` if it could not be taken
+    pub inventory: Option<Inventory>,
+
+    /// Why the inventory could not be taken
+    pub error: Option<String>,
+}
+
+impl FleetInventory {
+    /// Routers by RouterOS version, to spot the ones lagging behind
+    pub fn by_version(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut versions: BTreeMap<_, Vec<_>> = BTreeMap::new();
+
+        for router in &self.routers {
+            if let Some(inventory) = &router.inventory {
+                versions
+                    .entry(inventory.version.as_str())
+                    .or_default()
+                    .push(router.router.as_str());
+            }
+        }
+
+        versions
+    }
+
+    /// Routers whose inventory could not be taken, along with the reason
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.routers.iter().filter_map(|router| {
+            router
+                .error
+                .as_deref()
+                .map(|error| (router.router.as_str(), error))
+        })
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Gathers identity, board, serial number, RouterOS version, packages and license level into a single `Inventory`.
+    ///
+    /// All five prints are sent at once and their replies awaited together.
+    pub async fn inventory(&mut self) -> Result<Inventory, Error> {
+        let identity = self
+            .send_print::<Identity>("/system/identity/print")
+            .await?;
+        let resources = self
+            .send_print::<SystemResources>("/system/resource/print")
+            .await?;
+        let routerboard = self
+            .send_print::<Routerboard>("/system/routerboard/print")
+            .await?;
+        let packages = self
+            .send_print::<InstalledPackage>("/system/package/print")
+            .await?;
+        let license = self.send_print::<License>("/system/license/print").await?;
+
+        let (identity, resources, routerboard, packages, license) =
+            tokio::join!(identity, resources, routerboard, packages, license);
+
+        let identity = first(collect(identity)?, "/system/identity/print")?;
+        let resources = first(collect(resources)?, "/system/resource/print")?;
+        let routerboard = collect(routerboard)?
+            .into_iter()
+            .next()
+            .filter(|info| info.routerboard);
+        let license = collect(license)?.into_iter().next();
+
+        Ok(Inventory {
+            identity: identity.name,
+            board_name: resources.board_name,
+            model: routerboard.as_ref().and_then(|info| info.model.clone()),
+            serial_number: routerboard.and_then(|info| info.serial_number),
+            version: resources.version,
+            architecture: resources.architecture_name,
+            packages: collect(packages)?,
+            license_level: license.and_then(|license| license.level),
+        })
+    }
+}
+
+fn first<T>(replies: Vec<T>, command: &'static str) -> Result<T, Error> {
+    replies.into_iter().next().ok_or_else(|| {
+        Error::Deserialize(DeserializerError::custom(format!(
+            "{} returned no reply",
+            command
+        )))
+    })
+}
+
+/// Connects to every router at once and takes its inventory, credentials being fetched from `secrets`
+/// with the address of the router as its id. Routers that could not be reached or inventoried are
+/// reported along with the error instead of failing the whole report.
+pub async fn fleet_inventory(
+    policy: &ReconnectPolicy,
+    secrets: &dyn SecretsSource,
+    routers: &[&str],
+) -> FleetInventory {
+    let inventories = routers.iter().map(|&router| async move {
+        let inventory = match policy.connect_with_secrets(router, router, secrets).await {
+            Ok(mut api) => api.inventory().await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        let (inventory, error) = match inventory {
+            Ok(inventory) => (Some(inventory), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        RouterInventory {
+            router: router.to_owned(),
+            inventory,
+            error,
+        }
+    });
+
+    FleetInventory {
+        routers: future::join_all(inventories).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::{MockReply, MockRouter};
+
+    use super::{FleetInventory, RouterInventory};
+
+    /// Answers the prints of `inventory` as a hAP ac² would
+    fn mock_router() -> MockRouter {
+        let resource = MockReply::new().re(&[
+            ("uptime", "1d"),
+            ("version", "7.16.1 (stable)"),
+            ("build-time", "2024-10-10 14:03:32"),
+            ("factory-software", "6.44.6"),
+            ("free-memory", "80000000"),
+            ("total-memory", "134217728"),
+            ("cpu", "ARMv7"),
+            ("cpu-count", "4"),
+            ("cpu-load", "3"),
+            ("free-hdd-space", "2000000"),
+            ("total-hdd-space", "16777216"),
+            ("architecture-name", "arm"),
+            ("board-name", "hAP ac^2"),
+            ("platform", "MikroTik"),
+        ]);
+
+        let routerboard = MockReply::new().re(&[
+            ("routerboard", "true"),
+            ("model", "RBD52G-5HacD2HnD"),
+            ("serial-number", "D7C40C4E7B2A"),
+        ]);
+
+        let packages = MockReply::new()
+            .re(&[("name", "routeros"), ("version", "7.16.1")])
+            .re(&[
+                ("name", "wifi-qcom-ac"),
+                ("version", "7.16.1"),
+                ("disabled", "true"),
+            ]);
+
+        MockRouter::new()
+            .on(
+                "/system/identity/print",
+                MockReply::new().re(&[("name", "office")]),
+            )
+            .on("/system/resource/print", resource)
+            .on("/system/routerboard/print", routerboard)
+            .on("/system/package/print", packages)
+            .on(
+                "/system/license/print",
+                MockReply::new().re(&[("software-id", "ABCD-1234"), ("nlevel", "4")]),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_inventory() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let inventory = api.inventory().await.unwrap();
+
+        assert_eq!("office", inventory.identity);
+        assert_eq!(Some("RBD52G-5HacD2HnD"), inventory.model.as_deref());
+        assert_eq!(Some("D7C40C4E7B2A"), inventory.serial_number.as_deref());
+        assert_eq!("7.16.1 (stable)", inventory.version);
+        assert_eq!(Some("4"), inventory.license_level.as_deref());
+
+        let disabled: Vec<_> = inventory
+            .packages
+            .iter()
+            .map(|package| (package.name.as_str(), package.disabled))
+            .collect();
+        assert_eq!(vec![("routeros", false), ("wifi-qcom-ac", true)], disabled);
+
+        let fleet = FleetInventory {
+            routers: vec![
+                RouterInventory {
+                    router: "10.0.0.1".into(),
+                    inventory: Some(inventory),
+                    error: None,
+                },
+                RouterInventory {
+                    router: "10.0.0.2".into(),
+                    inventory: None,
+                    error: Some("router unreachable".into()),
+                },
+            ],
+        };
+
+        assert_eq!(vec!["10.0.0.1"], fleet.by_version()["7.16.1 (stable)"]);
+        assert_eq!(
+            vec![("10.0.0.2", "router unreachable")],
+            fleet.failures().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use futures::{stream, Stream};
+
+use super::{
+    error::Error,
+    model::{IpsecActivePeer, IpsecInstalledSa, IpsecPeerState, IpsecPolicy},
+    Authenticated, MikrotikAPI,
+};
+
+/// A change to the IPsec peers of the router, see `MikrotikAPI::ipsec_peer_changes`
+#[derive(Debug, Clone)]
+pub enum IpsecPeerChange {
+    /// A peer showed up, or was already there when polling started
+    Up(IpsecActivePeer),
+
+    /// The phase 1 state of a peer changed, ie it got established
+    StateChanged(IpsecActivePeer),
+
+    /// A peer is gone, holding its remote address
+    Down(String),
+}
+
+/// Changes between the `previous` states of the peers, by remote address, and the `current` peers
+fn peer_changes(
+    previous: &HashMap<String, IpsecPeerState>,
+    current: &[IpsecActivePeer],
+) -> Vec<IpsecPeerChange> {
+    let mut changes: Vec<_> = current
+        .iter()
+        .filter_map(|peer| match previous.get(&peer.remote_address) {
+            None => Some(IpsecPeerChange::Up(peer.clone())),
+            Some(state) if *state != peer.state => {
+                Some(IpsecPeerChange::StateChanged(peer.clone()))
+            }
+            Some(_) => None,
+        })
+        .collect();
+
+    let gone = previous
+        .keys()
+        .filter(|address| !current.iter().any(|peer| &&peer.remote_address == address))
+        .map(|address| IpsecPeerChange::Down(address.clone()));
+
+    changes.extend(gone);
+    changes
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// List the IKE peers the router is currently talking to (`/ip/ipsec/active-peers`)
+    pub async fn ipsec_active_peers(&mut self) -> Result<Vec<IpsecActivePeer>, Error> {
+        self.generic_array_call("/ip/ipsec/active-peers/print", None)
+            .await
+    }
+
+    /// List the IPsec policies along with their phase 2 state
+    pub async fn ipsec_policies(&mut self) -> Result<Vec<IpsecPolicy>, Error> {
+        self.generic_array_call("/ip/ipsec/policy/print", None)
+            .await
+    }
+
+    /// List the SAs installed in the kernel
+    pub async fn ipsec_installed_sas(&mut self) -> Result<Vec<IpsecInstalledSa>, Error> {
+        self.generic_array_call("/ip/ipsec/installed-sa/print", None)
+            .await
+    }
+
+    /// Polls `/ip/ipsec/active-peers` every `period`, RouterOS having no `listen` for it, reporting peers going up,
+    /// down or changing state. Peers found by the first poll are reported as up.
+    /// Ends after the first failed poll, its error being the last item.
+    pub fn ipsec_peer_changes(
+        &mut self,
+        period: Duration,
+    ) -> impl Stream<Item = Result<IpsecPeerChange, Error>> + '_ {
+        struct Polling<'a> {
+            api: &'a mut MikrotikAPI<Authenticated>,
+            interval: tokio::time::Interval,
+            states: HashMap<String, IpsecPeerState>,
+            pending: VecDeque<IpsecPeerChange>,
+            failed: bool,
+        }
+
+        let polling = Polling {
+            api: self,
+            interval: tokio::time::interval(period),
+            states: HashMap::new(),
+            pending: VecDeque::new(),
+            failed: false,
+        };
+
+        stream::unfold(polling, |mut polling| async move {
+            loop {
+                if let Some(change) = polling.pending.pop_front() {
+                    return Some((Ok(change), polling));
+                }
+
+                if polling.failed {
+                    return None;
+                }
+
+                polling.interval.tick().await;
+
+                match polling.api.ipsec_active_peers().await {
+                    Ok(peers) => {
+                        polling
+                            .pending
+                            .extend(peer_changes(&polling.states, &peers));
+
+                        polling.states = peers
+                            .into_iter()
+                            .map(|peer| (peer.remote_address, peer.state))
+                            .collect();
+                    }
+
+                    Err(e) => {
+                        polling.failed = true;
+                        return Some((Err(e), polling));
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::api::model::{IpsecActivePeer, IpsecPeerState, RosId};
+
+    use super::{peer_changes, IpsecPeerChange};
+
+    fn peer(address: &str, state: IpsecPeerState) -> IpsecActivePeer {
+        IpsecActivePeer {
+            id: RosId(1),
+            identity: None,
+            remote_address: address.to_owned(),
+            local_address: None,
+            port: None,
+            state,
+            side: None,
+            uptime: None,
+            ph2_total: None,
+            dynamic_address: None,
+        }
+    }
+
+    #[test]
+    fn test_peer_changes() {
+        let previous = HashMap::from([
+            (
+                "198.51.100.7".to_owned(),
+                IpsecPeerState::Negotiating("sa-init-sent".into()),
+            ),
+            ("198.51.100.8".to_owned(), IpsecPeerState::Established),
+        ]);
+
+        let current = [
+            peer("198.51.100.7", IpsecPeerState::Established),
+            peer("198.51.100.9", IpsecPeerState::Established),
+        ];
+
+        let changes: Vec<_> = peer_changes(&previous, &current)
+            .into_iter()
+            .map(|change| match change {
+                IpsecPeerChange::Up(peer) => format!("up {}", peer.remote_address),
+                IpsecPeerChange::StateChanged(peer) => format!("changed {}", peer.remote_address),
+                IpsecPeerChange::Down(address) => format!("down {}", address),
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                "changed 198.51.100.7",
+                "up 198.51.100.9",
+                "down 198.51.100.8"
+            ],
+            changes
+        );
+    }
+}
@@ -0,0 +1,450 @@
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use futures::{future, Stream};
+use log::info;
+use serde::de::DeserializeOwned;
+use tokio::sync::Notify;
+
+use super::{
+    call::SequencedStreamingCall, error::Error, model::Response, subscription::StreamHandle,
+    Authenticated, MikrotikAPI,
+};
+
+/// How `KeepAlive` tells a configuration reload from a subscription simply ending
+#[derive(Debug, Clone)]
+pub struct KeepAliveOptions {
+    /// Subscriptions ending within this delay of each other are counted together
+    pub window: Duration,
+
+    /// Number of subscriptions that must end together for it to be a reload
+    pub min_streams: usize,
+
+    /// Time given to the router to settle after a reload, before subscribing again
+    pub settle_delay: Duration,
+}
+
+impl Default for KeepAliveOptions {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(1),
+            min_streams: 2,
+            settle_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl KeepAliveOptions {
+    /// Sets the delay within which subscriptions ending are counted together
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the number of subscriptions that must end together for it to be a reload
+    pub fn with_min_streams(mut self, min_streams: usize) -> Self {
+        self.min_streams = min_streams;
+        self
+    }
+
+    /// Sets the time waited after a reload before subscribing again
+    pub fn with_settle_delay(mut self, settle_delay: Duration) -> Self {
+        self.settle_delay = settle_delay;
+        self
+    }
+}
+
+/// Item of a subscription kept alive by `KeepAlive`
+#[derive(Debug)]
+pub enum SubscriptionEvent<T> {
+    /// Event sent by the router
+    Event(Response<T>),
+
+    /// The router ended the subscription along with others and it was sent again:
+    /// events that happened in the meantime were missed
+    Resubscribed,
+}
+
+/// State shared by a `KeptStream` and its entry in `KeepAlive`
+struct Shared<T> {
+    /// Sequence number of the first event of the current command, until the marker went out before it
+    resubscribed_at: Option<u64>,
+
+    /// First event of the current command, held back while the marker goes out
+    held: Option<Response<T>>,
+
+    waker: Option<Waker>,
+}
+
+/// Stream of a subscription kept alive by `KeepAlive`, spanning configuration reloads
+pub struct KeptStream<T> {
+    stream: SequencedStreamingCall<T>,
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Stream for KeptStream<T> {
+    type Item = SubscriptionEvent<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let shared = self.shared.clone();
+
+        let mut shared = match shared.lock() {
+            Ok(shared) => shared,
+            Err(_) => return Poll::Ready(None),
+        };
+
+        if let Some(response) = shared.held.take() {
+            return Poll::Ready(Some(SubscriptionEvent::Event(response)));
+        }
+
+        let resubscribed_at = shared.resubscribed_at;
+
+        match (Pin::new(&mut self.stream).poll_next(cx), resubscribed_at) {
+            // The marker goes between the events of the earlier command and those of the new one
+            (Poll::Ready(Some(item)), Some(seq)) if item.seq >= seq => {
+                shared.resubscribed_at = None;
+                shared.held = Some(item.value);
+
+                Poll::Ready(Some(SubscriptionEvent::Resubscribed))
+            }
+
+            (Poll::Ready(Some(item)), _) => Poll::Ready(Some(SubscriptionEvent::Event(item.value))),
+
+            // Ended along with its handle: on its own, on a `!trap`, or cancelled
+            (Poll::Ready(None), _) => Poll::Ready(None),
+
+            (Poll::Pending, Some(_)) => {
+                shared.resubscribed_at = None;
+
+                Poll::Ready(Some(SubscriptionEvent::Resubscribed))
+            }
+
+            (Poll::Pending, None) => {
+                shared.waker = Some(cx.waker().clone());
+
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A `StreamHandle` held by `KeepAlive`, its type of events erased
+trait Kept {
+    fn finished(&self) -> Arc<Notify>;
+
+    fn is_finished(&self) -> bool;
+
+    fn is_failed(&self) -> bool;
+
+    /// Has the stream tell the events of the earlier command from those of the one about to be sent
+    fn mark_resubscribed(&self);
+
+    fn resume<'a>(
+        &'a mut self,
+        api: &'a mut MikrotikAPI<Authenticated>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + 'a>>;
+
+    fn cancel<'a>(
+        self: Box<Self>,
+        api: &'a mut MikrotikAPI<Authenticated>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + 'a>>;
+}
+
+struct Entry<T> {
+    handle: StreamHandle<T>,
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Kept for Entry<T>
+where
+    T: DeserializeOwned + Debug + Sync + Send + 'static,
+{
+    fn finished(&self) -> Arc<Notify> {
+        self.handle.finished()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    fn is_failed(&self) -> bool {
+        self.handle.failure().is_some()
+    }
+
+    fn mark_resubscribed(&self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.resubscribed_at = Some(self.handle.next_seq());
+
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn resume<'a>(
+        &'a mut self,
+        api: &'a mut MikrotikAPI<Authenticated>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + 'a>> {
+        Box::pin(self.handle.resume(api))
+    }
+
+    fn cancel<'a>(
+        self: Box<Self>,
+        api: &'a mut MikrotikAPI<Authenticated>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + 'a>> {
+        Box::pin(self.handle.cancel(api))
+    }
+}
+
+/// `listen` subscriptions kept alive across configuration reloads.
+///
+/// Some configuration changes make RouterOS end all `listen` commands with a `!done`. When several subscriptions end
+/// together, `watch` waits for the router to settle and resumes their `StreamHandle`s, their streams carrying on after a
+/// `SubscriptionEvent::Resubscribed` marker. A subscription ending on its own, or on a `!trap`, ends its stream.
+pub struct KeepAlive {
+    options: KeepAliveOptions,
+    entries: Vec<Box<dyn Kept + Send>>,
+}
+
+impl KeepAlive {
+    /// Empty set of subscriptions
+    pub fn new(options: KeepAliveOptions) -> Self {
+        Self {
+            options,
+            entries: vec![],
+        }
+    }
+
+    /// Number of subscriptions still running
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether all subscriptions ended
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Starts a `listen` command kept alive across reloads
+    pub async fn subscribe<T>(
+        &mut self,
+        api: &mut MikrotikAPI<Authenticated>,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+    ) -> Result<KeptStream<T>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        let handle = api.subscribe(command, attributes).await?;
+
+        let shared = Arc::new(Mutex::new(Shared {
+            resubscribed_at: None,
+            held: None,
+            waker: None,
+        }));
+
+        let stream = KeptStream {
+            stream: handle.sequenced(),
+            shared: shared.clone(),
+        };
+
+        self.entries.push(Box::new(Entry { handle, shared }));
+
+        Ok(stream)
+    }
+
+    /// Waits for a configuration reload and subscribes again, returning the number of subscriptions sent again.
+    /// Subscriptions ending on their own meanwhile are dropped, `Ok(0)` being returned once none is left.
+    pub async fn watch(&mut self, api: &mut MikrotikAPI<Authenticated>) -> Result<usize, Error> {
+        loop {
+            if self.entries.is_empty() {
+                return Ok(0);
+            }
+
+            self.any_finished().await;
+
+            // Letting the other subscriptions of a reload end as well
+            tokio::time::sleep(self.options.window).await;
+
+            let (finished, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entries)
+                .into_iter()
+                .partition(|entry| entry.is_finished());
+
+            self.entries = running;
+
+            let (failed, ended): (Vec<_>, Vec<_>) =
+                finished.into_iter().partition(|entry| entry.is_failed());
+
+            Self::end(api, failed).await;
+
+            if ended.len() < self.options.min_streams {
+                Self::end(api, ended).await;
+                continue;
+            }
+
+            info!(
+                "{} subscriptions ended together, resubscribing in {:?}",
+                ended.len(),
+                self.options.settle_delay
+            );
+
+            tokio::time::sleep(self.options.settle_delay).await;
+
+            let count = ended.len();
+            let resumed = self.entries.len();
+
+            self.entries.extend(ended);
+
+            for entry in &mut self.entries[resumed..] {
+                entry.mark_resubscribed();
+                entry.resume(api).await?;
+            }
+
+            return Ok(count);
+        }
+    }
+
+    /// Cancels all subscriptions, ending their streams
+    pub async fn cancel(self, api: &mut MikrotikAPI<Authenticated>) {
+        Self::end(api, self.entries).await;
+    }
+
+    /// Ends `entries` for good, along with their streams
+    async fn end(api: &mut MikrotikAPI<Authenticated>, entries: Vec<Box<dyn Kept + Send>>) {
+        for entry in entries {
+            // `/cancel` is sent without waiting, nothing can go wrong
+            let _ = entry.cancel(api).await;
+        }
+    }
+
+    async fn any_finished(&self) {
+        let notifies: Vec<_> = self.entries.iter().map(|entry| entry.finished()).collect();
+
+        // Created before checking, so that no notification is missed
+        let notified: Vec<_> = notifies
+            .iter()
+            .map(|notify| Box::pin(notify.notified()))
+            .collect();
+
+        if self.entries.iter().any(|entry| entry.is_finished()) {
+            return;
+        }
+
+        future::select_all(notified).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use futures::StreamExt;
+
+    use crate::{
+        api::model::Response,
+        mock::{MockReply, MockRouter},
+    };
+
+    use super::{KeepAlive, KeepAliveOptions, SubscriptionEvent};
+
+    /// Sends one event for each `listen`, and ends them all on `/test/reload`
+    fn mock_router() -> MockRouter {
+        let listen = |command: &str| MockReply::new().re(&[("name", command)]).endless();
+
+        let reload = MockReply::new()
+            .ending("/log/listen")
+            .ending("/interface/listen")
+            .re(&[("message", "reloaded")]);
+
+        MockRouter::new()
+            .on("/log/listen", listen("/log/listen"))
+            .on("/interface/listen", listen("/interface/listen"))
+            .on("/test/reload", reload)
+            .on(
+                "/ip/address/listen",
+                MockReply::new()
+                    .re(&[("name", "/ip/address/listen")])
+                    .trap("no such item"),
+            )
+    }
+
+    fn name(event: Option<SubscriptionEvent<HashMap<String, String>>>) -> String {
+        match event {
+            Some(SubscriptionEvent::Event(Response::Reply(reply))) => reply["name"].clone(),
+            other => panic!("expected an event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_on_reload() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let options = KeepAliveOptions::default()
+            .with_window(Duration::from_millis(50))
+            .with_settle_delay(Duration::from_millis(10));
+
+        let mut keep_alive = KeepAlive::new(options);
+
+        let mut logs = keep_alive
+            .subscribe(&mut api, "/log/listen", None)
+            .await
+            .unwrap();
+        let mut interfaces = keep_alive
+            .subscribe(&mut api, "/interface/listen", None)
+            .await
+            .unwrap();
+
+        assert_eq!("/log/listen", name(logs.next().await));
+        assert_eq!("/interface/listen", name(interfaces.next().await));
+
+        api.generic_oneshot_call::<HashMap<String, String>>("/test/reload", None)
+            .await
+            .unwrap();
+
+        assert_eq!(2, keep_alive.watch(&mut api).await.unwrap());
+
+        assert!(matches!(
+            logs.next().await,
+            Some(SubscriptionEvent::Resubscribed)
+        ));
+        assert_eq!("/log/listen", name(logs.next().await));
+
+        assert!(matches!(
+            interfaces.next().await,
+            Some(SubscriptionEvent::Resubscribed)
+        ));
+        assert_eq!("/interface/listen", name(interfaces.next().await));
+
+        keep_alive.cancel(&mut api).await;
+    }
+
+    #[tokio::test]
+    async fn test_trap_ends_stream() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let options = KeepAliveOptions::default().with_window(Duration::from_millis(10));
+        let mut keep_alive = KeepAlive::new(options);
+
+        let mut addresses = keep_alive
+            .subscribe(&mut api, "/ip/address/listen", None)
+            .await
+            .unwrap();
+
+        // Not a reload: the subscription is dropped rather than sent again
+        assert_eq!(0, keep_alive.watch(&mut api).await.unwrap());
+        assert!(keep_alive.is_empty());
+
+        assert_eq!("/ip/address/listen", name(addresses.next().await));
+        assert!(matches!(
+            addresses.next().await,
+            Some(SubscriptionEvent::Event(Response::Trap { .. }))
+        ));
+        assert!(addresses.next().await.is_none());
+    }
+}
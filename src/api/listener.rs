@@ -1,13 +1,49 @@
-use std::{io::Cursor, time::Duration};
+use std::{
+    io::Cursor,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use bytes::{Buf, BytesMut};
 use log::{debug, error, trace, warn};
-use tokio::{io::Interest, net::tcp::OwnedReadHalf};
+use tokio::{io::AsyncReadExt, sync::Notify};
 
-use super::{error::Error, read::read_sentence, SharedTagMap};
+use super::{
+    budget::{self, Admission, Budget},
+    call::CallError,
+    cancel::Cancels,
+    error::Error,
+    lock_tags,
+    monitor::SharedMonitor,
+    options::Priority,
+    read::read_sentence,
+    transport::TransportReader,
+    ConnectionClosed, SharedTagMap, SharedWriter, TaggedCall,
+};
+
+/// How often in-flight calls are checked for idleness, and streams for lag
+const MONITOR_PERIOD: Duration = Duration::from_millis(500);
+
+/// Prefix of the reason the connection was closed for, when the router sent a `!fatal`
+const FATAL_PREFIX: &str = "!fatal from the router: ";
+
+/// Error for calls failed because the connection closed for `reason`: a `!fatal` telling that the router has
+/// too many API sessions is an `Error::ConnectionLimit`, anything else an `Error::ConnectionLost`
+pub(crate) fn closed_error(reason: &str) -> Error {
+    let limit = reason.strip_prefix(FATAL_PREFIX).filter(|message| {
+        let message = message.to_ascii_lowercase();
+
+        message.contains("limit") || message.contains("too many")
+    });
+
+    match limit {
+        Some(message) => Error::ConnectionLimit(message.to_owned()),
+        None => Error::ConnectionLost(reason.to_owned()),
+    }
+}
 
 async fn try_read_sentence(
-    reader: &mut OwnedReadHalf,
+    reader: &mut TransportReader,
     buffer: &mut BytesMut,
 ) -> Result<Vec<String>, Error> {
     let _sleepy_time = Duration::from_millis(20);
@@ -15,63 +51,198 @@ async fn try_read_sentence(
     loop {
         let mut cursor = Cursor::new(&buffer[..]);
 
-        if let Ok(sentence) = read_sentence(&mut cursor) {
-            let res = sentence.iter().map(|t| t.to_string()).collect();
+        match read_sentence(&mut cursor) {
+            Ok(sentence) => {
+                let res = sentence.iter().map(|t| t.to_string()).collect();
 
-            let consumed = cursor.position() as usize;
+                let consumed = cursor.position() as usize;
 
-            debug!("try_read_sentence: read new sentence ({} bytes).", consumed);
-            trace!("try_read_sentence: {:?}", sentence);
+                debug!("try_read_sentence: read new sentence ({} bytes).", consumed);
+                trace!("try_read_sentence: {:?}", sentence);
 
-            buffer.advance(consumed);
+                buffer.advance(consumed);
+
+                return Ok(res);
+            }
 
-            return Ok(res);
+            // Wait for the rest of the sentence
+            Err(Error::Incomplete) => (),
+
+            // No way to find where the next sentence starts
+            Err(e) => return Err(e),
         }
 
-        if reader.ready(Interest::READABLE).await?.is_readable() {
-            let new_bytes = reader.try_read_buf(buffer)?;
+        let new_bytes = reader.read_buf(buffer).await?;
 
-            trace!(
-                "try_read_sentence: filling buffer with {} new bytes.",
-                new_bytes
-            );
+        trace!(
+            "try_read_sentence: filling buffer with {} new bytes.",
+            new_bytes
+        );
 
-            if new_bytes == 0 {
-                return Err(Error::EndOfStream);
-            }
+        if new_bytes == 0 {
+            return Err(Error::EndOfStream);
         }
         //tokio::time::sleep(sleepy_time).await;
     }
 }
 
-pub async fn event_loop(mut socket: OwnedReadHalf, tags: SharedTagMap) {
+fn trap_message(sentence: &[String]) -> Option<&str> {
+    if sentence.first()? != "!trap" {
+        return None;
+    }
+
+    sentence
+        .iter()
+        .find_map(|word| word.strip_prefix("=message="))
+}
+
+/// Tag of a sentence, wherever its `.tag=` word is: `Ok(None)` if it has none, `Err` holding the value if it is no tag
+fn sentence_tag(sentence: &[String]) -> Result<Option<u16>, &str> {
+    match sentence.iter().find_map(|word| word.strip_prefix(".tag=")) {
+        Some(tag) => tag.parse().map(Some).map_err(|_| tag),
+        None => Ok(None),
+    }
+}
+
+/// Fails the calls still waiting for a reply once the event loop is over, even if it panicked,
+/// so that callers get an `Error::ConnectionLost` rather than waiting forever
+struct Teardown {
+    tags: SharedTagMap,
+    closed: ConnectionClosed,
+    reason: String,
+}
+
+impl Drop for Teardown {
+    fn drop(&mut self) {
+        let reason = match std::thread::panicking() {
+            true => "event loop panicked",
+            false => self.reason.as_str(),
+        };
+
+        warn!("event_loop: exiting ({})!", reason);
+
+        close_connection(&self.tags, &self.closed, reason);
+    }
+}
+
+/// Marks the connection as closed for `reason` and fails the calls still waiting for a reply,
+/// later calls failing at once with an `Error::ConnectionLost`
+pub(crate) fn close_connection(tags: &SharedTagMap, closed: &ConnectionClosed, reason: &str) {
+    let _ = closed.set(reason.to_owned());
+
+    for (tag, mut tagged) in lock_tags(tags).drain() {
+        debug!("event_loop: failing call with tag {}", tag);
+
+        let fatal = vec!["!fatal".to_owned(), reason.to_owned(), String::new()];
+        let done = vec!["!done".to_owned(), String::new()];
+
+        let _ = tagged.call.push_reply(fatal);
+        let _ = tagged.call.push_reply(done);
+        let _ = tagged.call.done();
+    }
+}
+
+/// Hands `sentence` over to `tagged`, returning the properties a strict call does not expect, if any: the reply is
+/// then dropped
+fn push_reply(tagged: &mut TaggedCall, sentence: Vec<String>) -> Result<Vec<String>, CallError> {
+    match tagged.strict && sentence[0] == "!re" {
+        true => tagged.call.push_strict_reply(sentence),
+        false => tagged.call.push_reply(sentence).map(|_| vec![]),
+    }
+}
+
+pub async fn event_loop(
+    mut socket: TransportReader,
+    tags: SharedTagMap,
+    monitor: SharedMonitor,
+    closed: ConnectionClosed,
+    writer: SharedWriter,
+    cancels: Arc<Cancels>,
+    budget: Option<Budget>,
+) {
+    let mut teardown = Teardown {
+        tags: tags.clone(),
+        closed,
+        reason: String::new(),
+    };
+
     let mut buffer = BytesMut::with_capacity(16384);
 
+    let mut ticker = tokio::time::interval(MONITOR_PERIOD);
+
+    let mut cancel_requested = Box::pin(cancels.changed());
+
+    // Reply waiting for room in the memory budget, along with the streams whose consumers may make some
+    let mut held: Option<(Vec<String>, Vec<Arc<Notify>>)> = None;
+
     debug!("event_loop: running!");
 
     loop {
-        if let Ok(sentence) = try_read_sentence(&mut socket, &mut buffer).await {
-            let mut iter = sentence.iter();
+        let streams = held
+            .as_ref()
+            .map(|(_, streams)| streams.clone())
+            .unwrap_or_default();
 
-            let first = iter.next().map(String::as_str);
-            let second = iter.next().map(String::as_str);
+        // Reads are paused while a reply is held, which is retried whenever room may have been made
+        let read = tokio::select! {
+            read = try_read_sentence(&mut socket, &mut buffer), if held.is_none() => read.map(Some),
 
-            let both = first.zip(second);
+            _ = budget::room(streams) => Ok(None),
+
+            _ = ticker.tick() => {
+                let lags: Vec<_> = lock_tags(&tags)
+                    .iter()
+                    .filter_map(|(tag, tagged)| Some((*tag, tagged.call.lag()?)))
+                    .collect();
+
+                if let Ok(mut monitor) = monitor.lock() {
+                    monitor.check_idle();
+                    monitor.check_lag(&lags);
+                }
+                Ok(None)
+            }
 
+            _ = &mut cancel_requested => {
+                if let Err(e) = cancels.send(&tags, &monitor, &writer).await {
+                    teardown.reason = e.to_string();
+                    break;
+                }
+
+                cancel_requested = Box::pin(cancels.changed());
+                Ok(None)
+            }
+        };
+
+        let sentence = match read {
+            Ok(Some(sentence)) => sentence,
+
+            Ok(None) => match held.take() {
+                Some((sentence, _)) => sentence,
+                None => continue,
+            },
+
+            Err(e) => {
+                teardown.reason = e.to_string();
+                break;
+            }
+        };
+
+        {
             enum FrameType {
                 Reply,
                 Done,
             }
 
             use FrameType::*;
-            let tuple = match both {
-                Some(("!re", tag)) | Some(("!trap", tag)) if tag.starts_with(".tag") => {
-                    Some((Reply, tag))
-                }
-                Some(("!done", tag)) => Some((Done, tag)),
+            let frame_type = match sentence.first().map(String::as_str) {
+                Some("!re") | Some("!trap") => Some(Reply),
+                Some("!done") => Some(Done),
+
+                Some("!fatal") => {
+                    let message = sentence.get(1).map(String::as_str).unwrap_or_default();
 
-                Some(("!fatal", message)) => {
                     error!("received !fatal from the router: {}", message);
+                    teardown.reason = format!("{}{}", FATAL_PREFIX, message);
                     break;
                 }
 
@@ -79,34 +250,277 @@ pub async fn event_loop(mut socket: OwnedReadHalf, tags: SharedTagMap) {
                     warn!("unknown frame type: {:?}", unknown);
                     None
                 }
-            }
-            .map(|(f_type, tag)| {
-                let (_, id) = tag.split_at(5);
+            };
 
-                let id: u16 = id.parse().unwrap();
+            let tuple = frame_type.and_then(|f_type| match sentence_tag(&sentence) {
+                Ok(Some(id)) => Some((f_type, id)),
 
-                (f_type, id)
+                Ok(None) => {
+                    warn!("event_loop: untagged sentence ignored: {:?}", sentence);
+                    None
+                }
+
+                // The call it was meant for is unknown: it is left to its deadline, if any
+                Err(tag) => {
+                    error!("event_loop: malformed tag '{}', sentence ignored", tag);
+                    None
+                }
             });
 
             if let Some((frame_type, id)) = tuple {
-                if let Ok(mut guarded_map) = tags.lock() {
-                    if let Some(caller) = guarded_map.get_mut(&id) {
-                        if let Err(e) = caller.push_reply(sentence) {
-                            error!("on push_reply: {:?}", e);
-                            break;
+                let mut priority = Priority::Interactive;
+
+                let bytes = sentence.iter().map(String::len).sum();
+
+                // Reads pause here under backpressure
+                if let (Some(budget), true) = (&budget, sentence[0] == "!re") {
+                    match budget.admit_reply(&tags, id, bytes) {
+                        Admission::Admit => (),
+
+                        Admission::Drop => continue,
+
+                        Admission::Wait(streams) => {
+                            trace!(
+                                "event_loop: reply held back by the memory budget (tag {})",
+                                id
+                            );
+
+                            held = Some((sentence, streams));
+                            continue;
+                        }
+                    }
+                }
+
+                {
+                    let mut guarded_map = lock_tags(&tags);
+
+                    // Properties of the reply a strict call does not expect
+                    let mut unknown = vec![];
+
+                    if let Some(tagged) = guarded_map.get_mut(&id) {
+                        priority = tagged.priority;
+
+                        let trap = trap_message(&sentence).map(str::to_owned);
+
+                        let start = Instant::now();
+
+                        let is_reply = sentence[0] == "!re";
+
+                        if is_reply {
+                            tagged.received += 1;
+
+                            if !tagged.call.is_streaming() {
+                                tagged.pending_bytes += bytes;
+                            }
+
+                            if let Some(progress) = &tagged.progress {
+                                progress.report(tagged.received);
+                            }
+                        }
+
+                        let dropped = is_reply
+                            && tagged
+                                .pre_filter
+                                .as_ref()
+                                .is_some_and(|filter| !filter.keeps(&sentence));
+
+                        if dropped {
+                            trace!("event_loop: sentence dropped by pre-filter (tag {})", id);
+                        } else {
+                            match push_reply(tagged, sentence) {
+                                Ok(fields) => unknown = fields,
+
+                                Err(e) => {
+                                    error!("on push_reply: {}", e);
+                                    teardown.reason = e.to_string();
+                                    break;
+                                }
+                            }
+                        }
+
+                        if let Ok(mut monitor) = monitor.lock() {
+                            monitor.activity(id, bytes);
+                            monitor.deserialized(id, start.elapsed());
+
+                            if let Some(message) = trap.as_deref() {
+                                monitor.trapped(id, message);
+                            }
                         }
 
                         if let Done = frame_type {
-                            if let Err(e) = caller.done() {
-                                error!("on done: {:?}", e);
+                            // Handed over to the caller
+                            tagged.pending_bytes = 0;
+
+                            if let Ok(mut monitor) = monitor.lock() {
+                                monitor.finished(id);
+                            }
+
+                            cancels.unwatch(id);
+
+                            if let Err(e) = tagged.call.done() {
+                                error!("on done: {}", e);
+                                teardown.reason = e.to_string();
                                 break;
                             }
                         }
                     }
+
+                    if !unknown.is_empty() {
+                        warn!("strict mode: reply holds unknown properties {:?}", unknown);
+
+                        cancels.fail(&mut guarded_map, id, Error::UnknownFields { unknown });
+                    }
+                }
+
+                // Let other tasks (interactive callers) run between sentences of bulk replies.
+                if let Priority::Bulk = priority {
+                    tokio::task::yield_now().await;
                 }
             }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use crate::{
+        api::{error::Error, options::CallOptions, read::MAX_SENTENCE_LEN, Priority},
+        mock::{MockReply, MockRouter},
+    };
+
+    use super::sentence_tag;
+
+    #[test]
+    fn test_sentence_tag() {
+        let sentence = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(Ok(Some(7)), sentence_tag(&sentence(&["!re", ".tag=7", ""])));
+        assert_eq!(
+            Ok(Some(7)),
+            sentence_tag(&sentence(&["!re", "=name=ether1", ".tag=7", ""]))
+        );
+        assert_eq!(Ok(None), sentence_tag(&sentence(&["!done", ""])));
+        assert_eq!(Err("7x"), sentence_tag(&sentence(&["!re", ".tag=7x", ""])));
+        assert_eq!(
+            Err("70000"),
+            sentence_tag(&sentence(&["!done", ".tag=70000", ""]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_malformed_tags_are_skipped() {
+        // Every answer is preceded by sentences holding a broken tag or none
+        let sloppy = || {
+            MockReply::new()
+                .untagged(&["!re", ".tag=oops", "=name=nope"])
+                .untagged(&["!done"])
+        };
+
+        let router = MockRouter::new().on("/login", sloppy()).on(
+            "/system/identity/print",
+            sloppy().re(&[("name", "MikroTik")]),
+        );
+
+        let mut api = router.connect().await.unwrap();
+
+        for _ in 0..2 {
+            let identity = api
+                .generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+                .await
+                .unwrap();
+
+            assert_eq!("MikroTik", identity["name"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_sentence() {
+        let huge = format!("={}", "x".repeat(MAX_SENTENCE_LEN));
+
+        let router = MockRouter::new()
+            .on(
+                "/system/identity/print",
+                MockReply::new().untagged(&["!re", &huge]),
+            )
+            .on("/interface/print", MockReply::new());
+
+        let mut api = router.connect().await.unwrap();
+
+        // The connection is closed as soon as the length prefix is read, not once 16 MiB are buffered
+        assert!(matches!(
+            api.generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+                .await,
+            Err(Error::ConnectionLost(_))
+        ));
+
+        let error = api
+            .generic_array_call::<HashMap<String, String>>("/interface/print", None)
+            .await
+            .unwrap_err();
+
+        match error {
+            Error::ConnectionLost(reason) => {
+                assert!(reason.contains("bytes at most"), "{}", reason)
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interactive_call_during_bulk_print() {
+        const CONNECTIONS: usize = 3000;
+
+        let connections = (0..CONNECTIONS).fold(MockReply::new(), |reply, i| {
+            reply.re(&[("src-address", &format!("10.0.{}.{}", i / 256, i % 256))])
+        });
+
+        let router = MockRouter::new()
+            .on("/ip/firewall/connection/print", connections)
+            .on(
+                "/system/identity/print",
+                MockReply::new().re(&[("name", "MikroTik")]),
+            );
+
+        let mut api = router.connect().await.unwrap();
 
-    warn!("event_loop: exiting!");
+        let received = Arc::new(AtomicUsize::new(0));
+        let progress = received.clone();
+
+        let options = CallOptions {
+            priority: Priority::Bulk,
+            ..Default::default()
+        }
+        .with_progress(move |count| progress.store(count, Ordering::SeqCst));
+
+        // Through the public API: the print is sent, its replies awaited once the identity is known
+        let bulk = api
+            .generic_array_call_detached::<HashMap<String, String>>(
+                "/ip/firewall/connection/print",
+                None,
+                &options,
+            )
+            .await
+            .unwrap();
+
+        let identity = api
+            .generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+            .await
+            .unwrap();
+
+        assert_eq!("MikroTik", identity["name"]);
+
+        // The interactive call did not wait for the whole print
+        assert!(received.load(Ordering::SeqCst) < CONNECTIONS);
+
+        let connections = bulk.await.unwrap();
+        assert_eq!(CONNECTIONS, connections.len());
+    }
 }
@@ -0,0 +1,331 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    call::ArrayListCall,
+    error::Error,
+    model::{ArpEntry, BridgeHost, MacAddress, Response, WirelessRegistration},
+    topology::collect,
+    Authenticated, MikrotikAPI,
+};
+
+/// Just enough of `/ip/dhcp-server/lease`, whatever the enabled features
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Lease {
+    address: String,
+    host_name: Option<String>,
+    status: Option<String>,
+}
+
+/// Looks for a device by MAC address in the ARP table, DHCP leases, bridge hosts and wireless registrations,
+/// see `MikrotikAPI::locate_device`
+#[derive(Debug, Clone)]
+pub struct MacQuery {
+    mac: MacAddress,
+    wireless: bool,
+}
+
+impl MacQuery {
+    /// Query for the device having the address `mac`
+    pub fn new(mac: MacAddress) -> Self {
+        Self {
+            mac,
+            wireless: true,
+        }
+    }
+
+    /// Skips wireless registrations, ie on routers without wireless interfaces
+    pub fn without_wireless(mut self) -> Self {
+        self.wireless = false;
+        self
+    }
+}
+
+/// Where a device was found, as returned by `MikrotikAPI::locate_device`. Fields are `None` when no table knows about it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceLocation {
+    #[allow(missing_docs)]
+    pub mac_address: Option<MacAddress>,
+
+    /// IP addresses, from the ARP table then from DHCP leases
+    pub addresses: Vec<String>,
+
+    /// Interface the device is reachable through, from the ARP table, ie a bridge
+    pub interface: Option<String>,
+
+    /// Bridge and port the address was learned on
+    pub bridge: Option<String>,
+    #[allow(missing_docs)]
+    pub port: Option<String>,
+
+    /// VLAN the address was learned in
+    pub vlan_id: Option<u16>,
+
+    /// Wireless interface the device is associated with
+    pub wireless_interface: Option<String>,
+    #[allow(missing_docs)]
+    pub ssid: Option<String>,
+    #[allow(missing_docs)]
+    pub signal_strength: Option<String>,
+
+    /// Host name sent in the DHCP request of the device
+    pub host_name: Option<String>,
+
+    /// Status of its DHCP lease, ie `bound`
+    pub lease_status: Option<String>,
+}
+
+impl DeviceLocation {
+    /// Whether any table knows about the device
+    pub fn is_found(&self) -> bool {
+        let empty = DeviceLocation {
+            mac_address: self.mac_address,
+            ..Default::default()
+        };
+
+        *self != empty
+    }
+}
+
+/// Replies of a print of a menu that may not exist, such as `/interface/wifi` on RouterOS 6: a `!trap` means no items
+fn collect_optional<T>(replies: Result<Vec<Response<T>>, Error>) -> Result<Vec<T>, Error> {
+    match collect(replies) {
+        Err(Error::Remote(_)) => Ok(vec![]),
+        other => other,
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Finds the device of `query` in the ARP table, DHCP leases, bridge hosts and wireless registrations,
+    /// merging what is known about it: IP address, bridge port, SSID, host name, ...
+    ///
+    /// All prints are sent at once and their replies awaited together.
+    pub async fn locate_device(&mut self, query: &MacQuery) -> Result<DeviceLocation, Error> {
+        let mac = query.mac.to_string();
+
+        let arp = self
+            .send_print_where::<ArpEntry>("/ip/arp/print", &mac)
+            .await?;
+        let leases = self
+            .send_print_where::<Lease>("/ip/dhcp-server/lease/print", &mac)
+            .await?;
+        let hosts = self
+            .send_print_where::<BridgeHost>("/interface/bridge/host/print", &mac)
+            .await?;
+
+        let (wireless, wifi) = match query.wireless {
+            true => (
+                Some(
+                    self.send_print_where::<WirelessRegistration>(
+                        "/interface/wireless/registration-table/print",
+                        &mac,
+                    )
+                    .await?,
+                ),
+                Some(
+                    self.send_print_where::<WirelessRegistration>(
+                        "/interface/wifi/registration-table/print",
+                        &mac,
+                    )
+                    .await?,
+                ),
+            ),
+            false => (None, None),
+        };
+
+        let wireless = async move {
+            match wireless {
+                Some(call) => Some(call.await),
+                None => None,
+            }
+        };
+        let wifi = async move {
+            match wifi {
+                Some(call) => Some(call.await),
+                None => None,
+            }
+        };
+
+        let (arp, leases, hosts, wireless, wifi) = tokio::join!(arp, leases, hosts, wireless, wifi);
+
+        let mut location = DeviceLocation {
+            mac_address: Some(query.mac),
+            ..Default::default()
+        };
+
+        for entry in collect(arp)? {
+            location.interface.get_or_insert(entry.interface);
+            location.addresses.push(entry.address);
+        }
+
+        // Leases are only in the `dhcp` package
+        for lease in collect_optional(leases)? {
+            if !location.addresses.contains(&lease.address) {
+                location.addresses.push(lease.address);
+            }
+
+            location.host_name = location.host_name.or(lease.host_name);
+            location.lease_status = location.lease_status.or(lease.status);
+        }
+
+        if let Some(host) = collect(hosts)?.into_iter().find(|host| !host.local) {
+            location.bridge = Some(host.bridge);
+            location.port = Some(host.on_interface);
+            location.vlan_id = host.vid;
+        }
+
+        let registrations = [wireless, wifi]
+            .into_iter()
+            .flatten()
+            .map(collect_optional)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(registration) = registrations.into_iter().flatten().next() {
+            let ssid = match registration.ssid {
+                Some(ssid) => Some(ssid),
+                None => self.wireless_ssid(&registration.interface).await?,
+            };
+
+            location.wireless_interface = Some(registration.interface);
+            location.ssid = ssid;
+            location.signal_strength = registration.signal_strength;
+        }
+
+        Ok(location)
+    }
+
+    /// SSID of an `/interface/wireless` interface
+    async fn wireless_ssid(&mut self, interface: &str) -> Result<Option<String>, Error> {
+        let interfaces = self
+            .generic_array_call::<HashMap<String, String>>(
+                "/interface/wireless/print",
+                Some(&[("?name", interface), ("=.proplist", "ssid")]),
+            )
+            .await?;
+
+        Ok(interfaces
+            .into_iter()
+            .find_map(|mut interface| interface.remove("ssid")))
+    }
+
+    async fn send_print_where<T>(
+        &mut self,
+        command: &str,
+        mac: &str,
+    ) -> Result<Box<ArrayListCall<T>>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        self.do_call(
+            command,
+            Some(&[("?mac-address", mac)]),
+            ArrayListCall::new(),
+            None,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::model::MacAddress,
+        mock::{MockReply, MockRouter},
+    };
+
+    use super::{DeviceLocation, MacQuery};
+
+    const MAC: &str = "4C:5E:0C:AA:BB:CC";
+
+    /// RouterOS 6 router knowing `MAC` as a wireless client, queries being ignored except for the MAC address
+    fn mock_router() -> MockRouter {
+        let known = format!("?mac-address={}", MAC);
+
+        let table = |router: MockRouter, command: &str, properties: &[(&str, &str)]| {
+            router.on(command, MockReply::new()).on_query(
+                command,
+                &[&known],
+                MockReply::new().re(properties),
+            )
+        };
+
+        let router = table(
+            MockRouter::new(),
+            "/ip/arp/print",
+            &[
+                (".id", "*1"),
+                ("address", "192.168.88.23"),
+                ("mac-address", MAC),
+                ("interface", "bridge"),
+            ],
+        );
+
+        let router = table(
+            router,
+            "/ip/dhcp-server/lease/print",
+            &[
+                ("address", "192.168.88.23"),
+                ("host-name", "reception-laptop"),
+                ("status", "bound"),
+            ],
+        );
+
+        let router = table(
+            router,
+            "/interface/bridge/host/print",
+            &[
+                ("mac-address", MAC),
+                ("on-interface", "wlan1"),
+                ("bridge", "bridge"),
+            ],
+        );
+
+        let router = table(
+            router,
+            "/interface/wireless/registration-table/print",
+            &[
+                ("interface", "wlan1"),
+                ("mac-address", MAC),
+                ("signal-strength", "-62dBm@6Mbps"),
+            ],
+        );
+
+        // `/interface/wifi/...` is unknown, as on RouterOS 6
+        router.on(
+            "/interface/wireless/print",
+            MockReply::new().re(&[("ssid", "office")]),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_locate_device() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let mac: MacAddress = MAC.parse().unwrap();
+
+        let location = api.locate_device(&MacQuery::new(mac)).await.unwrap();
+
+        assert_eq!(
+            DeviceLocation {
+                mac_address: Some(mac),
+                addresses: vec!["192.168.88.23".into()],
+                interface: Some("bridge".into()),
+                bridge: Some("bridge".into()),
+                port: Some("wlan1".into()),
+                vlan_id: None,
+                wireless_interface: Some("wlan1".into()),
+                ssid: Some("office".into()),
+                signal_strength: Some("-62dBm@6Mbps".into()),
+                host_name: Some("reception-laptop".into()),
+                lease_status: Some("bound".into()),
+            },
+            location
+        );
+
+        let unknown = MacQuery::new("00:11:22:33:44:55".parse().unwrap()).without_wireless();
+        assert!(!api.locate_device(&unknown).await.unwrap().is_found());
+    }
+}
@@ -1,33 +1,100 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
-    sync::{Arc, Mutex, MutexGuard},
+    future::Future,
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
 };
 
-use futures::Stream;
+use futures::{future, Stream, StreamExt};
 use log::{debug, trace};
 use rand::distributions::{Distribution, Uniform};
 use serde::de::DeserializeOwned;
 use tokio::{
     io::{AsyncWriteExt, BufWriter},
-    net::{tcp::OwnedWriteHalf, TcpStream},
+    net::TcpStream,
+    sync::broadcast,
 };
 
 use crate::api::call::{ArrayListCall, EmptyCall};
 
 use self::{
-    call::{AsyncCall, OneShotCall, StreamingCall},
+    audit::{redact, AuditEvent},
+    budget::Budget,
+    call::{AsyncCall, LoginCall, OneShotCall, StreamingCall},
+    cancel::Cancels,
+    entity::done_or_trap,
     error::Error,
-    listener::event_loop,
-    model::{ActiveUser, Interface, InterfaceChange, Response, SystemResources},
+    listener::{closed_error, event_loop},
+    model::{
+        ActiveUser, Interface, InterfaceChange, InterfaceMTU, IpCloud, IpCloudSettings, IpService,
+        IpServiceSettings, License, Response, Routerboard, StreamItem, SystemResources, FATAL,
+    },
+    monitor::{ReplyMonitor, SharedMonitor, SlowReplyWarnings},
+    options::{
+        ActiveUserFilter, CallOptions, ConnectOptions, LoginMethod, PreFilter, Priority, Progress,
+    },
+    policy::Policy,
+    sentence::{Attr, SentenceBuilder},
+    transport::{Connection, Transport, TransportReader, TransportWriter},
 };
 
+#[cfg(feature = "metrics")]
+use self::stats::StatsReport;
+
+pub(crate) mod audit;
+mod budget;
 mod call;
-mod de;
-mod error;
+mod cancel;
+#[cfg(feature = "clock")]
+pub(crate) mod clock;
+pub(crate) mod coalesce;
+pub(crate) mod de;
+#[cfg(feature = "dhcp")]
+pub(crate) mod dhcp;
+pub(crate) mod drift;
+#[cfg(feature = "email")]
+mod email;
+pub(crate) mod entity;
+pub(crate) mod error;
+pub(crate) mod escape;
+#[cfg(feature = "ethernet")]
+pub(crate) mod ethernet;
+pub(crate) mod export;
+pub(crate) mod inventory;
+#[cfg(feature = "ipsec")]
+pub(crate) mod ipsec;
+pub(crate) mod keep_alive;
 mod listener;
+#[cfg(feature = "mac")]
+pub(crate) mod mac;
 pub(crate) mod model;
-mod read;
+pub(crate) mod monitor;
+pub(crate) mod neighbor;
+pub(crate) mod options;
+pub(crate) mod policy;
+pub(crate) mod privileges;
+pub(crate) mod raw;
+pub(crate) mod read;
+pub(crate) mod reconnect;
+mod sample;
+pub(crate) mod secrets;
+pub(crate) mod self_test;
+pub(crate) mod sentence;
+mod ser;
+pub(crate) mod session;
+pub(crate) mod snapshot;
+#[cfg(feature = "metrics")]
+pub(crate) mod stats;
+pub(crate) mod subscription;
+pub(crate) mod template;
+pub(crate) mod topology;
+#[cfg(feature = "torch")]
+pub(crate) mod torch;
+pub(crate) mod transport;
+#[cfg(feature = "watchdog")]
+pub(crate) mod watchdog;
+#[cfg(feature = "wireless")]
+mod wireless;
 
 pub trait State {}
 
@@ -40,63 +107,157 @@ pub struct Authenticated;
 impl State for Disconnected {}
 impl State for Authenticated {}
 
-pub type TagMap = HashMap<u16, Box<dyn AsyncCall + Send + Sync>>;
+/// A call waiting for its reply, along with the options it was made with
+pub struct TaggedCall {
+    pub call: Box<dyn AsyncCall + Send + Sync>,
+    pub priority: Priority,
+    pub pre_filter: Option<PreFilter>,
+    pub progress: Option<Progress>,
+
+    /// `!re` sentences received so far
+    pub received: usize,
+
+    /// Size of the replies received while the call is in progress, see `MemoryBudget`
+    pub pending_bytes: usize,
+
+    /// Whether replies with unknown properties fail the call, see `CallOptions::strict`
+    pub strict: bool,
+}
+
+pub type TagMap = HashMap<u16, TaggedCall>;
 
 pub type SharedTagMap = Arc<Mutex<TagMap>>;
 
+/// Why the event loop stopped, set once it did
+pub type ConnectionClosed = Arc<OnceLock<String>>;
+
+/// Writing half of the connection, shared by the caller and the event loop, which sends `/cancel` on its own
+pub(crate) type SharedWriter = Arc<tokio::sync::Mutex<BufWriter<TransportWriter>>>;
+
+/// Locks the tag map, even if a thread panicked while holding it: the map itself is always left consistent
+pub(crate) fn lock_tags(tags: &SharedTagMap) -> MutexGuard<'_, TagMap> {
+    tags.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 /// Struct to interact with Mikrotik RouterOS API on port 8728
 pub struct MikrotikAPI<S: State> {
-    output: BufWriter<OwnedWriteHalf>,
+    output: SharedWriter,
+    sentence: SentenceBuilder,
     tag_map: SharedTagMap,
+    closed: ConnectionClosed,
+    cancels: Arc<Cancels>,
     tag_iter: Box<dyn Iterator<Item = u16>>,
+    monitor: SharedMonitor,
+    policy: Policy,
+    options: ConnectOptions,
+    /// User the connection is authenticated as
+    user: Option<String>,
 
     _state: S,
 }
 
 impl<S: State> MikrotikAPI<S> {
+    /// Sentence count and size statistics of the replies received so far, by command path
+    #[cfg(feature = "metrics")]
+    pub fn stats_report(&self) -> StatsReport {
+        self.monitor
+            .lock()
+            .map(|monitor| monitor.stats())
+            .unwrap_or_default()
+    }
+
+    /// Subscribes to the audit log of the connection: commands sent (secrets redacted), replies, traps and logins.
+    /// Events are only recorded once a first subscriber exists; lagging subscribers lose the oldest events.
+    pub fn audit_log(&mut self) -> broadcast::Receiver<AuditEvent> {
+        match self.monitor.lock() {
+            Ok(mut monitor) => monitor.subscribe_audit(),
+
+            // Never fed: the event loop is gone along with the connection
+            Err(_) => broadcast::channel(1).1,
+        }
+    }
+
+    /// Enables warnings (and an optional callback) about calls waiting too long for a reply
+    /// or sentences taking too long to be deserialized
+    pub fn set_slow_reply_warnings(&mut self, warnings: SlowReplyWarnings) {
+        if let Ok(mut monitor) = self.monitor.lock() {
+            monitor.configure(warnings);
+        }
+    }
+
+    /// Sets the policy every outgoing command is checked against. Commands it denies fail with `Error::Denied`
+    pub fn set_policy(&mut self, policy: Policy) {
+        self.policy = policy;
+    }
+
+    /// Stops dispatching replies for `tag`: whatever the router still sends for it is dropped
+    fn forget_tag(&mut self, tag: u16) {
+        lock_tags(&self.tag_map).remove(&tag);
+        self.cancels.unwatch(tag);
+
+        if let Ok(mut monitor) = self.monitor.lock() {
+            monitor.finished(tag);
+        }
+    }
+
     async fn send_command(
         &mut self,
         command: &str,
         attributes: &[(&str, &str)],
     ) -> Result<(), Error> {
-        let mut sentence = Vec::with_capacity(1 + attributes.len());
-
-        sentence.push(command.to_owned());
+        self.sentence.command(command);
 
         for (key, value) in attributes {
-            if key.starts_with('?') {
-                if value.is_empty() {
-                    sentence.push(key.to_string());
-                } else {
-                    sentence.push(format!("{}={}", key, value));
-                }
-            } else if key.starts_with(&['.', '=']) {
-                //.proplist, .tag
-                sentence.push(format!("{}={}", key, value));
-            } else {
-                // everything else (attributes)
-                sentence.push(format!("={}={}", key, value));
-            }
+            self.sentence.push(Attr::from_pair(key, value));
         }
 
-        let bytes = encode_sentence(sentence.as_slice());
+        let bytes = self.sentence.finish()?;
+
+        let mut output = self.output.lock().await;
 
-        self.output.write_all(&bytes).await?;
-        self.output.flush().await?;
+        output.write_all(bytes).await?;
+        output.flush().await?;
 
         Ok(())
     }
 
-    async fn do_call<'a, T>(
+    async fn do_call<T>(
         &mut self,
         command: &str,
         attributes: Option<&[(&str, &str)]>,
         call_type: T,
         future_tag: Option<&mut u16>,
-    ) -> Box<T>
+    ) -> Result<Box<T>, Error>
     where
         T: AsyncCall + Clone + Send + Sync + 'static,
     {
+        self.do_call_with_options(
+            command,
+            attributes,
+            call_type,
+            future_tag,
+            &CallOptions::default(),
+        )
+        .await
+    }
+
+    async fn do_call_with_options<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        call_type: T,
+        future_tag: Option<&mut u16>,
+        options: &CallOptions,
+    ) -> Result<Box<T>, Error>
+    where
+        T: AsyncCall + Clone + Send + Sync + 'static,
+    {
+        self.policy.check(command)?;
+
+        if let Some(reason) = self.closed.get() {
+            return Err(closed_error(reason));
+        }
+
         let mut attributes: Vec<(&str, &str)> = attributes.map(<[_]>::to_vec).unwrap_or_default();
 
         let boxed_call = Box::new(call_type);
@@ -104,10 +265,35 @@ impl<S: State> MikrotikAPI<S> {
 
         let mut tag = None;
 
-        if let Ok(mut map) = self.tag_map.lock() {
+        let is_streaming = boxed_call.is_streaming();
+
+        {
+            let mut map = lock_tags(&self.tag_map);
+
             let new_tag = tag.get_or_insert(next_tag(&mut self.tag_iter, &map));
 
-            map.insert(*new_tag, boxed_call);
+            map.insert(
+                *new_tag,
+                TaggedCall {
+                    call: boxed_call,
+                    priority: options.priority,
+                    pre_filter: options.pre_filter.clone(),
+                    progress: options.progress.clone(),
+                    received: 0,
+                    pending_bytes: 0,
+                    strict: options.strict,
+                },
+            );
+
+            // Before the command is sent, for the watch not to outlive a stream ending at once
+            if is_streaming {
+                self.cancels
+                    .watch(*new_tag, options.cancellation.clone(), options.deadline);
+            }
+        }
+
+        if let (Some(tag), Ok(mut monitor)) = (tag, self.monitor.lock()) {
+            monitor.register(tag, command, is_streaming);
         }
 
         if let Some(mut_tag) = future_tag {
@@ -121,19 +307,77 @@ impl<S: State> MikrotikAPI<S> {
         debug!("do_call: {}", command);
         trace!("do_call: {:?}", attributes);
 
-        self.send_command(command, attributes.as_slice())
-            .await
-            .unwrap();
+        self.send_command(command, attributes.as_slice()).await?;
 
-        cloned_call
+        if let (Some(tag), Ok(monitor)) = (tag, self.monitor.lock()) {
+            monitor.audit(AuditEvent::CommandSent {
+                tag,
+                command: command.to_owned(),
+                attributes: redact(&attributes),
+            });
+        }
+
+        Ok(cloned_call)
     }
 }
 
 impl MikrotikAPI<Disconnected> {
-    pub(crate) fn new(socket: TcpStream) -> Self {
+    pub(crate) fn new(socket: TcpStream, options: ConnectOptions) -> Self {
         let (sock_read, sock_write) = socket.into_split();
 
-        let output = BufWriter::new(sock_write);
+        Self::from_halves(Box::new(sock_read), Box::new(sock_write), options)
+    }
+
+    /// Runs the API over any byte stream instead of a TCP connection, such as a tunnel or an in-memory pipe.
+    /// The returned object is in a Disconnected state
+    pub fn from_transport<T: Transport>(transport: T) -> Self {
+        Self::from_transport_with_options(transport, ConnectOptions::default())
+    }
+
+    /// Same as `from_transport`, with specific connection options
+    pub fn from_transport_with_options<T: Transport>(
+        transport: T,
+        options: ConnectOptions,
+    ) -> Self {
+        let (reader, writer) = transport::split(transport);
+
+        Self::from_halves(reader, writer, options)
+    }
+
+    /// Same as `from_transport`, except that no task is spawned: the returned `Connection` reads the replies of the router
+    /// and must be polled by the caller, ie on a custom executor, for any call to complete
+    pub fn new_manual<T: Transport>(transport: T) -> (Self, Connection) {
+        Self::new_manual_with_options(transport, ConnectOptions::default())
+    }
+
+    /// Same as `new_manual`, with specific connection options
+    pub fn new_manual_with_options<T: Transport>(
+        transport: T,
+        options: ConnectOptions,
+    ) -> (Self, Connection) {
+        let (reader, writer) = transport::split(transport);
+
+        Self::from_halves_manual(reader, writer, options)
+    }
+
+    fn from_halves(
+        sock_read: TransportReader,
+        sock_write: TransportWriter,
+        options: ConnectOptions,
+    ) -> Self {
+        let (api, connection) = Self::from_halves_manual(sock_read, sock_write, options);
+
+        tokio::task::spawn(connection);
+
+        api
+    }
+
+    fn from_halves_manual(
+        sock_read: TransportReader,
+        sock_write: TransportWriter,
+        options: ConnectOptions,
+    ) -> (Self, Connection) {
+        let output = Arc::new(tokio::sync::Mutex::new(BufWriter::new(sock_write)));
 
         let tag_map: TagMap = HashMap::new();
 
@@ -148,45 +392,156 @@ impl MikrotikAPI<Disconnected> {
 
         let map_clone = shared_map.clone();
 
-        tokio::task::spawn(event_loop(sock_read, map_clone));
+        let monitor: SharedMonitor = Arc::new(Mutex::new(ReplyMonitor::default()));
+
+        let closed = ConnectionClosed::default();
 
-        Self {
+        let cancels = Arc::new(Cancels::default());
+
+        let connection = Connection {
+            driver: Box::pin(event_loop(
+                sock_read,
+                map_clone,
+                monitor.clone(),
+                closed.clone(),
+                output.clone(),
+                cancels.clone(),
+                options.memory_budget.map(|budget| Budget {
+                    budget,
+                    cancels: cancels.clone(),
+                }),
+            )),
+        };
+
+        let api = Self {
             tag_iter,
             output,
+            sentence: SentenceBuilder::new(),
             tag_map: shared_map,
+            closed,
+            cancels,
+            monitor,
+            policy: Policy::default(),
+            options,
+            user: None,
             _state: Disconnected,
-        }
+        };
+
+        (api, connection)
     }
 
-    /// Authenticate user with its login & password
+    /// Authenticate user with its login & password.
+    /// The login method (plain text or challenge) follows `ConnectOptions::login`, detected from the router's answer by default
     pub async fn authenticate(
         mut self,
         login: &str,
         password: &str,
     ) -> Result<MikrotikAPI<Authenticated>, Error> {
-        let success = self
-            .do_call(
-                "/login",
-                Some(&[("name", login), ("password", password)]),
-                EmptyCall::new(),
-                None,
-            )
-            .await;
-
         use Response::*;
-        match success.await {
+        let response = self.login(login, password).await?;
+
+        let event = match &response {
+            Trap { message, .. } => AuditEvent::LoginFailed {
+                user: login.to_owned(),
+                message: message.clone(),
+            },
+            _ => AuditEvent::Login {
+                user: login.to_owned(),
+            },
+        };
+
+        if let Ok(monitor) = self.monitor.lock() {
+            monitor.audit(event);
+        }
+
+        match response {
             Done | Reply(_) => Ok(MikrotikAPI {
                 output: self.output,
+                sentence: self.sentence,
                 tag_map: self.tag_map,
+                closed: self.closed,
+                cancels: self.cancels,
                 tag_iter: self.tag_iter,
+                monitor: self.monitor,
+                policy: self.policy,
+                options: self.options,
+                user: Some(login.to_owned()),
                 _state: Authenticated,
             }),
 
             Trap { message, .. } => Err(Error::Remote(message)),
 
-            Fatal => panic!("Fatal error."),
+            Fatal => Err(match self.closed.get() {
+                Some(reason) => closed_error(reason),
+                None => Error::ConnectionLost(FATAL.to_owned()),
+            }),
         }
     }
+
+    async fn login(&mut self, login: &str, password: &str) -> Result<Response<String>, Error> {
+        let challenge = match self.options.login {
+            LoginMethod::Auto | LoginMethod::Plaintext => {
+                let response = self
+                    .do_call(
+                        "/login",
+                        Some(&[("name", login), ("password", password)]),
+                        LoginCall::new(),
+                        None,
+                    )
+                    .await?
+                    .await?;
+
+                match (self.options.login, response) {
+                    // Routers older than 6.43 ignore the credentials and answer with a challenge
+                    (LoginMethod::Auto, Response::Reply(challenge)) => challenge,
+
+                    (_, response) => return Ok(response),
+                }
+            }
+
+            LoginMethod::Challenge => {
+                match self
+                    .do_call("/login", None, LoginCall::new(), None)
+                    .await?
+                    .await?
+                {
+                    Response::Reply(challenge) => challenge,
+
+                    Response::Done => {
+                        return Err(Error::Remote("no challenge in /login reply".to_owned()))
+                    }
+
+                    response => return Ok(response),
+                }
+            }
+        };
+
+        debug!("login: answering challenge");
+
+        let response = challenge_response(password, &challenge)?;
+
+        self.do_call(
+            "/login",
+            Some(&[("name", login), ("response", &response)]),
+            LoginCall::new(),
+            None,
+        )
+        .await?
+        .await
+    }
+}
+
+/// Answer to the challenge of pre-6.43 routers: `00` followed by the MD5 hash of a null byte, the password and the challenge
+fn challenge_response(password: &str, challenge: &str) -> Result<String, Error> {
+    let challenge = hex::decode(challenge)
+        .map_err(|_| Error::Remote(format!("invalid login challenge: {}", challenge)))?;
+
+    let mut context = md5::Context::new();
+    context.consume([0u8]);
+    context.consume(password.as_bytes());
+    context.consume(&challenge);
+
+    Ok(format!("00{}", hex::encode(context.compute().0)))
 }
 
 impl MikrotikAPI<Authenticated> {
@@ -198,35 +553,195 @@ impl MikrotikAPI<Authenticated> {
             OneShotCall::<SystemResources>::new(),
             None,
         )
-        .await
-        .await
+        .await?
+        .await?
         .into()
     }
 
+    /// Get the model, serial number and RouterBOOT versions of the router, from `/system/routerboard/print`
+    pub async fn routerboard(&mut self) -> Result<Routerboard, Error> {
+        self.generic_oneshot_call("/system/routerboard/print", None)
+            .await
+    }
+
+    /// Get the license level of the router, from `/system/license/print`
+    pub async fn license(&mut self) -> Result<License, Error> {
+        self.generic_oneshot_call("/system/license/print", None)
+            .await
+    }
+
+    /// Whether the RouterBOOT lags behind the one bundled with RouterOS, see `Routerboard::upgrade_pending`.
+    /// Always false on x86 and CHR
+    pub async fn firmware_upgrade_pending(&mut self) -> Result<bool, Error> {
+        Ok(self.routerboard().await?.upgrade_pending())
+    }
+
     /// List interfaces and their state in great details
     pub async fn interfaces(&mut self) -> Result<Vec<Interface>, Error> {
         self.do_call("/interface/print", None, ArrayListCall::new(), None)
-            .await
-            .await
+            .await?
+            .await?
             .into_iter()
             .collect::<Response<Vec<Interface>>>()
             .into()
     }
 
+    /// Sets the MTU of the interface `name_or_id` (a name or an `.id` such as `*1`).
+    /// Fails with `Error::InvalidArgument` if the interface is unknown or `mtu` exceeds its L2 MTU
+    pub async fn set_interface_mtu(
+        &mut self,
+        name_or_id: &str,
+        mtu: InterfaceMTU,
+    ) -> Result<(), Error> {
+        let key = if name_or_id.starts_with('*') {
+            "?.id"
+        } else {
+            "?name"
+        };
+
+        let interface = self
+            .generic_array_call::<Interface>("/interface/print", Some(&[(key, name_or_id)]))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::InvalidArgument(format!("no such interface: {}", name_or_id)))?;
+
+        if let (InterfaceMTU::Value(value), Some(l2mtu)) = (mtu, interface.l2mtu) {
+            if value > l2mtu {
+                return Err(Error::InvalidArgument(format!(
+                    "MTU {} of {} exceeds its L2 MTU {}",
+                    value, interface.name, l2mtu
+                )));
+            }
+        }
+
+        let mtu = mtu.to_string();
+
+        self.empty_call(
+            "/interface/set",
+            Some(&[("numbers", name_or_id), ("mtu", &mtu)]),
+        )
+        .await
+    }
+
+    /// Get the configuration of the management service `name` (`api`, `api-ssl`, `ssh`, `www`, ...)
+    pub async fn ip_service(&mut self, name: &str) -> Result<Option<IpService>, Error> {
+        let services = self
+            .generic_array_call::<IpService>("/ip/service/print", Some(&[("?name", name)]))
+            .await?;
+
+        Ok(services.into_iter().next())
+    }
+
+    /// Change the configuration of the management service `name`: port, allowed addresses, TLS certificate, ...
+    pub async fn set_ip_service(
+        &mut self,
+        name: &str,
+        settings: &IpServiceSettings,
+    ) -> Result<(), Error> {
+        self.set::<IpService, _>(name, settings).await
+    }
+
+    /// Get the state of MikroTik's cloud service: DDNS registration, public address, DNS name
+    pub async fn ip_cloud(&mut self) -> Result<IpCloud, Error> {
+        self.generic_oneshot_call::<IpCloud>("/ip/cloud/print", None)
+            .await
+    }
+
+    /// Change the settings of MikroTik's cloud service, ie enable DDNS
+    pub async fn set_ip_cloud(&mut self, settings: &IpCloudSettings) -> Result<(), Error> {
+        let attributes = ser::to_attributes(settings)?;
+
+        let attributes: Vec<(&str, &str)> = attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+
+        self.empty_call("/ip/cloud/set", Some(&attributes)).await
+    }
+
+    /// Ask the cloud service to register the current public address right away
+    pub async fn force_ddns_update(&mut self) -> Result<(), Error> {
+        self.empty_call("/ip/cloud/force-update", None).await
+    }
+
+    async fn empty_call(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+    ) -> Result<(), Error> {
+        let response = self
+            .do_call(command, attributes, EmptyCall::new(), None)
+            .await?
+            .await?;
+
+        done_or_trap(response)
+    }
+
     /// Listen to user activity in terms of login/logout
     pub async fn active_users(
         &mut self,
         tag: &mut u16,
-    ) -> impl Stream<Item = Response<ActiveUser>> {
-        self.do_call("/user/active/listen", None, StreamingCall::new(), Some(tag))
+    ) -> Result<impl Stream<Item = Response<ActiveUser>>, Error> {
+        self.active_users_with_filter(tag, &ActiveUserFilter::default())
             .await
     }
 
+    /// Same as `active_users`, only reporting the sessions selected by `filter`.
+    /// Logouts are reported unless the matching login was filtered out.
+    pub async fn active_users_with_filter(
+        &mut self,
+        tag: &mut u16,
+        filter: &ActiveUserFilter,
+    ) -> Result<impl Stream<Item = Response<ActiveUser>>, Error> {
+        // Logout events only hold an id: filtering must happen on our side
+        let stream = self
+            .do_call("/user/active/listen", None, StreamingCall::new(), Some(tag))
+            .await?;
+
+        let filter = filter.clone();
+        let user = self.user.clone();
+        let mut rejected = HashSet::new();
+
+        Ok(stream.filter(move |item| {
+            let keep = match item {
+                Response::Reply(ActiveUser::Dead(id)) => !rejected.remove(id),
+
+                Response::Reply(active @ ActiveUser::Active { id, .. }) => {
+                    let keep = filter.matches(active, user.as_deref());
+                    if !keep {
+                        rejected.insert(*id);
+                    }
+                    keep
+                }
+
+                _ => true,
+            };
+
+            future::ready(keep)
+        }))
+    }
+
+    /// Sessions currently open and selected by `filter`, filtering being done by the router where possible
+    pub async fn current_active_users(
+        &mut self,
+        filter: &ActiveUserFilter,
+    ) -> Result<Vec<ActiveUser>, Error> {
+        let users = self
+            .generic_array_call::<ActiveUser>("/user/active/print", Some(&filter.query()))
+            .await?;
+
+        Ok(users
+            .into_iter()
+            .filter(|user| filter.matches(user, self.user.as_deref()))
+            .collect())
+    }
+
     /// Listen to interface changes (up, down, ...)
     pub async fn interfaces_changes(
         &mut self,
         tag: &mut u16,
-    ) -> impl Stream<Item = Response<InterfaceChange>> {
+    ) -> Result<impl Stream<Item = Response<InterfaceChange>>, Error> {
         self.do_call("/interface/listen", None, StreamingCall::new(), Some(tag))
             .await
     }
@@ -240,9 +755,36 @@ impl MikrotikAPI<Authenticated> {
     where
         T: DeserializeOwned + Debug + Sync + Send + 'static,
     {
-        self.do_call(command, attributes, OneShotCall::<T>::new(), None)
-            .await
+        self.generic_oneshot_call_with_options(command, attributes, &CallOptions::default())
             .await
+    }
+
+    /// Same as `generic_oneshot_call`, with specific call options
+    pub async fn generic_oneshot_call_with_options<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        options: &CallOptions,
+    ) -> Result<T, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        let mut tag = 0;
+
+        check_proplist::<T>(attributes)?;
+
+        let call = self
+            .do_call_with_options(
+                command,
+                attributes,
+                OneShotCall::<T>::new(),
+                Some(&mut tag),
+                options,
+            )
+            .await?;
+
+        await_call(call, tag, options.clone(), self.cancels.clone())
+            .await??
             .into()
     }
 
@@ -255,12 +797,85 @@ impl MikrotikAPI<Authenticated> {
     where
         T: DeserializeOwned + Debug + Sync + Send + 'static,
     {
-        self.do_call(command, attributes, ArrayListCall::new(), None)
+        self.generic_array_call_with_options(command, attributes, &CallOptions::default())
             .await
+    }
+
+    /// Same as `generic_array_call`, with specific call options.
+    /// The connection stays borrowed until the last reply: see `generic_array_call_detached` to make other calls meanwhile
+    pub async fn generic_array_call_with_options<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        options: &CallOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        self.generic_array_call_detached(command, attributes, options)
+            .await?
             .await
-            .into_iter()
-            .collect::<Response<Vec<T>>>()
-            .into()
+    }
+
+    /// Same as `generic_array_call_with_options`, returning once the command is sent: the replies are then awaited
+    /// without borrowing the connection, which serves other calls meanwhile. Large prints should use `Priority::Bulk`,
+    /// for interactive calls made in the meantime not to wait behind them.
+    ///
+    /// ```no_run
+    /// # async fn example(mut api: mikrotik_api::MikrotikAPI<mikrotik_api::Authenticated>) -> Result<(), mikrotik_api::Error> {
+    /// use std::collections::HashMap;
+    ///
+    /// use mikrotik_api::{CallOptions, Priority};
+    ///
+    /// let options = CallOptions {
+    ///     priority: Priority::Bulk,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let connections = api
+    ///     .generic_array_call_detached::<HashMap<String, String>>("/ip/firewall/connection/print", None, &options)
+    ///     .await?;
+    ///
+    /// let identity = api
+    ///     .generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+    ///     .await?;
+    ///
+    /// let connections = connections.await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn generic_array_call_detached<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        options: &CallOptions,
+    ) -> Result<impl Future<Output = Result<Vec<T>, Error>> + Send + 'static, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        let mut tag = 0;
+
+        check_proplist::<T>(attributes)?;
+
+        let call = self
+            .do_call_with_options(
+                command,
+                attributes,
+                ArrayListCall::new(),
+                Some(&mut tag),
+                options,
+            )
+            .await?;
+
+        let awaited = await_call(call, tag, options.clone(), self.cancels.clone());
+
+        Ok(async move {
+            awaited
+                .await??
+                .into_iter()
+                .collect::<Response<Vec<T>>>()
+                .into()
+        })
     }
 
     /// Allows to generate a stream of events for `listen` endpoints.
@@ -270,72 +885,206 @@ impl MikrotikAPI<Authenticated> {
         command: &str,
         attributes: Option<&[(&str, &str)]>,
         tag: &mut u16,
-    ) -> impl Stream<Item = Response<T>>
+    ) -> Result<impl Stream<Item = Response<T>>, Error>
     where
         T: DeserializeOwned + Debug + Sync + Send + 'static,
     {
-        self.do_call(command, attributes, StreamingCall::new(), Some(tag))
+        self.generic_streaming_call_with_options(command, attributes, tag, &CallOptions::default())
             .await
     }
 
+    /// Same as `generic_streaming_call`, with specific call options
+    pub async fn generic_streaming_call_with_options<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        tag: &mut u16,
+        options: &CallOptions,
+    ) -> Result<impl Stream<Item = Response<T>>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        check_proplist::<T>(attributes)?;
+
+        self.do_call_with_options(
+            command,
+            attributes,
+            StreamingCall::<T>::with_buffer(options.stream_buffer),
+            Some(tag),
+            options,
+        )
+        .await
+    }
+
+    /// Same as `generic_streaming_call`, each item being wrapped in a `StreamItem`
+    /// holding its sequence number and the time it was received at
+    pub async fn generic_sequenced_streaming_call<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        tag: &mut u16,
+    ) -> Result<impl Stream<Item = StreamItem<Response<T>>>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        check_proplist::<T>(attributes)?;
+
+        let call = self
+            .do_call_with_options(
+                command,
+                attributes,
+                StreamingCall::new(),
+                Some(tag),
+                &CallOptions::default(),
+            )
+            .await?;
+
+        Ok(call.sequenced())
+    }
+
     /// Calls `/cancel` on a specific tag.
     /// Primary usage is to stop `listen` commands
     pub async fn cancel(&mut self, tag: u16) -> Response<()> {
-        self.do_call(
-            "/cancel",
-            Some(&[("tag", tag.to_string().as_str())]),
-            EmptyCall::new(),
-            None,
-        )
-        .await
-        .await
+        let call = self
+            .do_call(
+                "/cancel",
+                Some(&[("tag", tag.to_string().as_str())]),
+                EmptyCall::new(),
+                None,
+            )
+            .await;
+
+        let error = match call {
+            Ok(call) => match call.await {
+                Ok(response) => return response,
+                Err(e) => e,
+            },
+
+            Err(e) => e,
+        };
+
+        Response::Trap {
+            category: None,
+            message: error.to_string(),
+        }
     }
 }
 
-fn encode_len(data: &str) -> Vec<u8> {
-    let mut res = vec![];
+/// Waits for `call` to complete, cancelling it if the deadline of `options` passes or its cancellation token fires first.
+/// Borrows nothing from the connection, for the replies of a call to be awaited while others are made
+async fn await_call<F: Future>(
+    call: F,
+    tag: u16,
+    options: CallOptions,
+    cancels: Arc<Cancels>,
+) -> Result<F::Output, Error> {
+    if options.deadline.is_none() && options.cancellation.is_none() {
+        return Ok(call.await);
+    }
+
+    let deadline = async {
+        match options.deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => future::pending().await,
+        }
+    };
+
+    let cancelled = async {
+        match &options.cancellation {
+            Some(token) => token.cancelled().await,
+            None => future::pending().await,
+        }
+    };
+
+    let error = tokio::select! {
+        output = call => return Ok(output),
+
+        _ = deadline => {
+            debug!("await_call: deadline exceeded for tag {}, cancelling.", tag);
+            Error::DeadlineExceeded
+        }
+
+        _ = cancelled => {
+            debug!("await_call: tag {} cancelled by its token.", tag);
+            Error::Cancelled
+        }
+    };
 
-    let len = data.len();
+    // The router may be the reason the call is late: /cancel is sent without waiting for its answer
+    cancels.unwatch(tag);
+    cancels.forget(tag);
+    cancels.cancel(tag);
+
+    Err(error)
+}
+
+/// Fails if the `.proplist` among `attributes` leaves out fields `T` requires, rather than failing every reply
+fn check_proplist<T: DeserializeOwned>(attributes: Option<&[(&str, &str)]>) -> Result<(), Error> {
+    let proplist = attributes
+        .unwrap_or_default()
+        .iter()
+        .find(|(key, _)| key.trim_start_matches('=') == ".proplist");
+
+    let Some((_, proplist)) = proplist else {
+        return Ok(());
+    };
+
+    let present: Vec<&str> = proplist.split(',').map(str::trim).collect();
+
+    match de::missing_fields::<T>(&present) {
+        Some(missing) if !missing.is_empty() => Err(Error::ProplistMismatch {
+            missing: missing.into_iter().map(String::from).collect(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Length prefix of `data`, which the protocol cannot frame past 4 GiB
+pub(crate) fn encode_len(data: &str) -> Result<Vec<u8>, Error> {
+    let len = u32::try_from(data.len()).map_err(|_| {
+        Error::InvalidArgument(format!("word of {} bytes, 4 GiB at most", data.len()))
+    })?;
+
+    Ok(len_prefix(len))
+}
+
+/// Length prefix of a word, most significant byte first as per the spec. Bytes are taken with shifts rather than
+/// `to_*_bytes` so that the result does not depend on the endianness nor the pointer width of the target
+pub(crate) fn len_prefix(len: u32) -> Vec<u8> {
+    let byte = |shift: u32| (len >> shift) as u8;
 
     if len <= 0x7F {
-        res.push(len as u8)
+        vec![byte(0)]
     } else if len <= 0x3FFF {
-        let bytes = ((len as u16) | 0x8000).to_ne_bytes();
-        res.copy_from_slice(&bytes);
+        vec![byte(8) | 0x80, byte(0)]
     } else if len <= 0x1FFFFF {
-        let bytes = ((len as u32) | 0xC00000).to_ne_bytes();
-        res.copy_from_slice(&bytes[..3]);
+        vec![byte(16) | 0xC0, byte(8), byte(0)]
     } else if len <= 0xFFFFFFF {
-        let bytes = ((len as u32) | 0xE0000000).to_ne_bytes();
-        res.copy_from_slice(&bytes);
-    } else if len >= 0x10000000 {
-        let bytes = (len as u32).to_ne_bytes();
-        res.copy_from_slice(&bytes);
-        res.insert(0, 0xF0);
+        vec![byte(24) | 0xE0, byte(16), byte(8), byte(0)]
+    } else {
+        vec![0xF0, byte(24), byte(16), byte(8), byte(0)]
     }
-
-    res
 }
 
-fn encode_word(word: &str) -> Vec<u8> {
-    let mut res = encode_len(word);
+fn encode_word(word: &str) -> Result<Vec<u8>, Error> {
+    let mut res = encode_len(word)?;
 
     res.extend_from_slice(word.as_bytes());
 
-    res
+    Ok(res)
 }
 
-fn encode_sentence<S: AsRef<str>>(words: &[S]) -> Vec<u8> {
+pub(crate) fn encode_sentence<S: AsRef<str>>(words: &[S]) -> Result<Vec<u8>, Error> {
     let mut res = vec![];
 
     for w in words {
-        res.append(&mut encode_word(w.as_ref()));
+        res.append(&mut encode_word(w.as_ref())?);
     }
 
     //Empty word to close sentence.
     res.push(0x00);
 
-    res
+    Ok(res)
 }
 
 fn next_tag(tag_iter: &mut dyn Iterator<Item = u16>, unlocked_map: &MutexGuard<TagMap>) -> u16 {
@@ -349,3 +1098,680 @@ fn next_tag(tag_iter: &mut dyn Iterator<Item = u16>, unlocked_map: &MutexGuard<T
 
     unreachable!()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        ops::ControlFlow,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use futures::StreamExt;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        bench_support::{decode_sentence, encode_sentence},
+        mock::{MockReply, MockRouter},
+    };
+
+    use super::{
+        challenge_response, lock_tags, CallOptions, ConnectOptions, Error, LoginMethod,
+        MikrotikAPI, Response,
+    };
+
+    /// Answers `/interface/print` with two `!re` and `/system/identity/print` with one.
+    /// `/log/listen` sends a single event and never gets its `!done`, until cancelled
+    fn mock_router() -> MockRouter {
+        MockRouter::new()
+            .on(
+                "/interface/print",
+                MockReply::new()
+                    .re(&[("name", "ether1")])
+                    .re(&[("name", "ether2")]),
+            )
+            .on(
+                "/system/identity/print",
+                MockReply::new().re(&[("name", "MikroTik")]),
+            )
+            .on(
+                "/log/listen",
+                MockReply::new().re(&[("name", "MikroTik")]).endless(),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_from_transport() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let identity = api
+            .generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+            .await
+            .unwrap();
+
+        assert_eq!(Some("MikroTik"), identity.get("name").map(String::as_str));
+    }
+
+    #[tokio::test]
+    async fn test_new_manual() {
+        let (api, connection) = MikrotikAPI::new_manual(mock_router().spawn());
+
+        let calls = async {
+            let mut api = api.authenticate("admin", "").await.unwrap();
+
+            api.generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+                .await
+                .unwrap()
+        };
+
+        // Driven by this task alone, nothing being spawned
+        let identity = tokio::select! {
+            identity = calls => identity,
+            _ = connection => panic!("connection closed"),
+        };
+
+        assert_eq!(Some("MikroTik"), identity.get("name").map(String::as_str));
+    }
+
+    #[tokio::test]
+    async fn test_proplist_mismatch() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Named {
+            name: String,
+            mtu: u16,
+            comment: Option<String>,
+        }
+
+        let result = api
+            .generic_array_call::<Named>("/interface/print", Some(&[("=.proplist", "name")]))
+            .await;
+
+        let Err(Error::ProplistMismatch { missing }) = result else {
+            panic!("expected a proplist mismatch, got {:?}", result);
+        };
+        assert_eq!(vec!["mtu"], missing);
+
+        // Nothing was sent, the connection is still in sync
+        let interfaces = api
+            .generic_array_call::<HashMap<String, String>>(
+                "/interface/print",
+                Some(&[("=.proplist", "name")]),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(2, interfaces.len());
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode() {
+        let reply = MockReply::new()
+            .re(&[(".id", "*1"), ("name", "ether1")])
+            .re(&[(".id", "*2"), ("name", "ether2"), ("l2mtu", "1598")]);
+
+        let mut api = MockRouter::new()
+            .on("/interface/print", reply)
+            .connect()
+            .await
+            .unwrap();
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Named {
+            name: String,
+        }
+
+        let options = CallOptions::default().with_strict();
+
+        let result = api
+            .generic_array_call_with_options::<Named>("/interface/print", None, &options)
+            .await;
+
+        let Err(Error::UnknownFields { unknown }) = result else {
+            panic!("expected unknown fields, got {:?}", result);
+        };
+        assert_eq!(vec!["l2mtu"], unknown);
+
+        // Only the call failed, and unknown properties are skipped as usual without strict mode
+        let interfaces = api
+            .generic_array_call::<Named>("/interface/print", None)
+            .await
+            .unwrap();
+
+        assert_eq!(2, interfaces.len());
+    }
+
+    #[tokio::test]
+    async fn test_local_failures_not_read_from_traps() {
+        // Traps reading like failures found by the library itself are still the router's own
+        let mut api = MockRouter::new()
+            .on(
+                "/system/identity/print",
+                MockReply::new().trap("memory budget of the connection exceeded"),
+            )
+            .on(
+                "/interface/print",
+                MockReply::new().trap("strict mode, unknown properties: l2mtu"),
+            )
+            .connect()
+            .await
+            .unwrap();
+
+        let options = CallOptions::default().with_strict();
+
+        let identity = api
+            .generic_oneshot_call_with_options::<HashMap<String, String>>(
+                "/system/identity/print",
+                None,
+                &options,
+            )
+            .await;
+        assert!(matches!(identity, Err(Error::Remote(_))));
+
+        let interfaces = api
+            .generic_array_call_with_options::<HashMap<String, String>>(
+                "/interface/print",
+                None,
+                &options,
+            )
+            .await;
+        assert!(matches!(interfaces, Err(Error::Remote(_))));
+    }
+
+    #[tokio::test]
+    async fn test_pre_filter() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let options = CallOptions::default().with_pre_filter(|sentence| {
+            match sentence.iter().any(|word| word == "=name=ether2") {
+                true => ControlFlow::Break(()),
+                false => ControlFlow::Continue(()),
+            }
+        });
+
+        let interfaces = api
+            .generic_array_call_with_options::<HashMap<String, String>>(
+                "/interface/print",
+                None,
+                &options,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(1, interfaces.len());
+        assert_eq!("ether1", interfaces[0]["name"]);
+    }
+
+    #[tokio::test]
+    async fn test_progress() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let reported = Arc::new(Mutex::new(vec![]));
+        let progress = reported.clone();
+
+        let options = CallOptions::default()
+            .with_progress(move |received| progress.lock().unwrap().push(received))
+            .with_pre_filter(
+                |sentence| match sentence.iter().any(|w| w == "=name=ether2") {
+                    true => ControlFlow::Break(()),
+                    false => ControlFlow::Continue(()),
+                },
+            );
+
+        let interfaces = api
+            .generic_array_call_with_options::<HashMap<String, String>>(
+                "/interface/print",
+                None,
+                &options,
+            )
+            .await
+            .unwrap();
+
+        // Dropped replies are counted too
+        assert_eq!(1, interfaces.len());
+        assert_eq!(vec![1, 2], *reported.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_connection_lost() {
+        let (client, mut router) = tokio::io::duplex(4096);
+
+        tokio::spawn(async move {
+            let mut chunk = [0u8; 1024];
+
+            let read = router.read(&mut chunk).await.unwrap();
+            let (words, _) = decode_sentence(&chunk[..read]).unwrap();
+
+            let tag = words.iter().find(|w| w.starts_with(".tag=")).unwrap();
+
+            let done = encode_sentence(&["!done", tag]).unwrap();
+            router.write_all(&done).await.unwrap();
+
+            // Hang up in the middle of the next call
+            let _ = router.read(&mut chunk).await;
+        });
+
+        let mut api = MikrotikAPI::from_transport(client)
+            .authenticate("admin", "")
+            .await
+            .unwrap();
+
+        let call = api
+            .generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+            .await;
+        assert!(matches!(call, Err(Error::ConnectionLost(_))));
+
+        let call = api
+            .generic_array_call::<HashMap<String, String>>("/interface/print", None)
+            .await;
+        assert!(matches!(call, Err(Error::ConnectionLost(_))));
+    }
+
+    /// Random text, biased towards what could trip the encoding: separators, blanks, non-ASCII and long runs
+    fn random_text(rng: &mut StdRng, first: &[char], rest: &[char]) -> String {
+        let len = match rng.gen_range(0..20) {
+            0 => rng.gen_range(100..400),
+            1 => rng.gen_range(0x3FFF..0x4100),
+            _ => rng.gen_range(1..24),
+        };
+
+        (0..len)
+            .map(|i| {
+                let pool = if i == 0 { first } else { rest };
+                pool[rng.gen_range(0..pool.len())]
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_attribute_round_trip() {
+        const KEY_START: &[char] = &['a', 'k', 'z', 'é', '日'];
+        const KEY: &[char] = &['a', 'z', '0', '9', '-', '_', '.', '/', 'é', '日', '🦀'];
+        const VALUE: &[char] = &[
+            'a', 'Z', '5', ' ', '=', ',', ';', '"', '\'', '\\', '\n', '\r', '\t', '\0', '?', '.',
+            '!', 'é', 'ß', '日', '🦀', '\u{7f}',
+        ];
+
+        let router = MockRouter::new().on("/echo", MockReply::new().echo());
+
+        let mut api = router.connect().await.unwrap();
+
+        let mut rng = StdRng::seed_from_u64(0x3747);
+
+        for _ in 0..200 {
+            let sent: HashMap<String, String> = (0..rng.gen_range(1..6))
+                .map(|_| {
+                    let key = random_text(&mut rng, KEY_START, KEY);
+                    let value = match rng.gen_bool(0.1) {
+                        true => String::new(),
+                        false => random_text(&mut rng, VALUE, VALUE),
+                    };
+
+                    (key, value)
+                })
+                .collect();
+
+            let attributes: Vec<(&str, &str)> = sent
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect();
+
+            let received = api
+                .generic_oneshot_call::<HashMap<String, String>>("/echo", Some(&attributes))
+                .await
+                .unwrap();
+
+            assert_eq!(sent, received);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_n_and_collect_for() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let mut tag = 0;
+        let stream = api
+            .generic_streaming_call::<HashMap<String, String>>("/log/listen", None, &mut tag)
+            .await
+            .unwrap();
+
+        let replies = api.collect_n(stream, tag, 1).await.unwrap();
+        assert_eq!(1, replies.len());
+
+        let stream = api
+            .generic_streaming_call::<HashMap<String, String>>("/log/listen", None, &mut tag)
+            .await
+            .unwrap();
+
+        // The router keeps the stream open without sending anything else
+        let replies = api
+            .collect_for(stream, tag, Duration::from_millis(50))
+            .await
+            .unwrap();
+        assert_eq!(1, replies.len());
+
+        // Both listens were cancelled, the connection is still usable
+        api.generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_collect_n_stalled_router() {
+        // The router never answers /cancel
+        let router = mock_router().on("/cancel", MockReply::new().endless());
+        let received = router.received();
+        let mut api = router.connect().await.unwrap();
+
+        let mut tag = 0;
+        let stream = api
+            .generic_streaming_call::<HashMap<String, String>>("/log/listen", None, &mut tag)
+            .await
+            .unwrap();
+
+        let replies = tokio::time::timeout(Duration::from_secs(5), api.collect_n(stream, tag, 1))
+            .await
+            .expect("sampling waited on /cancel")
+            .unwrap();
+        assert_eq!(1, replies.len());
+
+        while !received.contains("/cancel") {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequenced_stream() {
+        let events = (0..50).fold(MockReply::new(), |reply, i| {
+            reply.re(&[("message", &i.to_string())])
+        });
+
+        let mut api = MockRouter::new()
+            .on("/log/listen", events.split_writes(7))
+            .connect()
+            .await
+            .unwrap();
+
+        let mut tag = 0;
+
+        let items: Vec<_> = api
+            .generic_sequenced_streaming_call::<HashMap<String, String>>(
+                "/log/listen",
+                None,
+                &mut tag,
+            )
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        // Numbered in the order received, without gaps
+        let seqs: Vec<u64> = items.iter().map(|item| item.seq).collect();
+        assert_eq!((0..50).collect::<Vec<_>>(), seqs);
+
+        assert!(items
+            .windows(2)
+            .all(|pair| pair[0].received_at <= pair[1].received_at));
+
+        for (i, item) in items.iter().enumerate() {
+            match &item.value {
+                Response::Reply(event) => assert_eq!(i.to_string(), event["message"]),
+                other => panic!("unexpected item: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_token() {
+        let router = MockRouter::new()
+            .on("/log/listen", MockReply::new().endless())
+            .on(
+                "/interface/listen",
+                MockReply::new().re(&[("name", "ether1")]).endless(),
+            );
+        let received = router.received();
+
+        let mut api = router.connect().await.unwrap();
+
+        let token = CancellationToken::new();
+        let options = CallOptions::default().with_cancellation(token.clone());
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        // Never done, until cancelled
+        let call = api
+            .generic_oneshot_call_with_options::<HashMap<String, String>>(
+                "/log/listen",
+                None,
+                &options,
+            )
+            .await;
+        assert!(matches!(call, Err(Error::Cancelled)));
+
+        let token = CancellationToken::new();
+        let options = CallOptions::default().with_cancellation(token.clone());
+
+        let mut tag = 0;
+        let mut stream = Box::pin(
+            api.generic_streaming_call_with_options::<HashMap<String, String>>(
+                "/interface/listen",
+                None,
+                &mut tag,
+                &options,
+            )
+            .await
+            .unwrap(),
+        );
+
+        assert!(matches!(stream.next().await, Some(Response::Reply(_))));
+
+        token.cancel();
+        assert!(stream.next().await.is_none());
+        assert!(!lock_tags(&api.tag_map).contains_key(&tag));
+
+        // Sent by the event loop, although the connection is idle
+        let cancelled = format!("=tag={}", tag);
+        let sent = async {
+            while !received
+                .sentences()
+                .iter()
+                .any(|sentence| sentence[0] == "/cancel" && sentence.contains(&cancelled))
+            {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), sent)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stream_deadline() {
+        let router = MockRouter::new().on(
+            "/interface/listen",
+            MockReply::new().re(&[("name", "ether1")]).endless(),
+        );
+        let received = router.received();
+
+        let (api, connection) = MikrotikAPI::new_manual(router.spawn());
+
+        let calls = async {
+            let mut api = api.authenticate("admin", "").await.unwrap();
+
+            let options = CallOptions::default().with_timeout(Duration::from_millis(50));
+
+            let mut tag = 0;
+            let stream = api
+                .generic_streaming_call_with_options::<HashMap<String, String>>(
+                    "/interface/listen",
+                    None,
+                    &mut tag,
+                    &options,
+                )
+                .await
+                .unwrap();
+
+            let items: Vec<_> = stream.collect().await;
+
+            while !received.contains("/cancel") {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+
+            items
+        };
+
+        // The stream ends and /cancel is sent with nothing but the connection being polled
+        let items = tokio::select! {
+            items = calls => items,
+            _ = connection => panic!("connection closed"),
+            _ = tokio::time::sleep(Duration::from_secs(1)) => panic!("stream still running"),
+        };
+
+        assert_eq!(1, items.len());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_with_stalled_router() {
+        // Neither the command nor its /cancel are ever answered
+        let router = MockRouter::new()
+            .on("/interface/print", MockReply::new().endless())
+            .on("/cancel", MockReply::new().endless());
+
+        let mut api = router.connect().await.unwrap();
+
+        let options = CallOptions::default().with_timeout(Duration::from_millis(50));
+
+        let call = tokio::time::timeout(
+            Duration::from_secs(1),
+            api.generic_array_call_with_options::<HashMap<String, String>>(
+                "/interface/print",
+                None,
+                &options,
+            ),
+        )
+        .await;
+
+        assert!(matches!(call, Ok(Err(Error::DeadlineExceeded))));
+    }
+
+    /// Router older than 6.43: `/login` is answered with a challenge, logging in once it gets the expected response
+    fn pre_643_router() -> MockRouter {
+        MockRouter::new()
+            .on(
+                "/login",
+                MockReply::new().ret("ebddd18775b2b9e3b9d11e5f7f8cf8ec"),
+            )
+            .on_query(
+                "/login",
+                &["=response=00239de6cd9b4a0455e87620b7bcc4fd3e"],
+                MockReply::new(),
+            )
+    }
+
+    async fn login(router: MockRouter, method: LoginMethod) -> Result<Vec<Vec<String>>, Error> {
+        let received = router.received();
+
+        let options = ConnectOptions {
+            login: method,
+            ..Default::default()
+        };
+
+        MikrotikAPI::from_transport_with_options(router.spawn(), options)
+            .authenticate("admin", "P@ssw0rd")
+            .await?;
+
+        Ok(received
+            .sentences()
+            .into_iter()
+            .filter(|sentence| sentence[0] == "/login")
+            .collect())
+    }
+
+    fn has(sentence: &[String], word: &str) -> bool {
+        sentence.iter().any(|w| w == word)
+    }
+
+    #[tokio::test]
+    async fn test_login_auto() {
+        // Modern router: the password is enough
+        let logins = login(MockRouter::new(), LoginMethod::Auto).await.unwrap();
+
+        assert_eq!(1, logins.len());
+        assert!(has(&logins[0], "=password=P@ssw0rd"));
+
+        // Older router: the `=ret=` of the `!done` is detected as a challenge and answered
+        let logins = login(pre_643_router(), LoginMethod::Auto).await.unwrap();
+
+        assert_eq!(2, logins.len());
+        assert!(has(&logins[0], "=password=P@ssw0rd"));
+        assert!(has(&logins[1], "=name=admin"));
+        assert!(has(
+            &logins[1],
+            "=response=00239de6cd9b4a0455e87620b7bcc4fd3e"
+        ));
+        assert!(!logins[1].iter().any(|w| w.starts_with("=password=")));
+    }
+
+    #[tokio::test]
+    async fn test_login_plaintext() {
+        let logins = login(MockRouter::new(), LoginMethod::Plaintext)
+            .await
+            .unwrap();
+
+        assert_eq!(1, logins.len());
+        assert!(has(&logins[0], "=name=admin"));
+        assert!(has(&logins[0], "=password=P@ssw0rd"));
+
+        // The challenge of an older router is left unanswered
+        let logins = login(pre_643_router(), LoginMethod::Plaintext)
+            .await
+            .unwrap();
+
+        assert_eq!(1, logins.len());
+    }
+
+    #[tokio::test]
+    async fn test_login_challenge() {
+        let logins = login(pre_643_router(), LoginMethod::Challenge)
+            .await
+            .unwrap();
+
+        // The password never goes on the wire
+        assert_eq!(2, logins.len());
+        assert_eq!(
+            vec!["/login".to_owned()],
+            logins[0]
+                .iter()
+                .filter(|w| !w.starts_with(".tag="))
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        assert!(has(
+            &logins[1],
+            "=response=00239de6cd9b4a0455e87620b7bcc4fd3e"
+        ));
+
+        // Without a challenge to answer, the login fails
+        let result = login(MockRouter::new(), LoginMethod::Challenge).await;
+
+        assert!(matches!(result, Err(Error::Remote(_))));
+    }
+
+    #[test]
+    fn test_challenge_response() {
+        let response = challenge_response("P@ssw0rd", "ebddd18775b2b9e3b9d11e5f7f8cf8ec").unwrap();
+
+        assert_eq!("00239de6cd9b4a0455e87620b7bcc4fd3e", response);
+        assert!(challenge_response("P@ssw0rd", "not hex").is_err());
+    }
+}
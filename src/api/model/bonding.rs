@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::entity::RosEntity;
+
+use super::{MacAddress, RosId};
+
+/// An entry of `/interface/bonding`
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Bonding {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    pub name: String,
+
+    /// Bonded interfaces
+    pub slaves: Vec<String>,
+
+    /// `balance-rr`, `active-backup`, `802.3ad`, ...
+    pub mode: String,
+    pub primary: Option<String>,
+    pub link_monitoring: Option<String>,
+    pub lacp_rate: Option<String>,
+    pub transmit_hash_policy: Option<String>,
+    pub min_links: Option<u16>,
+
+    #[serde(skip_serializing)]
+    pub mac_address: Option<MacAddress>,
+    #[serde(skip_serializing)]
+    pub actual_mtu: Option<u16>,
+
+    #[serde(default, skip_serializing)]
+    pub running: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+impl RosEntity for Bonding {
+    const PATH: &'static str = "/interface/bonding";
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// Reply from `/interface/bonding/monitor`
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BondingMonitor {
+    pub mode: String,
+
+    /// Slaves currently carrying traffic (`active-ports` on v7)
+    #[serde(alias = "active-ports")]
+    pub active_slaves: Option<Vec<String>>,
+
+    /// Slaves on standby or down (`inactive-ports` on v7)
+    #[serde(alias = "inactive-ports")]
+    pub inactive_slaves: Option<Vec<String>>,
+
+    pub lacp_system_id: Option<String>,
+    pub lacp_system_priority: Option<u16>,
+    pub lacp_partner_system_id: Option<String>,
+}
+
+/// Reply from `/interface/bonding/monitor-slaves`, one per bonded interface
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BondingSlave {
+    pub interface: String,
+
+    #[serde(default)]
+    pub active: bool,
+
+    /// LACP state flags reported for this port
+    pub status: Option<String>,
+
+    pub actor_key: Option<u16>,
+    pub partner_sys_id: Option<String>,
+    pub partner_sys_priority: Option<u16>,
+    pub partner_key: Option<u16>,
+    pub partner_port_number: Option<u16>,
+}
+
+/// State of a bonding interface and of each of its slaves
+#[derive(Debug)]
+pub struct BondingStatus {
+    /// Bond-wide state, including the active slave(s) and LACP partner
+    pub monitor: BondingMonitor,
+
+    /// Per-slave state
+    pub slaves: Vec<BondingSlave>,
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::entity::RosEntity;
+
+use super::{MacAddress, RosId};
+
+/// An entry of `/interface/bridge/port`
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BridgePort {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    pub interface: String,
+    pub bridge: String,
+    pub pvid: Option<u16>,
+
+    #[serde(default, skip_serializing)]
+    pub inactive: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+impl RosEntity for BridgePort {
+    const PATH: &'static str = "/interface/bridge/port";
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// An entry of `/interface/vlan`
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Vlan {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    pub name: String,
+    pub vlan_id: u16,
+
+    /// Parent interface
+    pub interface: String,
+
+    #[serde(default, skip_serializing)]
+    pub running: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+impl RosEntity for Vlan {
+    const PATH: &'static str = "/interface/vlan";
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// An entry of `/interface/bridge/host`: a MAC address learned on a port of a bridge
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BridgeHost {
+    pub mac_address: MacAddress,
+
+    /// Port the address was learned on
+    pub on_interface: String,
+    pub bridge: String,
+
+    /// VLAN the address was learned in, with VLAN filtering enabled
+    pub vid: Option<u16>,
+
+    /// Address of the bridge itself
+    #[serde(default)]
+    pub local: bool,
+    #[serde(default)]
+    pub external: bool,
+    #[serde(default)]
+    pub dynamic: bool,
+}
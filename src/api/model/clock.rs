@@ -0,0 +1,160 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use super::ParseValueError;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Reply from `/system/clock/print`
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SystemClock {
+    /// Local time, ie `14:03:07`
+    pub time: String,
+
+    /// Local date, `oct/15/2026` before RouterOS 7.10 and `2026-10-15` since
+    pub date: String,
+
+    pub time_zone_name: Option<String>,
+
+    /// Offset of the local time to UTC, ie `+02:00`
+    pub gmt_offset: Option<String>,
+
+    #[serde(default)]
+    pub dst_active: bool,
+}
+
+impl SystemClock {
+    /// The router's clock as a point in time, its offset to UTC being taken into account
+    pub fn to_system_time(&self) -> Result<SystemTime, ParseValueError> {
+        let (year, month, day) = parse_date(&self.date)?;
+
+        let time_error = || ParseValueError::new("a time", &self.time);
+
+        let mut seconds = 0;
+        for part in self.time.split(':') {
+            seconds = seconds * 60 + part.parse::<i64>().map_err(|_| time_error())?;
+        }
+
+        let offset = match self.gmt_offset.as_deref() {
+            Some(offset) => parse_offset(offset)?,
+            None => 0,
+        };
+
+        let timestamp = days_from_civil(year, month, day) * 86400 + seconds - offset;
+
+        u64::try_from(timestamp)
+            .map(|timestamp| UNIX_EPOCH + Duration::from_secs(timestamp))
+            .map_err(|_| ParseValueError::new("a date after 1970", &self.date))
+    }
+}
+
+fn parse_date(date: &str) -> Result<(i64, u32, u32), ParseValueError> {
+    let error = || ParseValueError::new("a date", date);
+
+    let number = |part: Option<&str>| part.and_then(|p| p.parse().ok()).ok_or_else(error);
+
+    let (year, month, day) = if date.contains('/') {
+        let mut parts = date.split('/');
+
+        let month = parts.next().ok_or_else(error)?.to_ascii_lowercase();
+        let month = MONTHS.iter().position(|m| *m == month).ok_or_else(error)? as u32 + 1;
+
+        let day = number(parts.next())?;
+        let year = number(parts.next())?;
+
+        (year, month, day)
+    } else {
+        let mut parts = date.split('-');
+
+        let year = number(parts.next())?;
+        let month = number(parts.next())?;
+        let day = number(parts.next())?;
+
+        (year, month, day)
+    };
+
+    match (1..=12).contains(&month) && (1..=31).contains(&day) {
+        true => Ok((year as i64, month, day)),
+        false => Err(error()),
+    }
+}
+
+/// Offset to UTC in seconds, from `+02:00` or a number of seconds
+fn parse_offset(offset: &str) -> Result<i64, ParseValueError> {
+    let error = || ParseValueError::new("an offset to UTC", offset);
+
+    let (sign, rest) = match offset.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+    };
+
+    let seconds = match rest.split_once(':') {
+        Some((hours, minutes)) => {
+            let hours: i64 = hours.parse().map_err(|_| error())?;
+            let minutes: i64 = minutes.parse().map_err(|_| error())?;
+
+            hours * 3600 + minutes * 60
+        }
+
+        None => rest.parse().map_err(|_| error())?,
+    };
+
+    Ok(sign * seconds)
+}
+
+/// Days since 1970-01-01 of a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+
+    let month = i64::from(month);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::SystemClock;
+
+    fn clock(date: &str, time: &str, offset: &str) -> SystemClock {
+        SystemClock {
+            time: time.to_owned(),
+            date: date.to_owned(),
+            time_zone_name: None,
+            gmt_offset: Some(offset.to_owned()),
+            dst_active: false,
+        }
+    }
+
+    #[test]
+    fn test_to_system_time() {
+        let expected = UNIX_EPOCH + Duration::from_secs(1_792_065_787);
+
+        assert_eq!(
+            Ok(expected),
+            clock("oct/15/2026", "14:03:07", "+02:00").to_system_time()
+        );
+        assert_eq!(
+            Ok(expected),
+            clock("2026-10-15", "07:03:07", "-05:00").to_system_time()
+        );
+        assert_eq!(
+            Ok(expected),
+            clock("2026-10-15", "12:03:07", "0").to_system_time()
+        );
+
+        assert!(clock("smarch/15/2026", "14:03:07", "+02:00")
+            .to_system_time()
+            .is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::entity::RosEntity;
+
+use super::{MacAddress, RosId};
+
+/// An entry of `/ip/dhcp-server/lease`
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DhcpLease {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    pub address: String,
+    pub mac_address: MacAddress,
+    pub client_id: Option<String>,
+    pub server: Option<String>,
+
+    /// `bound`, `waiting`, `offered`, ...
+    #[serde(skip_serializing)]
+    pub status: Option<String>,
+    #[serde(skip_serializing)]
+    pub host_name: Option<String>,
+    #[serde(skip_serializing)]
+    pub expires_after: Option<String>,
+    #[serde(skip_serializing)]
+    pub last_seen: Option<String>,
+
+    #[serde(default, skip_serializing)]
+    pub dynamic: bool,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub block_access: bool,
+
+    pub comment: Option<String>,
+}
+
+impl RosEntity for DhcpLease {
+    const PATH: &'static str = "/ip/dhcp-server/lease";
+
+    const PROPLIST: Option<&'static [&'static str]> = Some(&[
+        ".id",
+        "address",
+        "mac-address",
+        "client-id",
+        "server",
+        "status",
+        "host-name",
+        "expires-after",
+        "last-seen",
+        "dynamic",
+        "disabled",
+        "block-access",
+        "comment",
+    ]);
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// An entry of `/ip/dhcp-client`: a DHCP client running on an interface, typically a WAN uplink
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DhcpClient {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    pub interface: String,
+
+    /// `bound`, `searching...`, `requesting...`, `rebinding...`, `stopped`, ...
+    #[serde(skip_serializing)]
+    pub status: Option<String>,
+
+    /// Address leased to the router, with its prefix length
+    #[serde(skip_serializing)]
+    pub address: Option<String>,
+    #[serde(skip_serializing)]
+    pub gateway: Option<String>,
+    #[serde(skip_serializing)]
+    pub dhcp_server: Option<String>,
+
+    /// Time left on the lease, ie `23h59m12s`
+    #[serde(skip_serializing)]
+    pub expires_after: Option<String>,
+
+    /// `yes`, `no` or `special-classless`
+    pub add_default_route: Option<String>,
+    #[serde(default)]
+    pub use_peer_dns: bool,
+
+    #[serde(default, skip_serializing)]
+    pub invalid: bool,
+    #[serde(default, skip_serializing)]
+    pub dynamic: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+impl DhcpClient {
+    /// Whether the client holds a lease
+    pub fn is_bound(&self) -> bool {
+        self.status.as_deref() == Some("bound")
+    }
+}
+
+impl RosEntity for DhcpClient {
+    const PATH: &'static str = "/ip/dhcp-client";
+
+    const PROPLIST: Option<&'static [&'static str]> = Some(&[
+        ".id",
+        "interface",
+        "status",
+        "address",
+        "gateway",
+        "dhcp-server",
+        "expires-after",
+        "add-default-route",
+        "use-peer-dns",
+        "invalid",
+        "dynamic",
+        "disabled",
+        "comment",
+    ]);
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// Reply from `/tool/e-mail/print`: the SMTP server the router sends mail through.
+/// Accepts the names of properties both before (`address`, `start-tls`) and since RouterOS 7.12 (`server`, `tls`)
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EmailServer {
+    /// Host name or address of the SMTP server, `0.0.0.0` when unset
+    #[serde(alias = "address")]
+    pub server: Option<String>,
+
+    pub port: Option<u16>,
+
+    /// Sender address, ie `router@example.com`
+    pub from: Option<String>,
+    pub user: Option<String>,
+
+    /// `yes`, `no`, `starttls` since RouterOS 7.12; `yes`, `no`, `tls-only` before
+    #[serde(alias = "start-tls")]
+    pub tls: Option<String>,
+
+    pub vrf: Option<String>,
+}
+
+/// Changes to apply to `/tool/e-mail`, `None` fields being left untouched.
+/// `server` and `tls` are sent as `address` and `start-tls` to routers older than 7.12
+#[derive(Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EmailSettings {
+    /// Host name or address of the SMTP server
+    pub server: Option<String>,
+
+    /// Port of the SMTP server, ie `587`
+    pub port: Option<u16>,
+
+    /// Sender address
+    pub from: Option<String>,
+
+    /// User name of the SMTP account
+    pub user: Option<String>,
+
+    /// Password of the SMTP account
+    pub password: Option<String>,
+
+    /// Whether to use TLS, see `EmailServer::tls` for the values
+    pub tls: Option<String>,
+}
+
+impl std::fmt::Debug for EmailSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmailSettings")
+            .field("server", &self.server)
+            .field("port", &self.port)
+            .field("from", &self.from)
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("tls", &self.tls)
+            .finish()
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer,
+};
+
+/// Reply from `/interface/ethernet/cable-test`, sent repeatedly while the test runs
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CableTest {
+    pub name: String,
+
+    /// `link-ok`, `no-link`, `unknown`, ...
+    pub status: String,
+
+    /// State of each pair, only reported when there is no link
+    pub cable_pairs: Option<Vec<CablePair>>,
+}
+
+/// State of a single pair of an ethernet cable, such as `open:4` or `normal`
+#[derive(Debug, PartialEq, Eq)]
+pub struct CablePair {
+    /// `normal`, `open`, `short`, ...
+    pub state: String,
+
+    /// Distance to the fault, in meters
+    pub distance: Option<u16>,
+}
+
+struct BorrowedString;
+impl<'de> Visitor<'de> for BorrowedString {
+    type Value = &'de str;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(v)
+    }
+}
+
+impl<'de> Deserialize<'de> for CablePair {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let text = deserializer.deserialize_str(BorrowedString)?;
+
+        let (state, distance) = match text.split_once(':') {
+            Some((state, distance)) => {
+                let distance = distance.parse::<u16>().map_err(|_| {
+                    de::Error::invalid_value(de::Unexpected::Str(text), &"'<state>:<distance>'")
+                })?;
+
+                (state, Some(distance))
+            }
+
+            None => (text, None),
+        };
+
+        Ok(CablePair {
+            state: state.to_owned(),
+            distance,
+        })
+    }
+}
+
+/// Parses measurements such as `-5.123`, `35C` or `-5.123dBm`, ignoring the unit
+fn measurement<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = match Option::<&str>::deserialize(deserializer)? {
+        Some(text) => text,
+        None => return Ok(None),
+    };
+
+    let number = text.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%');
+
+    number
+        .parse()
+        .map(Some)
+        .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(text), &"a measurement"))
+}
+
+/// Reply from `/interface/ethernet/monitor`, restricted to link state and SFP diagnostics (DDM)
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EthernetMonitor {
+    pub name: String,
+
+    /// `link-ok`, `no-link`, ...
+    pub status: String,
+    pub rate: Option<String>,
+    pub full_duplex: Option<bool>,
+
+    #[serde(default)]
+    pub sfp_module_present: bool,
+    pub sfp_rx_loss: Option<bool>,
+    pub sfp_tx_fault: Option<bool>,
+
+    pub sfp_type: Option<String>,
+    pub sfp_connector_type: Option<String>,
+    pub sfp_vendor_name: Option<String>,
+    pub sfp_vendor_part_number: Option<String>,
+    pub sfp_vendor_serial: Option<String>,
+    pub sfp_manufacturing_date: Option<String>,
+
+    /// Wavelength, in nanometers
+    #[serde(default, deserialize_with = "measurement")]
+    pub sfp_wavelength: Option<f32>,
+
+    /// Module temperature, in degrees Celsius
+    #[serde(default, deserialize_with = "measurement")]
+    pub sfp_temperature: Option<f32>,
+
+    /// Supply voltage, in volts
+    #[serde(default, deserialize_with = "measurement")]
+    pub sfp_supply_voltage: Option<f32>,
+
+    /// Laser bias current, in milliamperes
+    #[serde(default, deserialize_with = "measurement")]
+    pub sfp_tx_bias_current: Option<f32>,
+
+    /// Transmitted optical power, in dBm
+    #[serde(default, deserialize_with = "measurement")]
+    pub sfp_tx_power: Option<f32>,
+
+    /// Received optical power, in dBm
+    #[serde(default, deserialize_with = "measurement")]
+    pub sfp_rx_power: Option<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{de::deserialize_sentence, model::Response};
+
+    use super::{CablePair, CableTest, EthernetMonitor};
+
+    fn sentence(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_cable_pairs() {
+        let words = sentence(&[
+            "!re",
+            "=name=ether2",
+            "=status=no-link",
+            "=cable-pairs=open:4,open:4,normal,short:12",
+            "",
+        ]);
+
+        let test = match deserialize_sentence::<CableTest>(&words).unwrap() {
+            Response::Reply(test) => test,
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        let pair = |state: &str, distance| CablePair {
+            state: state.to_owned(),
+            distance,
+        };
+
+        assert_eq!(
+            Some(vec![
+                pair("open", Some(4)),
+                pair("open", Some(4)),
+                pair("normal", None),
+                pair("short", Some(12))
+            ]),
+            test.cable_pairs
+        );
+    }
+
+    #[test]
+    fn test_sfp_measurements() {
+        let words = sentence(&[
+            "!re",
+            "=name=sfp1",
+            "=status=link-ok",
+            "=sfp-module-present=true",
+            "=sfp-temperature=35C",
+            "=sfp-rx-power=-6.354",
+            "",
+        ]);
+
+        let monitor = match deserialize_sentence::<EthernetMonitor>(&words).unwrap() {
+            Response::Reply(monitor) => monitor,
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        assert!(monitor.sfp_module_present);
+        assert_eq!(Some(35.0), monitor.sfp_temperature);
+        assert_eq!(Some(-6.354), monitor.sfp_rx_power);
+        assert_eq!(None, monitor.sfp_tx_power);
+    }
+}
@@ -0,0 +1,370 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::entity::RosEntity;
+
+use super::{
+    types::{string_serde, ParseValueError},
+    MacAddress, RosId,
+};
+
+/// An entry of `/ip/address`
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IpAddress {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    /// Address with its prefix length, ie `192.168.88.1/24`
+    pub address: String,
+    pub network: Option<String>,
+    pub interface: String,
+
+    #[serde(skip_serializing)]
+    pub actual_interface: Option<String>,
+
+    #[serde(default, skip_serializing)]
+    pub invalid: bool,
+    #[serde(default, skip_serializing)]
+    pub dynamic: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+impl RosEntity for IpAddress {
+    const PATH: &'static str = "/ip/address";
+
+    const PROPLIST: Option<&'static [&'static str]> = Some(&[
+        ".id",
+        "address",
+        "network",
+        "interface",
+        "actual-interface",
+        "invalid",
+        "dynamic",
+        "disabled",
+        "comment",
+    ]);
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// An entry of `/ip/route`
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Route {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    pub dst_address: String,
+    pub gateway: Option<String>,
+    pub distance: Option<u8>,
+    pub pref_src: Option<String>,
+
+    /// Routing table of the route (`routing-mark` on v6)
+    #[serde(alias = "routing-mark")]
+    pub routing_table: Option<String>,
+
+    #[serde(default, skip_serializing)]
+    pub active: bool,
+    #[serde(default, skip_serializing)]
+    pub dynamic: bool,
+    #[serde(rename = "static", default, skip_serializing)]
+    pub is_static: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+impl RosEntity for Route {
+    const PATH: &'static str = "/ip/route";
+
+    const PROPLIST: Option<&'static [&'static str]> = Some(&[
+        ".id",
+        "dst-address",
+        "gateway",
+        "distance",
+        "pref-src",
+        "routing-table",
+        "routing-mark",
+        "active",
+        "dynamic",
+        "static",
+        "disabled",
+        "comment",
+    ]);
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// An entry of `/ip/service`: one of the router's management services (`api`, `api-ssl`, `ssh`, `www`, ...)
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IpService {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    #[serde(skip_serializing)]
+    pub name: String,
+
+    pub port: u16,
+
+    /// Networks allowed to connect to the service, empty meaning everyone
+    #[serde(default)]
+    pub address: Vec<String>,
+
+    /// Certificate used by TLS services (`api-ssl`, `www-ssl`)
+    pub certificate: Option<String>,
+
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default, skip_serializing)]
+    pub invalid: bool,
+}
+
+impl RosEntity for IpService {
+    const PATH: &'static str = "/ip/service";
+
+    const VERBS: &'static [&'static str] = &["print", "set"];
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// Changes to apply to an `/ip/service` entry, `None` fields being left untouched
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IpServiceSettings {
+    /// TCP port the service listens on
+    pub port: Option<u16>,
+
+    /// Networks allowed to connect to the service, an empty list lifting any restriction
+    pub address: Option<Vec<String>>,
+
+    /// Certificate to use for TLS services, `none` to remove it
+    pub certificate: Option<String>,
+
+    /// Whether the service is disabled
+    pub disabled: Option<bool>,
+}
+
+/// An entry of `/ip/arp`
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ArpEntry {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    pub address: String,
+    pub mac_address: Option<MacAddress>,
+    pub interface: String,
+
+    #[serde(default, skip_serializing)]
+    pub dynamic: bool,
+    #[serde(default, skip_serializing)]
+    pub complete: bool,
+    #[serde(default, skip_serializing)]
+    pub invalid: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+impl RosEntity for ArpEntry {
+    const PATH: &'static str = "/ip/arp";
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// An entry of `/ip/neighbor`: a device discovered through MNDP, CDP or LLDP
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Neighbor {
+    #[serde(rename = ".id")]
+    pub id: RosId,
+
+    /// Local interface(s) the neighbor was seen on
+    pub interface: Vec<String>,
+
+    pub address: Option<String>,
+    pub mac_address: Option<MacAddress>,
+    pub identity: Option<String>,
+    pub platform: Option<String>,
+    pub board: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Whether the router registers its public address under its `*.sn.mynetname.net` name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DdnsMode {
+    /// Registration enabled
+    Enabled,
+
+    /// Registration disabled
+    Disabled,
+
+    /// Registration enabled by features needing it, such as Back To Home (RouterOS 7)
+    Auto,
+}
+
+impl FromStr for DdnsMode {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "true" | "yes" => Ok(DdnsMode::Enabled),
+            "false" | "no" => Ok(DdnsMode::Disabled),
+            "auto" => Ok(DdnsMode::Auto),
+            _ => Err(ParseValueError::new("a DDNS mode", s)),
+        }
+    }
+}
+
+impl Display for DdnsMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DdnsMode::Enabled => "yes",
+            DdnsMode::Disabled => "no",
+            DdnsMode::Auto => "auto",
+        })
+    }
+}
+
+string_serde!(DdnsMode);
+
+/// Reply from `/ip/cloud/print`: MikroTik's DDNS service and the public address it sees
+#[allow(missing_docs)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IpCloud {
+    pub ddns_enabled: DdnsMode,
+    pub ddns_update_interval: Option<String>,
+
+    /// Whether the router sets its clock from the cloud service
+    #[serde(default)]
+    pub update_time: bool,
+
+    pub public_address: Option<String>,
+    pub public_address_ipv6: Option<String>,
+
+    /// Name the public address is registered under, ie `529c0491d41c.sn.mynetname.net`
+    pub dns_name: Option<String>,
+
+    /// `updated`, `updating...`, `error: ...`
+    pub status: Option<String>,
+    pub warning: Option<String>,
+}
+
+/// Changes to apply to `/ip/cloud`, `None` fields being left untouched
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IpCloudSettings {
+    /// DDNS registration mode
+    pub ddns_enabled: Option<DdnsMode>,
+
+    /// Time between two updates, ie `1h` (`none` to only update on address changes)
+    pub ddns_update_interval: Option<String>,
+
+    /// Whether the router sets its clock from the cloud service
+    pub update_time: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{de::deserialize_sentence, model::Response};
+
+    use super::{DdnsMode, IpCloud, Route};
+
+    fn reply<T: serde::de::DeserializeOwned + std::fmt::Debug>(words: &[&str]) -> T {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+
+        match deserialize_sentence::<T>(&words).unwrap() {
+            Response::Reply(value) => value,
+            other => panic!("expected a reply, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_route_v6_and_v7() {
+        let v6: Route = reply(&[
+            "!re",
+            "=.id=*2",
+            "=dst-address=0.0.0.0/0",
+            "=gateway=203.0.113.1",
+            "=gateway-status=203.0.113.1 reachable via  ether1",
+            "=distance=1",
+            "=routing-mark=isp2",
+            "=active=true",
+            "=static=true",
+            "=disabled=false",
+            "",
+        ]);
+
+        let v7: Route = reply(&[
+            "!re",
+            "=.id=*80000002",
+            "=dst-address=0.0.0.0/0",
+            "=gateway=203.0.113.1",
+            "=immediate-gw=203.0.113.1%ether1",
+            "=distance=1",
+            "=routing-table=isp2",
+            "=active=true",
+            "=static=true",
+            "=disabled=false",
+            "",
+        ]);
+
+        for route in [v6, v7] {
+            assert_eq!(Some("isp2"), route.routing_table.as_deref());
+            assert!(route.active && route.is_static);
+        }
+    }
+
+    #[test]
+    fn test_ip_cloud() {
+        let words: Vec<String> = [
+            "!re",
+            "=ddns-enabled=true",
+            "=ddns-update-interval=none",
+            "=update-time=false",
+            "=public-address=203.0.113.7",
+            "=dns-name=529c0491d41c.sn.mynetname.net",
+            "=status=updated",
+            "",
+        ]
+        .iter()
+        .map(|w| w.to_string())
+        .collect();
+
+        let cloud = match deserialize_sentence::<IpCloud>(&words).unwrap() {
+            Response::Reply(cloud) => cloud,
+            other => panic!("expected a reply, got {:?}", other),
+        };
+
+        assert_eq!(DdnsMode::Enabled, cloud.ddns_enabled);
+        assert_eq!(Some("203.0.113.7"), cloud.public_address.as_deref());
+        assert_eq!("auto".parse::<DdnsMode>(), Ok(DdnsMode::Auto));
+        assert_eq!("no", DdnsMode::Disabled.to_string());
+    }
+}
@@ -0,0 +1,263 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use serde::Deserialize;
+
+use super::{
+    types::{parse_duration, string_serde, ParseValueError},
+    RosId,
+};
+
+/// State of an IKE (phase 1) negotiation with a peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpsecPeerState {
+    /// Phase 1 is complete
+    Established,
+
+    /// Any step of the negotiation, as reported by the router: `message-1-sent`, `sa-init-sent`, `auth-sent`, ...
+    Negotiating(String),
+}
+
+impl FromStr for IpsecPeerState {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "established" => Ok(IpsecPeerState::Established),
+            "" => Err(ParseValueError::new("a peer state", s)),
+            other => Ok(IpsecPeerState::Negotiating(other.to_owned())),
+        }
+    }
+}
+
+impl Display for IpsecPeerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpsecPeerState::Established => f.write_str("established"),
+            IpsecPeerState::Negotiating(step) => f.write_str(step),
+        }
+    }
+}
+
+/// State of the phase 2 of a policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpsecPh2State {
+    /// SAs are installed, traffic flows through the tunnel
+    Established,
+
+    /// No phase 2 yet, the peer being down or negotiating
+    NoPhase2,
+
+    /// SAs expired and were not renewed
+    Expired,
+
+    /// Any other state
+    Other(String),
+}
+
+impl FromStr for IpsecPh2State {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "established" => IpsecPh2State::Established,
+            "no-phase2" => IpsecPh2State::NoPhase2,
+            "expired" => IpsecPh2State::Expired,
+            other => IpsecPh2State::Other(other.to_owned()),
+        })
+    }
+}
+
+impl Display for IpsecPh2State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            IpsecPh2State::Established => "established",
+            IpsecPh2State::NoPhase2 => "no-phase2",
+            IpsecPh2State::Expired => "expired",
+            IpsecPh2State::Other(state) => state,
+        })
+    }
+}
+
+/// State of an installed SA
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpsecSaState {
+    /// Being negotiated
+    Larval,
+
+    /// In use
+    Mature,
+
+    /// Soft lifetime reached, about to be replaced
+    Dying,
+
+    /// Hard lifetime reached
+    Dead,
+
+    /// Any other state
+    Other(String),
+}
+
+impl FromStr for IpsecSaState {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "larval" => IpsecSaState::Larval,
+            "mature" => IpsecSaState::Mature,
+            "dying" => IpsecSaState::Dying,
+            "dead" => IpsecSaState::Dead,
+            other => IpsecSaState::Other(other.to_owned()),
+        })
+    }
+}
+
+impl Display for IpsecSaState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            IpsecSaState::Larval => "larval",
+            IpsecSaState::Mature => "mature",
+            IpsecSaState::Dying => "dying",
+            IpsecSaState::Dead => "dead",
+            IpsecSaState::Other(state) => state,
+        })
+    }
+}
+
+string_serde!(IpsecPeerState, IpsecPh2State, IpsecSaState);
+
+/// An entry of `/ip/ipsec/active-peers`
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IpsecActivePeer {
+    #[serde(rename = ".id")]
+    pub id: RosId,
+
+    /// Identity sent by the peer
+    #[serde(rename = "id")]
+    pub identity: Option<String>,
+
+    pub remote_address: String,
+    pub local_address: Option<String>,
+    pub port: Option<u16>,
+
+    pub state: IpsecPeerState,
+
+    /// `initiator` or `responder`
+    pub side: Option<String>,
+
+    /// Time since phase 1 completed, ie `1d2h3m4s`, see `peer_uptime`
+    pub uptime: Option<String>,
+
+    /// Number of phase 2 SAs negotiated with the peer
+    pub ph2_total: Option<u32>,
+
+    /// Address given to the peer through mode config
+    pub dynamic_address: Option<String>,
+}
+
+impl IpsecActivePeer {
+    /// Time since phase 1 completed, `None` while negotiating
+    pub fn peer_uptime(&self) -> Option<Result<Duration, ParseValueError>> {
+        self.uptime.as_deref().map(parse_duration)
+    }
+}
+
+/// An entry of `/ip/ipsec/policy`
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IpsecPolicy {
+    #[serde(rename = ".id")]
+    pub id: RosId,
+
+    pub peer: Option<String>,
+    pub src_address: Option<String>,
+    pub dst_address: Option<String>,
+
+    #[serde(default)]
+    pub tunnel: bool,
+
+    /// `encrypt`, `discard` or `none`
+    pub action: Option<String>,
+    pub sa_src_address: Option<String>,
+    pub sa_dst_address: Option<String>,
+
+    pub ph2_state: Option<IpsecPh2State>,
+    pub ph2_count: Option<u32>,
+
+    #[serde(default)]
+    pub template: bool,
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub invalid: bool,
+    #[serde(default)]
+    pub dynamic: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+/// An entry of `/ip/ipsec/installed-sa`
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IpsecInstalledSa {
+    #[serde(rename = ".id")]
+    pub id: RosId,
+
+    /// Security Parameter Index, in hex
+    pub spi: String,
+
+    pub src_address: String,
+    pub dst_address: String,
+
+    pub state: IpsecSaState,
+
+    pub auth_algorithm: Option<String>,
+    pub enc_algorithm: Option<String>,
+
+    /// Bytes that went through the SA
+    pub current_bytes: Option<u64>,
+
+    /// Remaining lifetime, ie `29m12s`
+    pub expires_in: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::api::{de::deserialize_sentence, model::Response};
+
+    use super::{IpsecActivePeer, IpsecPeerState};
+
+    #[test]
+    fn test_active_peer() {
+        let words: Vec<String> = [
+            "!re",
+            "=.id=*3",
+            "=id=branch-7",
+            "=remote-address=198.51.100.7",
+            "=state=established",
+            "=side=initiator",
+            "=uptime=1d2h3m4s",
+            "=ph2-total=2",
+            "",
+        ]
+        .iter()
+        .map(|w| w.to_string())
+        .collect();
+
+        let peer = match deserialize_sentence::<IpsecActivePeer>(&words).unwrap() {
+            Response::Reply(peer) => peer,
+            other => panic!("expected a reply, got {:?}", other),
+        };
+
+        assert_eq!(Some("branch-7"), peer.identity.as_deref());
+        assert_eq!(IpsecPeerState::Established, peer.state);
+        assert_eq!(Some(Ok(Duration::from_secs(93_784))), peer.peer_uptime());
+    }
+}
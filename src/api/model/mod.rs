@@ -1,12 +1,62 @@
-use std::iter::FromIterator;
+use std::{fmt::Display, iter::FromIterator};
 
 use serde::{
     de::{self, Visitor},
-    Deserialize,
+    Deserialize, Serialize, Serializer,
 };
 
 use super::error::Error;
 
+#[cfg(feature = "ethernet")]
+mod bonding;
+mod bridge;
+#[cfg(feature = "clock")]
+mod clock;
+#[cfg(feature = "dhcp")]
+mod dhcp;
+#[cfg(feature = "email")]
+mod email;
+#[cfg(feature = "ethernet")]
+mod ethernet;
+mod ip;
+#[cfg(feature = "ipsec")]
+mod ipsec;
+mod system;
+#[cfg(feature = "torch")]
+mod torch;
+mod types;
+#[cfg(feature = "wireless")]
+mod wireless;
+
+#[cfg(feature = "ethernet")]
+pub use bonding::{Bonding, BondingMonitor, BondingSlave, BondingStatus};
+pub use bridge::{BridgeHost, BridgePort, Vlan};
+#[cfg(feature = "clock")]
+pub use clock::SystemClock;
+#[cfg(feature = "dhcp")]
+pub use dhcp::{DhcpClient, DhcpLease};
+#[cfg(feature = "email")]
+pub use email::{EmailServer, EmailSettings};
+#[cfg(feature = "ethernet")]
+pub use ethernet::{CablePair, CableTest, EthernetMonitor};
+pub use ip::{
+    ArpEntry, DdnsMode, IpAddress, IpCloud, IpCloudSettings, IpService, IpServiceSettings,
+    Neighbor, Route,
+};
+#[cfg(feature = "ipsec")]
+pub use ipsec::{
+    IpsecActivePeer, IpsecInstalledSa, IpsecPeerState, IpsecPh2State, IpsecPolicy, IpsecSaState,
+};
+pub use system::{License, Routerboard};
+#[cfg(feature = "torch")]
+pub use torch::Torch;
+pub use types::{MacAddress, ParseValueError, RosId};
+#[cfg(feature = "wireless")]
+pub use wireless::{WifiInterface, WirelessRegistration, WirelessScan};
+
+/// Reason of `Error::ConnectionLost` for calls failed by a `Response::Fatal`
+pub(crate) const FATAL: &str = "the router sent !fatal or the connection dropped";
+
 /// A response to a command, sent by the router.
 #[derive(Debug, Deserialize)]
 pub enum Response<T> {
@@ -19,44 +69,97 @@ pub enum Response<T> {
     /// `!trap` sentence, sent by the router instead of `!re` when an error happened.
     Trap {
         /// Type of error
-        /// TODO: make an enum, as possible values are well-known
         category: Option<TrapCategory>,
 
         /// Error message, to be shown to the user
         message: String,
     },
     /// `!fatal` sentence. A !fatal word is succeded by a simple string being the error message.
+    /// Also given to calls still waiting for a reply when the connection is lost.
     Fatal,
 }
 
+/// An item of a stream, along with its position in the stream and the time it was received at.
+///
+/// Sequence numbers start at 0 and increase by one for every item of the stream, replies dropped by its `StreamBuffer`
+/// included: gaps or reordering can be detected after items have been spread across several consumers.
+#[derive(Debug, Clone)]
+pub struct StreamItem<T> {
+    /// Position of the item in the stream
+    pub seq: u64,
+
+    /// Time the sentence was read off the connection
+    pub received_at: std::time::Instant,
+
+    /// The item itself
+    pub value: T,
+}
+
 /// Possible values for !trap `category`.
 /// From https://wiki.mikrotik.com/wiki/Manual:API#category
-#[derive(Debug)]
-#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrapCategory {
     /// 0 - missing item or command
-    MissingItemOrCommand = 0,
+    MissingItemOrCommand,
 
     /// 1 - argument value failure
-    ArgumentValueFailure = 1,
+    ArgumentValueFailure,
 
     /// 2 - execution of command interrupted
-    CommandExecutionInterrupted = 2,
+    CommandExecutionInterrupted,
 
     /// 3 - scripting related failure
-    ScriptingFailure = 3,
+    ScriptingFailure,
 
     /// 4 - general failure
-    GeneralFailure = 4,
+    GeneralFailure,
 
     /// 5 - API related failure
-    APIFailure = 5,
+    APIFailure,
 
     /// 6 - TTY related failure
-    TTYFailure = 6,
+    TTYFailure,
 
     /// 7 - value generated with :return command
-    ReturnValue = 7,
+    ReturnValue,
+
+    /// Any category unknown to this crate, such as the ones RouterOS v7 sometimes sends
+    Other(u8),
+}
+
+impl TrapCategory {
+    /// Numeric value of the category, as sent by the router
+    pub fn code(&self) -> u8 {
+        use TrapCategory::*;
+        match self {
+            MissingItemOrCommand => 0,
+            ArgumentValueFailure => 1,
+            CommandExecutionInterrupted => 2,
+            ScriptingFailure => 3,
+            GeneralFailure => 4,
+            APIFailure => 5,
+            TTYFailure => 6,
+            ReturnValue => 7,
+            Other(code) => *code,
+        }
+    }
+}
+
+impl From<u8> for TrapCategory {
+    fn from(code: u8) -> Self {
+        use TrapCategory::*;
+        match code {
+            0 => MissingItemOrCommand,
+            1 => ArgumentValueFailure,
+            2 => CommandExecutionInterrupted,
+            3 => ScriptingFailure,
+            4 => GeneralFailure,
+            5 => APIFailure,
+            6 => TTYFailure,
+            7 => ReturnValue,
+            other => Other(other),
+        }
+    }
 }
 
 impl<'de> Deserialize<'de> for TrapCategory {
@@ -64,15 +167,7 @@ impl<'de> Deserialize<'de> for TrapCategory {
     where
         D: serde::Deserializer<'de>,
     {
-        match u8::deserialize(deserializer)? {
-            //Safe because enum is repr(u8) and range is valid (from 0 to 7 inclusive)
-            category @ 0..=7 => unsafe { Ok(core::mem::transmute(category)) },
-
-            unknown => Err(de::Error::invalid_value(
-                serde::de::Unexpected::Unsigned(unknown.into()),
-                &"a known trap category",
-            )),
-        }
+        u8::deserialize(deserializer).map(TrapCategory::from)
     }
 }
 
@@ -81,6 +176,7 @@ impl<T> From<Response<T>> for Result<T, Error> {
         match response {
             Response::Reply(value) => Ok(value),
             Response::Trap { message, .. } => Err(Error::Remote(message)),
+            Response::Fatal => Err(Error::ConnectionLost(FATAL.to_owned())),
             _ => unreachable!(),
         }
     }
@@ -93,10 +189,10 @@ impl<A, V: FromIterator<A>> FromIterator<Response<A>> for Response<V> {
         use Response::*;
         //No idea what I'm doing. This code has been inspired from https://github.com/rust-lang/rust/pull/59605
         let v: V = FromIterator::from_iter(iter.into_iter().scan((), |_, elt| match elt {
-            Done | Fatal => None,
+            Done => None,
             Reply(value) => Some(value),
 
-            trap @ Trap { .. } => {
+            trap @ (Trap { .. } | Fatal) => {
                 found_trap = Some(trap);
                 None
             }
@@ -105,6 +201,8 @@ impl<A, V: FromIterator<A>> FromIterator<Response<A>> for Response<V> {
         match found_trap {
             Some(Trap { message, category }) => Trap { category, message },
 
+            Some(Fatal) => Fatal,
+
             None => Reply(v),
 
             _ => unreachable!(),
@@ -142,7 +240,7 @@ pub struct SystemResources {
 #[serde(rename_all = "kebab-case")]
 pub struct ActiveUserRaw {
     #[serde(rename = ".id")]
-    pub id: String,
+    pub id: RosId,
 
     #[serde(rename = ".dead")]
     #[serde(default)]
@@ -159,13 +257,13 @@ pub struct ActiveUserRaw {
 /// An event to describe user activity in terms of logins and logouts
 #[derive(Debug)]
 pub enum ActiveUser {
-    /// Logout event, the String being the relative id of the user who logged out.
-    Dead(String),
+    /// Logout event, holding the relative id of the user who logged out.
+    Dead(RosId),
 
     /// Login event
     Active {
         /// Relative, incremental user id
-        id: String,
+        id: RosId,
 
         /// Login time
         when: String,
@@ -228,7 +326,7 @@ impl<'de> Deserialize<'de> for ActiveUser {
 #[derive(Debug, Deserialize)]
 pub struct InterfaceChange {
     #[serde(rename = ".id")]
-    pub id: String,
+    pub id: RosId,
 }
 
 /// Reply from `/system/interface/print` command
@@ -237,7 +335,7 @@ pub struct InterfaceChange {
 #[serde(rename_all = "kebab-case")]
 pub struct Interface {
     #[serde(rename = ".id")]
-    pub id: String,
+    pub id: RosId,
 
     pub name: String,
 
@@ -246,7 +344,11 @@ pub struct Interface {
 
     pub mtu: InterfaceMTU,
     pub actual_mtu: u16,
+    /// Absent on interfaces without layer 2 (tunnels, ...)
+    pub l2mtu: Option<u16>,
 
+    /// Last time the link came up (`last-link-up-time` on the router)
+    #[serde(alias = "last-link-up-time")]
     pub last_link_up: Option<String>,
     pub link_downs: u32,
 
@@ -272,7 +374,7 @@ pub struct Interface {
 }
 
 /// Enum to represent the `mtu` field that can take either a number value or a text: 'auto'
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InterfaceMTU {
     /// 'auto' value
     Auto,
@@ -311,3 +413,141 @@ impl<'de> Deserialize<'de> for InterfaceMTU {
         }
     }
 }
+
+impl Display for InterfaceMTU {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfaceMTU::Auto => f.write_str("auto"),
+
+            InterfaceMTU::Value(mtu) => write!(f, "{}", mtu),
+        }
+    }
+}
+
+impl Serialize for InterfaceMTU {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::api::{de::deserialize_sentence, ser::to_attributes};
+
+    use super::{Interface, InterfaceMTU, Response, TrapCategory};
+
+    fn trap_category(code: &str) -> Option<TrapCategory> {
+        let words: Vec<String> = ["!trap", &format!("=category={}", code), "=message=oops", ""]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+
+        match deserialize_sentence::<()>(&words).unwrap() {
+            Response::Trap { category, .. } => category,
+            other => panic!("expected a trap, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_known_trap_category() {
+        assert_eq!(
+            Some(TrapCategory::CommandExecutionInterrupted),
+            trap_category("2")
+        );
+    }
+
+    #[test]
+    fn test_unknown_trap_category() {
+        let category = trap_category("9");
+
+        assert_eq!(Some(TrapCategory::Other(9)), category);
+        assert_eq!(Some(9), category.map(|c| c.code()));
+    }
+
+    #[test]
+    fn test_interface_mtu_attribute() {
+        #[derive(serde::Serialize)]
+        struct Changes {
+            mtu: InterfaceMTU,
+        }
+
+        for (mtu, value) in [
+            (InterfaceMTU::Auto, "auto"),
+            (InterfaceMTU::Value(1500), "1500"),
+        ] {
+            let attributes = to_attributes(&Changes { mtu }).unwrap();
+            assert_eq!(vec![("mtu".to_owned(), value.to_owned())], attributes);
+
+            let words: Vec<String> = ["!re", &format!("=mtu={}", value), ""]
+                .iter()
+                .map(|w| w.to_string())
+                .collect();
+
+            match deserialize_sentence::<HashMap<String, InterfaceMTU>>(&words).unwrap() {
+                Response::Reply(map) => assert_eq!(mtu, map["mtu"]),
+                other => panic!("expected a reply, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_interface_v6_and_v7() {
+        let counters = [
+            "=link-downs=2",
+            "=rx-byte=1000",
+            "=tx-byte=2000",
+            "=rx-packet=10",
+            "=tx-packet=20",
+            "=tx-queue-drop=0",
+            "=fp-rx-byte=0",
+            "=fp-tx-byte=0",
+            "=fp-rx-packet=0",
+            "=fp-tx-packet=0",
+            "=running=true",
+            "=disabled=false",
+            "",
+        ];
+
+        let v6 = [
+            "!re",
+            "=.id=*1",
+            "=name=ether1",
+            "=type=ether",
+            "=mtu=1500",
+            "=actual-mtu=1500",
+            "=l2mtu=1598",
+            "=last-link-up-time=jan/02/2022 10:12:54",
+        ];
+
+        let v7 = [
+            "!re",
+            "=.id=*1",
+            "=name=ether1",
+            "=default-name=ether1",
+            "=type=ether",
+            "=mtu=1500",
+            "=actual-mtu=1500",
+            "=l2mtu=1598",
+            "=max-l2mtu=9796",
+            "=last-link-up-time=2024-10-10 14:03:32",
+        ];
+
+        for words in [&v6[..], &v7[..]] {
+            let words: Vec<String> = words
+                .iter()
+                .chain(&counters)
+                .map(|w| w.to_string())
+                .collect();
+
+            match deserialize_sentence::<Interface>(&words).unwrap() {
+                Response::Reply(iface) => assert!(iface.last_link_up.is_some()),
+                other => panic!("expected a reply, got {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+/// Reply from `/system/routerboard/print`
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Routerboard {
+    /// `false` on x86 and CHR, which have none of the other properties
+    #[serde(default)]
+    pub routerboard: bool,
+
+    pub board_name: Option<String>,
+    pub model: Option<String>,
+    pub revision: Option<String>,
+    pub serial_number: Option<String>,
+
+    /// `ar7240`, `qca9531L`, `ipq4000L`, ...
+    pub firmware_type: Option<String>,
+    pub factory_firmware: Option<String>,
+
+    /// Version of the RouterBOOT in use
+    pub current_firmware: Option<String>,
+
+    /// Version of the RouterBOOT bundled with the installed RouterOS, see `upgrade_pending`
+    pub upgrade_firmware: Option<String>,
+}
+
+impl Routerboard {
+    /// Whether the RouterBOOT lags behind the one bundled with RouterOS, ie `/system/routerboard/upgrade`
+    /// followed by a reboot would change it
+    pub fn upgrade_pending(&self) -> bool {
+        match (&self.current_firmware, &self.upgrade_firmware) {
+            (Some(current), Some(upgrade)) => current != upgrade,
+            _ => false,
+        }
+    }
+}
+
+/// Reply from `/system/license/print`
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct License {
+    /// Id of a RouterBOARD or x86 license
+    pub software_id: Option<String>,
+
+    /// Id of a CHR license
+    pub system_id: Option<String>,
+
+    /// `4`, `5`, `6` on RouterBOARDs (`nlevel`), `free`, `p1`, `p10`, `unlimited` on CHR (`level`)
+    #[serde(alias = "nlevel")]
+    pub level: Option<String>,
+
+    pub features: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{de::deserialize_sentence, model::Response};
+
+    use super::{License, Routerboard};
+
+    fn reply<T: serde::de::DeserializeOwned + std::fmt::Debug>(words: &[&str]) -> T {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+
+        match deserialize_sentence::<T>(&words).unwrap() {
+            Response::Reply(value) => value,
+            other => panic!("expected a reply, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_routerboard_upgrade_pending() {
+        let board: Routerboard = reply(&[
+            "!re",
+            "=routerboard=true",
+            "=model=RB4011iGS+",
+            "=serial-number=D4430C1A2B3C",
+            "=firmware-type=al2",
+            "=current-firmware=7.15.3",
+            "=upgrade-firmware=7.16.1",
+            "",
+        ]);
+
+        assert!(board.upgrade_pending());
+
+        let chr: Routerboard = reply(&["!re", "=routerboard=false", ""]);
+        assert!(!chr.upgrade_pending());
+    }
+
+    #[test]
+    fn test_license_level() {
+        let board: License = reply(&["!re", "=software-id=ABCD-1234", "=nlevel=4", ""]);
+        assert_eq!(Some("4"), board.level.as_deref());
+
+        let chr: License = reply(&["!re", "=system-id=aBcD", "=level=p1", ""]);
+        assert_eq!(Some("p1"), chr.level.as_deref());
+    }
+}
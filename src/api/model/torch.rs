@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+/// Reply from `/tool/torch`: traffic of a flow over the last second.
+///
+/// Every second the router sends a new section of replies, the last of them holding the totals of the interface
+/// with no address set.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Torch {
+    /// Second the reply belongs to
+    #[serde(rename = ".section")]
+    pub section: Option<u32>,
+
+    pub src_address: Option<String>,
+    pub dst_address: Option<String>,
+
+    /// `ip`, `ipv6`, `arp`, ...
+    pub mac_protocol: Option<String>,
+    /// `tcp`, `udp`, `icmp`, ...
+    pub ip_protocol: Option<String>,
+
+    /// Bits per second sent through the interface
+    #[serde(default)]
+    pub tx: u64,
+    /// Bits per second received by the interface
+    #[serde(default)]
+    pub rx: u64,
+
+    #[serde(default)]
+    pub tx_packets: u64,
+    #[serde(default)]
+    pub rx_packets: u64,
+}
@@ -0,0 +1,243 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use serde::de::{self, Visitor};
+
+/// Error returned when parsing a `MacAddress` or a `RosId` fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseValueError {
+    expected: &'static str,
+    value: String,
+}
+
+impl ParseValueError {
+    pub(crate) fn new(expected: &'static str, value: &str) -> Self {
+        Self {
+            expected,
+            value: value.to_owned(),
+        }
+    }
+}
+
+impl Display for ParseValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' is not {}", self.value, self.expected)
+    }
+}
+
+impl std::error::Error for ParseValueError {}
+
+/// A MAC address, such as `4C:5E:0C:AA:BB:CC`. Also parses `-` separated addresses.
+/// Displayed the way RouterOS does, upper case and `:` separated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl FromStr for MacAddress {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseValueError::new("a MAC address", s);
+
+        let mut bytes = [0u8; 6];
+        let mut parts = s.split([':', '-']);
+
+        for byte in bytes.iter_mut() {
+            let part = parts
+                .next()
+                .filter(|part| part.len() == 2)
+                .ok_or_else(error)?;
+
+            *byte = u8::from_str_radix(part, 16).map_err(|_| error())?;
+        }
+
+        match parts.next() {
+            None => Ok(MacAddress(bytes)),
+            Some(_) => Err(error()),
+        }
+    }
+}
+
+impl Display for MacAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+
+        write!(
+            f,
+            "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+/// Internal id of an item of a RouterOS table (the `.id` property), such as `*1A`.
+/// Ordered by numeric value, which follows creation order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RosId(pub u32);
+
+impl FromStr for RosId {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_prefix('*')
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .map(RosId)
+            .ok_or_else(|| ParseValueError::new("an id", s))
+    }
+}
+
+impl Display for RosId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "*{:X}", self.0)
+    }
+}
+
+/// Parses a duration as printed by RouterOS: `1w2d3h4m5s`, `150ms`, or with a trailing clock such as `1d02:03:04`
+#[cfg_attr(not(feature = "ipsec"), allow(dead_code))]
+pub(crate) fn parse_duration(s: &str) -> Result<Duration, ParseValueError> {
+    let error = || ParseValueError::new("a duration", s);
+
+    if s.is_empty() {
+        return Err(error());
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = s;
+
+    if let Some(colon) = s.find(':') {
+        let start = s[..colon]
+            .rfind(|c: char| !c.is_ascii_digit())
+            .map_or(0, |i| i + 1);
+
+        let mut seconds = 0;
+        for part in s[start..].split(':') {
+            seconds = seconds * 60 + part.parse::<u64>().map_err(|_| error())?;
+        }
+
+        total += Duration::from_secs(seconds);
+        rest = &s[..start];
+    }
+
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(error)?;
+
+        let value: u64 = rest[..digits].parse().map_err(|_| error())?;
+        rest = &rest[digits..];
+
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+
+        let millis = match &rest[..unit] {
+            "w" => 7 * 24 * 3600 * 1000,
+            "d" => 24 * 3600 * 1000,
+            "h" => 3600 * 1000,
+            "m" => 60 * 1000,
+            "s" => 1000,
+            "ms" => 1,
+            _ => return Err(error()),
+        };
+
+        total += Duration::from_millis(value * millis);
+        rest = &rest[unit..];
+    }
+
+    Ok(total)
+}
+
+pub(crate) struct ParsedStr<T>(pub std::marker::PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for ParsedStr<T>
+where
+    T: FromStr<Err = ParseValueError>,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        v.parse().map_err(E::custom)
+    }
+}
+
+macro_rules! string_serde {
+    ($($ty:ty),*) => {
+        $(
+            impl<'de> serde::Deserialize<'de> for $ty {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    deserializer.deserialize_str($crate::api::model::types::ParsedStr(
+                        std::marker::PhantomData,
+                    ))
+                }
+            }
+
+            impl serde::Serialize for $ty {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    serializer.collect_str(self)
+                }
+            }
+        )*
+    };
+}
+
+pub(crate) use string_serde;
+
+string_serde!(MacAddress, RosId);
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_duration, MacAddress, RosId};
+
+    #[test]
+    fn test_mac_address() {
+        let mac: MacAddress = "4c-5e-0c-aa-bb-0c".parse().unwrap();
+
+        assert_eq!(MacAddress([0x4C, 0x5E, 0x0C, 0xAA, 0xBB, 0x0C]), mac);
+        assert_eq!("4C:5E:0C:AA:BB:0C", mac.to_string());
+
+        assert!("4C:5E:0C:AA:BB".parse::<MacAddress>().is_err());
+        assert!("4C:5E:0C:AA:BB:CC:DD".parse::<MacAddress>().is_err());
+        assert!("4C:5E:0C:AA:BB:GG".parse::<MacAddress>().is_err());
+    }
+
+    #[test]
+    fn test_ros_id() {
+        let id: RosId = "*1A".parse().unwrap();
+
+        assert_eq!(RosId(0x1A), id);
+        assert_eq!("*1A", id.to_string());
+        assert!(RosId(2) < id);
+
+        assert!("1A".parse::<RosId>().is_err());
+        assert!("*".parse::<RosId>().is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            Ok(Duration::from_secs(694_861)),
+            parse_duration("1w1d1h1m1s")
+        );
+        assert_eq!(Ok(Duration::from_millis(150)), parse_duration("150ms"));
+        assert_eq!(
+            Ok(Duration::from_secs(93_784)),
+            parse_duration("1d02:03:04")
+        );
+        assert_eq!(Ok(Duration::from_secs(312)), parse_duration("00:05:12"));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5").is_err());
+        assert!(parse_duration("5y").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::entity::RosEntity;
+
+use super::{MacAddress, RosId};
+
+/// An entry of `/interface/wifi`: an interface managed by the `wifi` package of RouterOS 7.13 and later
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WifiInterface {
+    /// `None` for an item yet to be added
+    #[serde(rename = ".id", default, skip_serializing)]
+    pub id: Option<RosId>,
+
+    pub name: String,
+
+    #[serde(skip_serializing)]
+    pub mac_address: Option<MacAddress>,
+
+    #[serde(default, skip_serializing)]
+    pub running: bool,
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub comment: Option<String>,
+}
+
+impl RosEntity for WifiInterface {
+    const PATH: &'static str = "/interface/wifi";
+
+    const PROPLIST: Option<&'static [&'static str]> = Some(&[
+        ".id",
+        "name",
+        "mac-address",
+        "running",
+        "disabled",
+        "comment",
+    ]);
+
+    const VERBS: &'static [&'static str] = &["print", "set"];
+
+    const MIN_VERSION: Option<&'static str> = Some("7.13");
+
+    fn id(&self) -> Option<String> {
+        self.id.map(|id| id.to_string())
+    }
+}
+
+/// An entry of `/interface/wireless/registration-table`, or of `/interface/wifi/registration-table` since RouterOS 7.13:
+/// a client associated with a local access point
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WirelessRegistration {
+    pub interface: String,
+    pub mac_address: MacAddress,
+
+    /// Only reported by `/interface/wifi`, the SSID of `/interface/wireless` being a property of the interface
+    pub ssid: Option<String>,
+
+    /// Signal strength, ie `-62dBm@6Mbps` or `-62`
+    #[serde(alias = "signal")]
+    pub signal_strength: Option<String>,
+
+    /// Time since the client associated, ie `1h2m3s`
+    pub uptime: Option<String>,
+}
+
+/// A BSS found by `/interface/wireless/scan`, or `/interface/wifi/scan` since RouterOS 7.13
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WirelessScan {
+    /// MAC address of the access point
+    #[serde(rename = "address")]
+    pub bssid: MacAddress,
+
+    /// Empty for hidden networks
+    #[serde(default)]
+    pub ssid: String,
+
+    /// Frequency, width and band, ie `2412/20-Ce/gn` or `5180/ax/Ceee`
+    pub channel: Option<String>,
+
+    /// Signal strength in dBm
+    #[serde(alias = "sig")]
+    pub signal_strength: Option<i32>,
+
+    /// Noise floor in dBm, only reported by `/interface/wireless`
+    #[serde(rename = "nf")]
+    pub noise_floor: Option<i32>,
+
+    /// Identity of MikroTik access points
+    pub radio_name: Option<String>,
+}
@@ -0,0 +1,407 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use log::warn;
+use tokio::{sync::broadcast, time::Instant};
+
+use super::{
+    audit::{AuditEvent, AuditSender, AUDIT_CAPACITY},
+    subscription::StreamLag,
+};
+
+#[cfg(feature = "metrics")]
+use super::stats::StatsReport;
+
+/// Callback invoked on every slow reply report
+pub type SlowReplyCallback = Arc<dyn Fn(&SlowReply) + Send + Sync>;
+
+/// Thresholds above which calls are reported as slow. Everything is disabled by default.
+#[derive(Clone, Default)]
+pub struct SlowReplyWarnings {
+    /// Report a command that has not received any sentence for this long. `listen` commands are never reported.
+    pub idle: Option<Duration>,
+
+    /// Report a sentence that took longer than this to be deserialized
+    pub deserialization: Option<Duration>,
+
+    /// Report a stream whose oldest item has been waiting for its consumer for longer than this
+    pub lag: Option<Duration>,
+
+    /// Called on every report, in addition to the warning being logged. Runs within the event loop, so it must not block.
+    pub callback: Option<SlowReplyCallback>,
+}
+
+/// A slow reply, as reported by the event loop
+#[derive(Debug, Clone)]
+pub enum SlowReply {
+    /// Command has been waiting for a sentence for too long
+    Idle {
+        /// Tag of the command
+        tag: u16,
+        /// Command, ie `/interface/print`
+        command: String,
+        /// Time since the command was sent or since its last sentence
+        waiting: Duration,
+    },
+
+    /// A single sentence took too long to be deserialized
+    Deserialization {
+        /// Tag of the command
+        tag: u16,
+        /// Command, ie `/interface/print`
+        command: String,
+        /// Time spent deserializing the sentence
+        took: Duration,
+    },
+
+    /// The consumer of a stream is falling behind
+    Lagging {
+        /// Tag of the command
+        tag: u16,
+        /// Command, ie `/interface/listen`
+        command: String,
+        /// Items waiting to be consumed, and the age of the oldest
+        lag: StreamLag,
+    },
+}
+
+struct PendingCall {
+    command: String,
+    streaming: bool,
+    last_activity: Instant,
+    reported: bool,
+    lagging: bool,
+
+    sentences: u64,
+    bytes: u64,
+}
+
+pub(crate) type SharedMonitor = Arc<Mutex<ReplyMonitor>>;
+
+/// Keeps track of in-flight calls on behalf of the event loop
+#[derive(Default)]
+pub(crate) struct ReplyMonitor {
+    config: SlowReplyWarnings,
+    pending: HashMap<u16, PendingCall>,
+    audit: Option<AuditSender>,
+
+    #[cfg(feature = "metrics")]
+    stats: StatsReport,
+}
+
+impl ReplyMonitor {
+    pub fn configure(&mut self, config: SlowReplyWarnings) {
+        self.config = config;
+    }
+
+    pub fn subscribe_audit(&mut self) -> broadcast::Receiver<AuditEvent> {
+        self.audit
+            .get_or_insert_with(|| broadcast::channel(AUDIT_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Publishes `event` to the audit log, if anyone ever subscribed to it
+    pub fn audit(&self, event: AuditEvent) {
+        if let Some(sender) = self.audit.as_ref() {
+            let _ = sender.send(event);
+        }
+    }
+
+    pub fn register(&mut self, tag: u16, command: &str, streaming: bool) {
+        self.pending.insert(
+            tag,
+            PendingCall {
+                command: command.to_owned(),
+                streaming,
+                last_activity: Instant::now(),
+                reported: false,
+                lagging: false,
+                sentences: 0,
+                bytes: 0,
+            },
+        );
+    }
+
+    /// A sentence of `bytes` bytes was received for `tag`
+    pub fn activity(&mut self, tag: u16, bytes: usize) {
+        if let Some(call) = self.pending.get_mut(&tag) {
+            call.last_activity = Instant::now();
+            call.reported = false;
+
+            call.sentences += 1;
+            call.bytes += bytes as u64;
+        }
+    }
+
+    pub fn deserialized(&self, tag: u16, took: Duration) {
+        let threshold = match self.config.deserialization {
+            Some(threshold) if took > threshold => threshold,
+            _ => return,
+        };
+
+        let command = self
+            .pending
+            .get(&tag)
+            .map(|call| call.command.clone())
+            .unwrap_or_default();
+
+        warn!(
+            "tag {} ({}): deserializing a sentence took {:?} (threshold: {:?})",
+            tag, command, took, threshold
+        );
+
+        self.report(&SlowReply::Deserialization { tag, command, took });
+    }
+
+    pub fn trapped(&self, tag: u16, message: &str) {
+        if let Some(call) = self.pending.get(&tag) {
+            self.audit(AuditEvent::Trap {
+                tag,
+                command: call.command.clone(),
+                message: message.to_owned(),
+            });
+        }
+    }
+
+    pub fn finished(&mut self, tag: u16) {
+        let call = self.pending.remove(&tag);
+
+        if let Some(call) = call.as_ref() {
+            self.audit(AuditEvent::Finished {
+                tag,
+                command: call.command.clone(),
+                sentences: call.sentences,
+            });
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(call) = call {
+            self.stats.record(&call.command, call.sentences, call.bytes);
+        }
+
+        #[cfg(not(feature = "metrics"))]
+        let _ = call;
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> StatsReport {
+        self.stats.clone()
+    }
+
+    /// Reports, once per idle period, calls that have been waiting for longer than the threshold
+    pub fn check_idle(&mut self) {
+        let threshold = match self.config.idle {
+            Some(threshold) => threshold,
+            None => return,
+        };
+
+        let mut reports = vec![];
+
+        for (tag, call) in self.pending.iter_mut() {
+            let waiting = call.last_activity.elapsed();
+
+            if call.streaming || call.reported || waiting <= threshold {
+                continue;
+            }
+
+            call.reported = true;
+
+            warn!(
+                "tag {} ({}): no reply for {:?} (threshold: {:?})",
+                tag, call.command, waiting, threshold
+            );
+
+            reports.push(SlowReply::Idle {
+                tag: *tag,
+                command: call.command.clone(),
+                waiting,
+            });
+        }
+
+        for report in reports {
+            self.report(&report);
+        }
+    }
+
+    /// Samples the lag of streams, reporting once those whose consumer is behind by more than the threshold,
+    /// until it catches up
+    pub fn check_lag(&mut self, lags: &[(u16, StreamLag)]) {
+        let mut reports = vec![];
+
+        for (tag, lag) in lags {
+            let call = match self.pending.get_mut(tag) {
+                Some(call) => call,
+                None => continue,
+            };
+
+            #[cfg(feature = "metrics")]
+            self.stats.record_lag(&call.command, lag);
+
+            let threshold = match self.config.lag {
+                Some(threshold) => threshold,
+                None => continue,
+            };
+
+            let behind = lag.oldest.is_some_and(|oldest| oldest > threshold);
+
+            if !behind || call.lagging {
+                call.lagging = behind;
+                continue;
+            }
+
+            call.lagging = true;
+
+            warn!(
+                "tag {} ({}): consumer lagging, {} item(s) waiting, oldest for {:?} (threshold: {:?})",
+                tag,
+                call.command,
+                lag.depth,
+                lag.oldest.unwrap_or_default(),
+                threshold
+            );
+
+            reports.push(SlowReply::Lagging {
+                tag: *tag,
+                command: call.command.clone(),
+                lag: *lag,
+            });
+        }
+
+        for report in reports {
+            self.report(&report);
+        }
+    }
+
+    fn report(&self, report: &SlowReply) {
+        if let Some(callback) = self.config.callback.as_ref() {
+            callback(report);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::{ReplyMonitor, SlowReply, SlowReplyWarnings};
+    use crate::api::subscription::StreamLag;
+
+    /// Monitor reporting into the returned list
+    fn monitor(config: SlowReplyWarnings) -> (ReplyMonitor, Arc<Mutex<Vec<SlowReply>>>) {
+        let reports = Arc::new(Mutex::new(vec![]));
+        let sink = reports.clone();
+
+        let mut monitor = ReplyMonitor::default();
+        monitor.configure(SlowReplyWarnings {
+            callback: Some(Arc::new(move |report: &SlowReply| {
+                sink.lock().unwrap().push(report.clone())
+            })),
+            ..config
+        });
+
+        (monitor, reports)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_warnings() {
+        let (mut monitor, reports) = monitor(SlowReplyWarnings {
+            idle: Some(Duration::from_secs(2)),
+            ..Default::default()
+        });
+
+        monitor.register(1, "/interface/print", false);
+        monitor.register(2, "/log/listen", true);
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        monitor.check_idle();
+        assert!(reports.lock().unwrap().is_empty());
+
+        // Past the threshold, reported once; streams never are
+        tokio::time::advance(Duration::from_millis(500)).await;
+        monitor.check_idle();
+        monitor.check_idle();
+        assert_eq!(1, reports.lock().unwrap().len());
+
+        // A sentence starts a new idle period
+        monitor.activity(1, 100);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        monitor.check_idle();
+        assert_eq!(1, reports.lock().unwrap().len());
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        monitor.check_idle();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(2, reports.len());
+        assert!(matches!(
+            &reports[1],
+            SlowReply::Idle { tag: 1, waiting, .. } if *waiting == Duration::from_secs(3)
+        ));
+    }
+
+    #[test]
+    fn test_deserialization_warnings() {
+        let (mut monitor, reports) = monitor(SlowReplyWarnings {
+            deserialization: Some(Duration::from_millis(10)),
+            ..Default::default()
+        });
+
+        monitor.register(1, "/interface/print", false);
+
+        monitor.deserialized(1, Duration::from_millis(10));
+        assert!(reports.lock().unwrap().is_empty());
+
+        // Every slow sentence is reported
+        monitor.deserialized(1, Duration::from_millis(11));
+        monitor.deserialized(1, Duration::from_millis(50));
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(2, reports.len());
+        assert!(matches!(
+            &reports[0],
+            SlowReply::Deserialization { tag: 1, command, took }
+                if command == "/interface/print" && *took == Duration::from_millis(11)
+        ));
+    }
+
+    #[test]
+    fn test_lag_warnings() {
+        let (mut monitor, reports) = monitor(SlowReplyWarnings {
+            lag: Some(Duration::from_secs(1)),
+            ..Default::default()
+        });
+        monitor.register(1, "/log/listen", true);
+
+        let lag = |depth, secs| StreamLag {
+            depth,
+            oldest: Some(Duration::from_secs(secs)),
+            dropped: 0,
+        };
+
+        monitor.check_lag(&[(1, lag(10, 0))]);
+        assert!(reports.lock().unwrap().is_empty());
+
+        // Reported once while the consumer stays behind
+        monitor.check_lag(&[(1, lag(50, 2))]);
+        monitor.check_lag(&[(1, lag(80, 3))]);
+        assert_eq!(1, reports.lock().unwrap().len());
+
+        // Then again once it caught up and fell behind anew
+        monitor.check_lag(&[(1, StreamLag::default())]);
+        monitor.check_lag(&[(1, lag(60, 2))]);
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(2, reports.len());
+        assert!(matches!(
+            &reports[1],
+            SlowReply::Lagging { tag: 1, lag, .. } if lag.depth == 60
+        ));
+    }
+}
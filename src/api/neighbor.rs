@@ -0,0 +1,135 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+use log::debug;
+
+use super::{
+    model::{ArpEntry, MacAddress},
+    Authenticated, Disconnected, MikrotikAPI,
+};
+
+/// Where to look for the IP address currently used by a MAC address
+pub enum NeighborSource<'a> {
+    /// ARP table (`/ip/arp`) of an already connected router, usually the one acting as gateway for the lab
+    Router(&'a mut MikrotikAPI<Authenticated>),
+
+    /// Neighbor table of the local host, as found in `/proc/net/arp` (Linux only)
+    LocalArp,
+}
+
+fn in_subnet(address: Ipv4Addr, (network, prefix): (Ipv4Addr, u8)) -> bool {
+    let mask = u32::MAX
+        .checked_shl(32 - u32::from(prefix.min(32)))
+        .unwrap_or(0);
+
+    u32::from(address) & mask == u32::from(network) & mask
+}
+
+fn parse_proc_arp(table: &str) -> Vec<(MacAddress, Ipv4Addr)> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+
+            let address = columns.next()?.parse().ok()?;
+            let mac = columns.nth(2)?.parse().ok()?;
+
+            Some((mac, address))
+        })
+        .collect()
+}
+
+impl<'a> NeighborSource<'a> {
+    /// Addresses known for `mac`
+    async fn lookup(self, mac: MacAddress) -> io::Result<Vec<Ipv4Addr>> {
+        let neighbors = match self {
+            NeighborSource::Router(api) => api
+                .list::<ArpEntry>()
+                .await
+                .map_err(io::Error::other)?
+                .into_iter()
+                .filter(|entry| !entry.invalid)
+                .filter_map(|entry| {
+                    let address = entry.address.parse().ok()?;
+                    Some((entry.mac_address?, address))
+                })
+                .collect(),
+
+            NeighborSource::LocalArp => {
+                let table = tokio::fs::read_to_string("/proc/net/arp").await?;
+                parse_proc_arp(&table)
+            }
+        };
+
+        Ok(neighbors
+            .into_iter()
+            .filter(|(neighbor, _)| *neighbor == mac)
+            .map(|(_, address)| address)
+            .collect())
+    }
+}
+
+/// Resolves the current IP address of the router with the given `mac` address through `source`,
+/// then opens a connection to it on `port`. Only addresses within `subnet` (network and prefix length) are considered.
+///
+/// Useful in labs where DHCP churn makes IP addresses unstable.
+pub async fn connect_by_mac(
+    mac: MacAddress,
+    subnet: (Ipv4Addr, u8),
+    port: u16,
+    source: NeighborSource<'_>,
+) -> io::Result<MikrotikAPI<Disconnected>> {
+    let address = source
+        .lookup(mac)
+        .await?
+        .into_iter()
+        .find(|address| in_subnet(*address, subnet))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no address found for {} in {}/{}", mac, subnet.0, subnet.1),
+            )
+        })?;
+
+    debug!("connect_by_mac: {} resolved to {}", mac, address);
+
+    crate::connect(SocketAddr::new(IpAddr::V4(address), port)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use crate::api::model::MacAddress;
+
+    use super::{in_subnet, parse_proc_arp};
+
+    #[test]
+    fn test_in_subnet() {
+        let subnet = (Ipv4Addr::new(192, 168, 88, 0), 24);
+
+        assert!(in_subnet(Ipv4Addr::new(192, 168, 88, 1), subnet));
+        assert!(!in_subnet(Ipv4Addr::new(192, 168, 89, 1), subnet));
+        assert!(in_subnet(
+            Ipv4Addr::new(10, 0, 0, 1),
+            (Ipv4Addr::UNSPECIFIED, 0)
+        ));
+    }
+
+    #[test]
+    fn test_parse_proc_arp() {
+        let table = "IP address       HW type     Flags       HW address            Mask     Device\n\
+                     192.168.88.1     0x1         0x2         4c:5e:0c:aa:bb:cc     *        eth0\n";
+
+        assert_eq!(
+            vec![(
+                MacAddress([0x4C, 0x5E, 0x0C, 0xAA, 0xBB, 0xCC]),
+                Ipv4Addr::new(192, 168, 88, 1)
+            )],
+            parse_proc_arp(table)
+        );
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    ops::ControlFlow,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use super::model::ActiveUser;
+
+/// Services through which a user gets a shell or a GUI, see `ActiveUserFilter::interactive`
+const INTERACTIVE_SERVICES: &[&str] = &["console", "ssh", "telnet", "web", "winbox"];
+
+/// Items a stream holds for its consumer by default, see `StreamBuffer`
+const DEFAULT_STREAM_CAPACITY: usize = 1024;
+
+/// Scheduling hint for a call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// Short calls a user is waiting on
+    #[default]
+    Interactive,
+
+    /// Large replies (prints of big tables, ...): the event loop yields to other tasks between each of their sentences,
+    /// so that interactive calls sharing the connection are not held back. Other calls can only be made meanwhile when
+    /// the replies are awaited through `generic_array_call_detached`
+    Bulk,
+}
+
+/// How `authenticate` logs in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoginMethod {
+    /// Sends the password in plain text, then answers the challenge if the router turns out to be older than 6.43
+    #[default]
+    Auto,
+
+    /// Login for RouterOS 6.43 and newer: the password is sent in plain text
+    Plaintext,
+
+    /// Login for RouterOS older than 6.43: the router sends a challenge, answered with an MD5 hash of the password
+    Challenge,
+}
+
+/// What the connection does when its `MemoryBudget` is exceeded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BudgetOverflow {
+    /// Stops reading from the socket until streams are consumed, the router then waiting for the connection to drain.
+    /// Falls back to `FailGreediest` when no stream holds anything that could be consumed, ie only array calls are in flight.
+    /// Streams dropped without being cancelled are failed rather than waited for, whichever the overflow policy
+    #[default]
+    Backpressure,
+
+    /// Fails the call holding the most bytes with `Error::MemoryBudgetExceeded`, a stream ending on a `!trap`.
+    /// `/cancel` is sent for it right away
+    FailGreediest,
+}
+
+/// Bound on the size of the replies a connection holds on behalf of its calls: replies of array calls still in progress,
+/// and items waiting in streams. Sizes are those of the sentences received, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// Bytes the replies may take up
+    pub limit: usize,
+
+    /// What happens past `limit`
+    pub overflow: BudgetOverflow,
+}
+
+impl MemoryBudget {
+    /// Budget of `limit` bytes, applying backpressure when exceeded
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            overflow: BudgetOverflow::default(),
+        }
+    }
+
+    /// Fails the greediest call when the budget is exceeded, see `BudgetOverflow::FailGreediest`
+    pub fn failing_greediest(mut self) -> Self {
+        self.overflow = BudgetOverflow::FailGreediest;
+        self
+    }
+}
+
+/// What a stream does with a new reply once its consumer is `StreamBuffer::capacity` items behind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamOverflow {
+    /// Drops the oldest reply waiting, to make room for the new one
+    #[default]
+    DropOldest,
+
+    /// Drops the new reply, keeping those waiting
+    DropNewest,
+}
+
+/// Bound on the items a stream holds until they are consumed. Every reply is numbered as it is received, dropped or not,
+/// so that consumers of `generic_sequenced_streaming_call` can tell a gap from the `StreamItem::seq` of the items.
+/// `!trap` and `!done` are never dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamBuffer {
+    /// Items waiting to be consumed
+    pub capacity: usize,
+
+    /// What happens past `capacity`
+    pub overflow: StreamOverflow,
+}
+
+impl Default for StreamBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_STREAM_CAPACITY)
+    }
+}
+
+impl StreamBuffer {
+    /// Buffer of `capacity` items, dropping the oldest when exceeded
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            overflow: StreamOverflow::default(),
+        }
+    }
+
+    /// Drops new replies rather than old ones when the buffer is full, see `StreamOverflow::DropNewest`
+    pub fn dropping_newest(mut self) -> Self {
+        self.overflow = StreamOverflow::DropNewest;
+        self
+    }
+}
+
+/// Options applying to a whole connection
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    /// Login method, detected from the router's answer by default
+    pub login: LoginMethod,
+
+    /// Bound on the replies buffered by the connection, unbounded by default
+    pub memory_budget: Option<MemoryBudget>,
+}
+
+impl ConnectOptions {
+    /// Bounds the replies buffered by the connection
+    pub fn with_memory_budget(mut self, budget: MemoryBudget) -> Self {
+        self.memory_budget = Some(budget);
+        self
+    }
+}
+
+/// Options applying to a single call
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    /// Scheduling hint, see `Priority`
+    pub priority: Priority,
+
+    /// Point in time after which the call is abandoned: `/cancel` is sent for its tag and `Error::DeadlineExceeded` is returned.
+    /// A stream simply ends at its deadline.
+    pub deadline: Option<Instant>,
+
+    /// Runs on every `!re` sentence of the call before it is deserialized, see `PreFilter`
+    pub pre_filter: Option<PreFilter>,
+
+    /// Abandons the call once cancelled, typically when the request it serves goes away: `/cancel` is sent for its tag
+    /// and `Error::Cancelled` is returned. Streams simply end, `/cancel` being sent for them by the event loop.
+    pub cancellation: Option<CancellationToken>,
+
+    /// Told how many `!re` sentences the call received so far, see `Progress`
+    pub progress: Option<Progress>,
+
+    /// Fails the call with `Error::UnknownFields` on replies holding properties the reply type has no field for,
+    /// rather than skipping them. Meant for tests of models against new RouterOS versions; a stream ends on such a reply.
+    /// Has no effect on replies deserialized into maps or `Lenient<T>`
+    pub strict: bool,
+
+    /// Bound on the items a stream holds for its consumer, 1024 replies by default
+    pub stream_buffer: StreamBuffer,
+}
+
+impl CallOptions {
+    /// Sets the deadline of the call
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the deadline of the call to `timeout` from now
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Ties the call to `token`, see `cancellation`
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Calls `progress` with the number of replies received so far, every time one comes in
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        self.progress = Some(Progress::new(progress));
+        self
+    }
+
+    /// Makes the call strict, see `strict`
+    pub fn with_strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Sets the bound on the items the stream holds, see `StreamBuffer`
+    pub fn with_stream_buffer(mut self, buffer: StreamBuffer) -> Self {
+        self.stream_buffer = buffer;
+        self
+    }
+
+    /// Sets the pre-filter of the call
+    pub fn with_pre_filter<F>(mut self, filter: F) -> Self
+    where
+        F: FnMut(&[String]) -> ControlFlow<()> + Send + 'static,
+    {
+        self.pre_filter = Some(PreFilter::new(filter));
+        self
+    }
+}
+
+/// Closure looking at the raw words of every `!re` sentence of a call, ie `["!re", ".tag=3", "=name=ether1", ...]`:
+/// `ControlFlow::Break` drops the sentence before it is deserialized, which is cheaper than filtering replies afterwards
+/// when the router cannot filter them itself. `!trap` and `!done` sentences are never filtered.
+///
+/// Runs within the event loop, so it must not block.
+#[derive(Clone)]
+pub struct PreFilter(Arc<Mutex<FilterFn>>);
+
+type FilterFn = dyn FnMut(&[String]) -> ControlFlow<()> + Send;
+
+impl PreFilter {
+    /// Wraps `filter`
+    pub fn new<F>(filter: F) -> Self
+    where
+        F: FnMut(&[String]) -> ControlFlow<()> + Send + 'static,
+    {
+        Self(Arc::new(Mutex::new(filter)))
+    }
+
+    /// Whether `sentence` is to be kept. A filter that panicked once keeps everything
+    pub(crate) fn keeps(&self, sentence: &[String]) -> bool {
+        match self.0.lock() {
+            Ok(mut filter) => filter(sentence).is_continue(),
+            Err(_) => true,
+        }
+    }
+}
+
+impl std::fmt::Debug for PreFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PreFilter")
+    }
+}
+
+/// Closure told the number of `!re` sentences received so far by a call, every time one comes in, ie to render a progress
+/// bar while a large table is being printed. Sentences dropped by a `PreFilter` are counted as well.
+///
+/// Runs within the event loop, so it must not block.
+#[derive(Clone)]
+pub struct Progress(Arc<Mutex<ProgressFn>>);
+
+type ProgressFn = dyn FnMut(usize) + Send;
+
+impl Progress {
+    /// Wraps `progress`
+    pub fn new<F>(progress: F) -> Self
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        Self(Arc::new(Mutex::new(progress)))
+    }
+
+    /// Reports `received` sentences. A closure that panicked once is not called anymore
+    pub(crate) fn report(&self, received: usize) {
+        if let Ok(mut progress) = self.0.lock() {
+            progress(received)
+        }
+    }
+}
+
+impl std::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Progress")
+    }
+}
+
+/// Selects the sessions reported by `active_users_with_filter` and `current_active_users`
+#[derive(Debug, Clone, Default)]
+pub struct ActiveUserFilter {
+    /// Leaves out the API sessions of the user this connection is authenticated as. Sessions are told apart by user name
+    /// only: every program logged in through the API with the same user is left out, not only this connection
+    pub exclude_self: bool,
+
+    /// Only keeps sessions opened through one of these services: `ssh`, `winbox`, `web`, `api`, ... Keeps all of them if empty
+    pub via: Vec<String>,
+
+    /// Only keeps sessions of users from this group
+    pub group: Option<String>,
+}
+
+impl ActiveUserFilter {
+    /// Only keeps logins of humans: console, SSH, telnet, web and Winbox sessions
+    pub fn interactive() -> Self {
+        Self {
+            exclude_self: true,
+            via: INTERACTIVE_SERVICES.iter().map(|s| s.to_string()).collect(),
+            group: None,
+        }
+    }
+
+    /// Query words selecting the sessions on the router's side, `self_user` being filtered client side
+    pub(crate) fn query(&self) -> Vec<(&str, &str)> {
+        let mut query: Vec<(&str, &str)> =
+            self.via.iter().map(|via| ("?via", via.as_str())).collect();
+
+        // n `?via` words need n - 1 `or` operations
+        for _ in 1..self.via.len() {
+            query.push(("?#|", ""));
+        }
+
+        if let Some(group) = self.group.as_deref() {
+            query.push(("?group", group));
+        }
+
+        query
+    }
+
+    /// Whether a login event matches the filter, `self_user` being the user of the connection
+    pub(crate) fn matches(&self, user: &ActiveUser, self_user: Option<&str>) -> bool {
+        let (name, via, group) = match user {
+            ActiveUser::Active {
+                name, via, group, ..
+            } => (name, via, group),
+
+            // Logouts only hold an id
+            ActiveUser::Dead(_) => return true,
+        };
+
+        let is_self = self.exclude_self && via == "api" && Some(name.as_str()) == self_user;
+
+        let in_group = match &self.group {
+            Some(g) => g == group,
+            None => true,
+        };
+
+        !is_self && (self.via.is_empty() || self.via.contains(via)) && in_group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::model::{ActiveUser, RosId};
+
+    use super::ActiveUserFilter;
+
+    fn login(name: &str, via: &str) -> ActiveUser {
+        ActiveUser::Active {
+            id: RosId(1),
+            when: "jan/02/2022 10:12:54".into(),
+            name: name.into(),
+            address: "192.168.88.10".into(),
+            via: via.into(),
+            group: "full".into(),
+            radius: false,
+        }
+    }
+
+    #[test]
+    fn test_interactive_filter() {
+        let filter = ActiveUserFilter::interactive();
+
+        assert!(filter.matches(&login("admin", "ssh"), Some("admin")));
+        assert!(!filter.matches(&login("admin", "api"), Some("admin")));
+        assert!(!filter.matches(&login("monitoring", "api"), Some("admin")));
+        assert!(filter.matches(&ActiveUser::Dead(RosId(1)), Some("admin")));
+    }
+
+    #[test]
+    fn test_filter_query() {
+        let filter = ActiveUserFilter {
+            exclude_self: false,
+            via: vec!["ssh".into(), "winbox".into(), "web".into()],
+            group: Some("full".into()),
+        };
+
+        assert_eq!(
+            vec![
+                ("?via", "ssh"),
+                ("?via", "winbox"),
+                ("?via", "web"),
+                ("?#|", ""),
+                ("?#|", ""),
+                ("?group", "full"),
+            ],
+            filter.query()
+        );
+    }
+}
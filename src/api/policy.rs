@@ -0,0 +1,114 @@
+use super::error::Error;
+
+/// Commands that are never subject to the policy, so that a session can always be opened and calls cancelled
+const ALWAYS_ALLOWED: &[&str] = &["/login", "/cancel"];
+
+/// Commands changing the configuration or the state of the router, denied by `Policy::read_only`
+const WRITE_PATTERNS: &[&str] = &[
+    "*/add",
+    "*/set",
+    "*/unset",
+    "*/remove",
+    "*/enable",
+    "*/disable",
+    "*/move",
+    "*/reset*",
+    "*/comment",
+    "*/import",
+    "*/run",
+    "/system/reboot",
+    "/system/shutdown",
+    "/system/reset-configuration",
+    "/execute",
+];
+
+/// Allow/deny rules checked against the path of every outgoing command, before it is sent.
+///
+/// Patterns are matched against the whole command path, `*` matching any sequence of characters (including `/`).
+/// A command is denied when it matches a deny pattern, unless it also matches an allow pattern.
+/// Denied commands fail with `Error::Denied`. The default policy allows everything.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl Policy {
+    /// Policy denying every command that modifies the router: `add`, `set`, `remove`, `enable`, reboots, scripts, ...
+    pub fn read_only() -> Self {
+        WRITE_PATTERNS
+            .iter()
+            .fold(Self::default(), |policy, pattern| policy.deny(pattern))
+    }
+
+    /// Allows commands matching `pattern`, even if they match a deny pattern
+    pub fn allow(mut self, pattern: &str) -> Self {
+        self.allow.push(pattern.to_owned());
+        self
+    }
+
+    /// Denies commands matching `pattern`
+    pub fn deny(mut self, pattern: &str) -> Self {
+        self.deny.push(pattern.to_owned());
+        self
+    }
+
+    /// Returns `Error::Denied` if `command` may not be sent
+    pub fn check(&self, command: &str) -> Result<(), Error> {
+        let matches = |patterns: &[String]| patterns.iter().any(|p| glob_match(p, command));
+
+        if ALWAYS_ALLOWED.contains(&command) || !matches(&self.deny) || matches(&self.allow) {
+            return Ok(());
+        }
+
+        Err(Error::Denied(command.to_owned()))
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+
+        Some((prefix, rest)) => {
+            let text = match text.strip_prefix(prefix) {
+                Some(text) => text,
+                None => return false,
+            };
+
+            (0..=text.len())
+                .filter(|i| text.is_char_boundary(*i))
+                .any(|i| glob_match(rest, &text[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::error::Error;
+
+    use super::{glob_match, Policy};
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*/set", "/ip/address/set"));
+        assert!(glob_match("/ip/*/print", "/ip/address/print"));
+        assert!(glob_match("/interface/*", "/interface/ethernet/print"));
+        assert!(!glob_match("*/set", "/ip/address/settings"));
+        assert!(!glob_match("/ip/*", "/interface/print"));
+    }
+
+    #[test]
+    fn test_read_only_policy() {
+        let policy = Policy::read_only().allow("/ip/dhcp-server/lease/remove");
+
+        assert!(policy.check("/interface/print").is_ok());
+        assert!(policy.check("/ip/dhcp-server/lease/remove").is_ok());
+        assert!(policy.check("/cancel").is_ok());
+
+        assert!(matches!(
+            policy.check("/ip/address/remove"),
+            Err(Error::Denied(command)) if command == "/ip/address/remove"
+        ));
+        assert!(policy.check("/system/reboot").is_err());
+    }
+}
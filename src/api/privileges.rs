@@ -0,0 +1,150 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::Serialize;
+
+use super::{error::Error, model::ActiveUser, Authenticated, MikrotikAPI};
+
+/// Outcome of `MikrotikAPI::verify_policies`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyCheck {
+    /// Group of the user of the connection
+    pub group: String,
+
+    /// Required policies the group does not grant, in the order they were given
+    pub missing: Vec<String>,
+}
+
+impl PolicyCheck {
+    /// Whether the group grants all the required policies
+    pub fn is_satisfied(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl Display for PolicyCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.is_satisfied() {
+            true => write!(f, "group '{}' grants all required policies", self.group),
+            false => write!(
+                f,
+                "group '{}' lacks the policies: {}",
+                self.group,
+                self.missing.join(", ")
+            ),
+        }
+    }
+}
+
+/// Policies granted by a `policy` property of `/user/group`, ie `read,write,!sensitive`: denied ones start with `!`
+fn granted(policy: &str) -> Vec<&str> {
+    policy
+        .split(',')
+        .filter(|policy| !policy.is_empty() && !policy.starts_with('!'))
+        .collect()
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Checks that the user of the connection is granted the `required` policies, ie `&["read", "write", "api"]`,
+    /// so that a workflow can fail fast instead of midway through a change.
+    ///
+    /// The group of the user is read from `/user/active`, then its policies from `/user/group`.
+    pub async fn verify_policies(&mut self, required: &[&str]) -> Result<PolicyCheck, Error> {
+        let user = self
+            .user
+            .clone()
+            .ok_or_else(|| Error::InvalidArgument("the connection has no user".into()))?;
+
+        let sessions = self
+            .generic_array_call::<ActiveUser>(
+                "/user/active/print",
+                Some(&[("?name", &user), ("?via", "api")]),
+            )
+            .await?;
+
+        let group = sessions
+            .into_iter()
+            .find_map(|session| match session {
+                ActiveUser::Active { group, .. } => Some(group),
+                ActiveUser::Dead(_) => None,
+            })
+            .ok_or_else(|| Error::InvalidArgument(format!("no API session of {}", user)))?;
+
+        let groups = self
+            .generic_array_call::<HashMap<String, String>>(
+                "/user/group/print",
+                Some(&[("?name", &group), ("=.proplist", "name,policy")]),
+            )
+            .await?;
+
+        let policy = groups
+            .into_iter()
+            .find_map(|mut item| item.remove("policy"))
+            .ok_or_else(|| Error::InvalidArgument(format!("no such group: {}", group)))?;
+
+        let granted = granted(&policy);
+
+        let missing = required
+            .iter()
+            .filter(|policy| !granted.contains(policy))
+            .map(|policy| policy.to_string())
+            .collect();
+
+        Ok(PolicyCheck { group, missing })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::MikrotikAPI,
+        mock::{MockReply, MockRouter},
+    };
+
+    /// Router on which `provision` belongs to a group denied `write`
+    fn mock_router() -> MockRouter {
+        let active = MockReply::new().re(&[
+            (".id", "*4"),
+            ("when", "2026-10-15 09:12:54"),
+            ("name", "provision"),
+            ("address", "10.0.0.5"),
+            ("via", "api"),
+            ("group", "monitoring"),
+            ("radius", "false"),
+        ]);
+
+        let group = MockReply::new().re(&[
+            ("name", "monitoring"),
+            ("policy", "local,read,test,api,!write,!policy,!sensitive"),
+        ]);
+
+        MockRouter::new()
+            .on("/user/active/print", active)
+            .on("/user/group/print", group)
+    }
+
+    #[tokio::test]
+    async fn test_verify_policies() {
+        let mut api = MikrotikAPI::from_transport(mock_router().spawn())
+            .authenticate("provision", "")
+            .await
+            .unwrap();
+
+        let check = api
+            .verify_policies(&["read", "write", "api", "sensitive"])
+            .await
+            .unwrap();
+
+        assert_eq!("monitoring", check.group);
+        assert_eq!(vec!["write", "sensitive"], check.missing);
+        assert_eq!(
+            "group 'monitoring' lacks the policies: write, sensitive",
+            check.to_string()
+        );
+
+        assert!(api
+            .verify_policies(&["read", "api"])
+            .await
+            .unwrap()
+            .is_satisfied());
+    }
+}
@@ -0,0 +1,101 @@
+use serde::Deserialize;
+
+use super::{
+    call::RawArrayCall, de::deserialize_sentence_borrowed, error::Error, model::Response,
+    Authenticated, MikrotikAPI,
+};
+
+/// Replies of a call kept as received by `MikrotikAPI::generic_array_call_borrowed`,
+/// to be deserialized into types borrowing from them, such as structs with `&str` fields
+#[derive(Debug, Clone, Default)]
+pub struct RawReplies {
+    sentences: Vec<Vec<String>>,
+}
+
+impl RawReplies {
+    /// Number of replies
+    pub fn len(&self) -> usize {
+        self.sentences.len()
+    }
+
+    /// Whether there is no reply at all
+    pub fn is_empty(&self) -> bool {
+        self.sentences.is_empty()
+    }
+
+    /// Deserializes every reply without copying its values: `&'de str` fields point into the replies.
+    /// Fails with `Error::Remote` if the router sent a `!trap`
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<Vec<T>, Error> {
+        let replies = self
+            .sentences
+            .iter()
+            .map(|sentence| deserialize_sentence_borrowed(sentence))
+            .collect::<Result<Vec<Response<T>>, _>>()?;
+
+        replies.into_iter().collect::<Response<Vec<T>>>().into()
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Same as `generic_array_call`, deserialization being left to the caller so that the replies can be borrowed from
+    pub async fn generic_array_call_borrowed(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+    ) -> Result<RawReplies, Error> {
+        let sentences = self
+            .do_call(command, attributes, RawArrayCall::new(), None)
+            .await?
+            .await?;
+
+        Ok(RawReplies { sentences })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::api::error::Error;
+
+    use super::RawReplies;
+
+    #[derive(Debug, Deserialize)]
+    struct Address<'a> {
+        address: &'a str,
+        interface: &'a str,
+    }
+
+    fn replies(sentences: &[&[&str]]) -> RawReplies {
+        let sentences = sentences
+            .iter()
+            .map(|words| words.iter().map(|w| w.to_string()).collect())
+            .collect();
+
+        RawReplies { sentences }
+    }
+
+    #[test]
+    fn test_borrowed_fields() {
+        let replies = replies(&[
+            &["!re", "=address=192.168.88.1/24", "=interface=bridge", ""],
+            &["!re", "=address=10.0.0.1/8", "=interface=ether1", ""],
+        ]);
+
+        let addresses = replies.deserialize::<Address>().unwrap();
+
+        assert_eq!(2, addresses.len());
+        assert_eq!("10.0.0.1/8", addresses[1].address);
+        assert_eq!("ether1", addresses[1].interface);
+    }
+
+    #[test]
+    fn test_trap() {
+        let replies = replies(&[&["!trap", "=message=no such command", ""]]);
+
+        assert!(matches!(
+            replies.deserialize::<Address>(),
+            Err(Error::Remote(message)) if message == "no such command"
+        ));
+    }
+}
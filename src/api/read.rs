@@ -1,96 +1,100 @@
-use std::io::Cursor;
+use std::{borrow::Cow, io::Cursor};
 
 use bytes::Buf;
 
 use super::error::Error;
 
+/// Bound on the size of a sentence received, length prefixes included. Anything announcing more is taken for a corrupt
+/// or hostile stream rather than buffered, as a length prefix alone can announce a 4 GiB word
+pub(crate) const MAX_SENTENCE_LEN: usize = 16 * 1024 * 1024;
+
 fn get_byte(cursor: &mut Cursor<&[u8]>) -> Option<u8> {
     cursor.has_remaining().then(|| cursor.get_u8())
 }
 
-fn read_len(cursor: &mut Cursor<&[u8]>) -> Result<u32, Error> {
+pub(crate) fn read_len(cursor: &mut Cursor<&[u8]>) -> Result<u32, Error> {
     let mut next_byte = || get_byte(cursor).ok_or(Error::Incomplete);
 
     let first_byte = next_byte()?;
 
-    if first_byte >> 7 == 0b0 {
-        return Ok(first_byte as u32);
-    }
-
-    let mut data: [u8; 4] = [0; 4];
-
-    if first_byte >> 6 == 0b10 {
-        data[2] = first_byte & !0xC0;
-        data[3] = next_byte()?;
-
-        return Ok(u32::from_be_bytes(data));
-    }
-
-    if first_byte >> 5 == 0b110 {
-        data[1] = first_byte & !0xE0;
-        data[2] = next_byte()?;
-        data[3] = next_byte()?;
-
-        return Ok(u32::from_be_bytes(data));
-    }
-
-    if first_byte >> 4 == 0b1110 {
-        data[0] = first_byte & !0xF0;
-        data[1] = next_byte()?;
-        data[2] = next_byte()?;
-        data[3] = next_byte()?;
-
-        return Ok(u32::from_be_bytes(data));
-    }
+    // Number of bytes following the first one, and the bits of the first one that are part of the length
+    let (following, high_bits) = match first_byte {
+        0x00..=0x7F => (0, first_byte),
+        0x80..=0xBF => (1, first_byte & 0x3F),
+        0xC0..=0xDF => (2, first_byte & 0x1F),
+        0xE0..=0xEF => (3, first_byte & 0x0F),
+        0xF0 => (4, 0),
+        // 0xF1 to 0xFF are reserved for control bytes, which the API never sends
+        _ => {
+            return Err(Error::Malformed(format!(
+                "unexpected control byte {:#x}",
+                first_byte
+            )))
+        }
+    };
 
-    if first_byte == 0xF0 {
-        data[0] = next_byte()?;
-        data[1] = next_byte()?;
-        data[2] = next_byte()?;
-        data[3] = next_byte()?;
+    // Most significant byte first, whatever the endianness of the target
+    let mut len = high_bits as u32;
 
-        return Ok(u32::from_be_bytes(data));
+    for _ in 0..following {
+        len = (len << 8) | next_byte()? as u32;
     }
 
-    unreachable!()
+    Ok(len)
 }
 
 fn read_bytes<'buf>(cursor: &mut Cursor<&'buf [u8]>, len: u32) -> Result<&'buf [u8], Error> {
     let start = cursor.position() as usize;
-    let end = cursor.get_ref().len();
 
-    let remaining = end - start;
+    let len = len as usize;
 
-    if len > (remaining as u32) {
+    if len > cursor.remaining() {
         return Err(Error::Incomplete);
     }
 
-    cursor.set_position((start + len as usize) as u64);
+    cursor.set_position((start + len) as u64);
 
-    Ok(&cursor.get_ref()[start..start + (len as usize)])
+    Ok(&cursor.get_ref()[start..start + len])
 }
 
-fn read_word<'buf>(cursor: &mut Cursor<&'buf [u8]>) -> Result<&'buf str, Error> {
+/// Reads a word, decoding it as Latin-1 if it is not valid UTF-8: routers set up before UTF-8 support send identities
+/// and comments in the code page of the Winbox that typed them. Every byte then maps to the char of the same value,
+/// so that `escape::latin1_bytes` gets the original bytes back
+fn read_word<'buf>(cursor: &mut Cursor<&'buf [u8]>, room: usize) -> Result<Cow<'buf, str>, Error> {
     let str_len = read_len(cursor)?;
 
-    let str_bytes = read_bytes(cursor, str_len)?;
+    if str_len as usize > room {
+        return Err(Error::Malformed(format!(
+            "word of {} bytes, sentences being {} bytes at most",
+            str_len, MAX_SENTENCE_LEN
+        )));
+    }
 
-    let text = unsafe { core::str::from_utf8_unchecked(str_bytes) };
+    let str_bytes = read_bytes(cursor, str_len)?;
 
-    Ok(text)
+    match core::str::from_utf8(str_bytes) {
+        Ok(word) => Ok(Cow::Borrowed(word)),
+        Err(_) => Ok(Cow::Owned(
+            str_bytes.iter().map(|&b| char::from(b)).collect(),
+        )),
+    }
 }
 
-pub fn read_sentence<'buf>(cursor: &mut Cursor<&'buf [u8]>) -> Result<Vec<&'buf str>, Error> {
+pub fn read_sentence<'buf>(cursor: &mut Cursor<&'buf [u8]>) -> Result<Vec<Cow<'buf, str>>, Error> {
+    let start = cursor.position() as usize;
     let mut sentence = vec![];
 
     loop {
-        match read_word(cursor)? {
-            empty @ "" => {
-                sentence.push(empty);
-                break;
-            }
+        let word = read_word(
+            cursor,
+            MAX_SENTENCE_LEN - (cursor.position() as usize - start),
+        )?;
+        let last = word.is_empty();
 
-            word => sentence.push(word),
+        sentence.push(word);
+
+        if last {
+            break;
         }
     }
 
@@ -101,7 +105,51 @@ pub fn read_sentence<'buf>(cursor: &mut Cursor<&'buf [u8]>) -> Result<Vec<&'buf
 mod tests {
     use std::io::Cursor;
 
-    use super::read_len;
+    use super::{read_len, read_sentence, MAX_SENTENCE_LEN};
+    use crate::api::{encode_sentence, error::Error, len_prefix};
+
+    /// Length prefixes as spelled out in the spec, around each boundary between encodings. Being written byte by byte,
+    /// they hold on big-endian targets as well
+    const WIRE_LENGTHS: &[(u32, &[u8])] = &[
+        (0x00, &[0x00]),
+        (0x7F, &[0x7F]),
+        (0x80, &[0x80, 0x80]),
+        (0x3FFF, &[0xBF, 0xFF]),
+        (0x4000, &[0xC0, 0x40, 0x00]),
+        (0x1FFFFF, &[0xDF, 0xFF, 0xFF]),
+        (0x200000, &[0xE0, 0x20, 0x00, 0x00]),
+        (0xFFFFFFF, &[0xEF, 0xFF, 0xFF, 0xFF]),
+        (0x10000000, &[0xF0, 0x10, 0x00, 0x00, 0x00]),
+        (0xAABBCCDD, &[0xF0, 0xAA, 0xBB, 0xCC, 0xDD]),
+        (u32::MAX, &[0xF0, 0xFF, 0xFF, 0xFF, 0xFF]),
+    ];
+
+    #[test]
+    fn test_len_wire_format() {
+        for &(len, bytes) in WIRE_LENGTHS {
+            assert_eq!(bytes, &len_prefix(len)[..], "encoding {:#x}", len);
+
+            let mut cursor = Cursor::new(bytes);
+
+            assert_eq!(len, read_len(&mut cursor).unwrap(), "decoding {:x?}", bytes);
+            assert_eq!(bytes.len() as u64, cursor.position());
+        }
+    }
+
+    #[test]
+    fn test_len_round_trip() {
+        let lengths = (0..32).flat_map(|bit| {
+            let len = 1u32 << bit;
+            [len - 1, len, len | 1]
+        });
+
+        for len in lengths {
+            let bytes = len_prefix(len);
+            let mut cursor = Cursor::new(&bytes[..]);
+
+            assert_eq!(len, read_len(&mut cursor).unwrap());
+        }
+    }
 
     #[test]
     fn test_readlen_one_byte() {
@@ -156,7 +204,7 @@ mod tests {
         let value = result.unwrap();
         eprintln!("{:x}", value);
 
-        assert_eq!(test_value as u32, value);
+        assert_eq!(test_value, value);
     }
 
     #[test]
@@ -175,7 +223,7 @@ mod tests {
         let value = result.unwrap();
         eprintln!("{:x}", value);
 
-        assert_eq!(test_value as u32, value);
+        assert_eq!(test_value, value);
     }
 
     #[test]
@@ -197,6 +245,88 @@ mod tests {
         let value = result.unwrap();
         eprintln!("{:x}", value);
 
-        assert_eq!(test_value as u32, value);
+        assert_eq!(test_value, value);
+    }
+
+    #[test]
+    fn test_readlen_control_byte() {
+        let mut cursor = Cursor::new(&[0xF8u8, 0x00][..]);
+
+        assert!(matches!(read_len(&mut cursor), Err(Error::Malformed(_))));
+    }
+
+    #[test]
+    fn test_read_sentence_truncated() {
+        let bytes = encode_sentence(&["!re", "=name=ether1"]).unwrap();
+
+        for end in 0..bytes.len() {
+            let mut cursor = Cursor::new(&bytes[..end]);
+
+            assert!(matches!(read_sentence(&mut cursor), Err(Error::Incomplete)));
+        }
+    }
+
+    #[test]
+    fn test_read_sentence_too_long() {
+        // Length prefix announcing more bytes than could ever be buffered
+        let mut cursor = Cursor::new(&[0xF0u8, 0xFF, 0xFF, 0xFF, 0xFF, b'a'][..]);
+        assert!(matches!(
+            read_sentence(&mut cursor),
+            Err(Error::Malformed(_))
+        ));
+
+        // Words fitting on their own, but not together
+        let half = "x".repeat(MAX_SENTENCE_LEN / 2);
+        let bytes = encode_sentence(&["!re", &half, &half]).unwrap();
+
+        let mut cursor = Cursor::new(&bytes[..bytes.len() / 2]);
+        assert!(matches!(read_sentence(&mut cursor), Err(Error::Incomplete)));
+
+        let mut cursor = Cursor::new(&bytes[..]);
+        assert!(matches!(
+            read_sentence(&mut cursor),
+            Err(Error::Malformed(_))
+        ));
+
+        let bytes = encode_sentence(&["!re", &half[..MAX_SENTENCE_LEN / 4]]).unwrap();
+        let mut cursor = Cursor::new(&bytes[..]);
+        assert!(read_sentence(&mut cursor).is_ok());
+    }
+
+    #[test]
+    fn test_read_sentence_invalid_utf8() {
+        // `=name=Роутер` in Windows-1251
+        let bytes = [
+            0x0C, b'=', b'n', b'a', b'm', b'e', b'=', 0xD0, 0xEE, 0xF3, 0xF2, 0xE5, 0xF0, 0x00,
+        ];
+        let mut cursor = Cursor::new(&bytes[..]);
+
+        let sentence = read_sentence(&mut cursor).unwrap();
+
+        assert_eq!("=name=\u{d0}\u{ee}\u{f3}\u{f2}\u{e5}\u{f0}", sentence[0]);
+        assert_eq!(
+            Some(bytes[7..13].to_vec()),
+            crate::api::escape::latin1_bytes(&sentence[0][6..])
+        );
+
+        // Valid UTF-8 is left as is
+        let bytes = encode_sentence(&["=name=Роутер"]).unwrap();
+        let mut cursor = Cursor::new(&bytes[..]);
+
+        assert_eq!("=name=Роутер", read_sentence(&mut cursor).unwrap()[0]);
+    }
+
+    #[test]
+    fn test_long_words_round_trip() {
+        let long = "x".repeat(0x4000);
+        let words = ["!re", &long[..200], &long[..0x3FFF], &long];
+
+        let bytes = encode_sentence(&words).unwrap();
+        let mut cursor = Cursor::new(&bytes[..]);
+
+        let sentence = read_sentence(&mut cursor).unwrap();
+
+        assert_eq!(&words[..], &sentence[..4]);
+        assert_eq!(bytes.len() as u64, cursor.position());
     }
 }
@@ -0,0 +1,387 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use log::warn;
+use rand::Rng;
+use tokio::{
+    net::{lookup_host, ToSocketAddrs},
+    sync::OwnedMutexGuard,
+};
+
+use super::{
+    error::Error,
+    options::ConnectOptions,
+    secrets::{Credentials, SecretsSource, StaticSecrets},
+    Authenticated, MikrotikAPI,
+};
+
+/// Why `ReconnectPolicy::connect` gave up
+#[derive(Debug)]
+pub enum ConnectFailure {
+    /// The router could not be reached or the connection dropped during login (refused, timed out, reset, ...).
+    /// Retried until the attempts are exhausted, this holds the last error
+    Network(io::Error),
+
+    /// The router rejected the credentials, holding its message. Never retried, so that a bad password
+    /// does not lock the account out
+    Authentication(String),
+
+    /// The router has too many API sessions open, holding its message.
+    /// Retried like network failures, this is the last error once the attempts are exhausted
+    ConnectionLimit(String),
+
+    /// Any other error, not retried either
+    Fatal(Error),
+}
+
+impl std::fmt::Display for ConnectFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ConnectFailure::*;
+        match self {
+            Network(e) => write!(f, "router unreachable: {}", e),
+
+            Authentication(message) => write!(f, "authentication failed: {}", message),
+
+            ConnectionLimit(message) => write!(f, "connection limit reached: {}", message),
+
+            Fatal(e) => write!(f, "connection failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConnectFailure {}
+
+impl From<Error> for ConnectFailure {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => ConnectFailure::Network(e),
+
+            Error::EndOfStream => {
+                ConnectFailure::Network(io::Error::from(io::ErrorKind::UnexpectedEof))
+            }
+
+            Error::ConnectionLost(reason) => {
+                ConnectFailure::Network(io::Error::new(io::ErrorKind::ConnectionAborted, reason))
+            }
+
+            Error::ConnectionLimit(message) => ConnectFailure::ConnectionLimit(message),
+
+            Error::Remote(message) => ConnectFailure::Authentication(message),
+
+            e => ConnectFailure::Fatal(e),
+        }
+    }
+}
+
+/// Serializes login attempts to the same router, shared by cloning it into the `ReconnectPolicy` of every connection.
+///
+/// When many instances of a service start at once, they otherwise all log in together and can exhaust the router's
+/// API session limit. Attempts to different routers are not held up.
+#[derive(Debug, Clone, Default)]
+pub struct LoginGate {
+    targets: Arc<Mutex<HashMap<SocketAddr, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl LoginGate {
+    /// Gate letting through one login at a time per router
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits for the logins to `target` in progress, the returned guard letting the next one through once dropped
+    async fn enter(&self, target: SocketAddr) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut targets = self.targets.lock().unwrap_or_else(|e| e.into_inner());
+
+            targets.entry(target).or_default().clone()
+        };
+
+        lock.lock_owned().await
+    }
+}
+
+async fn resolve<A: ToSocketAddrs>(addr: A) -> io::Result<SocketAddr> {
+    lookup_host(addr)
+        .await?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address resolved to nothing"))
+}
+
+/// How to connect and authenticate to a router that may be temporarily unreachable.
+///
+/// Network failures are retried with an exponential backoff, while authentication failures are surfaced right away.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_attempts: Option<u32>,
+    timeout: Duration,
+    jitter: bool,
+    gate: Option<LoginGate>,
+    options: ConnectOptions,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_attempts: None,
+            timeout: Duration::from_secs(10),
+            jitter: true,
+            gate: None,
+            options: ConnectOptions::default(),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Retries forever, waiting from 1 second up to a minute between attempts, each attempt lasting at most 10 seconds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits between the first two attempts, doubled after each failure
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Maximum wait between two attempts
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Gives up after `attempts` network failures
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts.max(1));
+        self
+    }
+
+    /// Time allowed to connect and authenticate, an attempt taking longer being a network failure
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether to wait a random time between half the backoff and the backoff itself, rather than the backoff,
+    /// so that instances which failed together do not retry together. Enabled by default
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Logins go through `gate`, one at a time per router
+    pub fn login_gate(mut self, gate: LoginGate) -> Self {
+        self.gate = Some(gate);
+        self
+    }
+
+    /// Options of the connections
+    pub fn options(mut self, options: ConnectOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Connects to `addr` and authenticates, retrying network failures according to the policy
+    pub async fn connect<A>(
+        &self,
+        addr: A,
+        login: &str,
+        password: &str,
+    ) -> Result<MikrotikAPI<Authenticated>, ConnectFailure>
+    where
+        A: ToSocketAddrs + Clone,
+    {
+        let secrets = StaticSecrets::new().fallback(Credentials::new(login, password));
+
+        self.connect_with_secrets(addr, "", &secrets).await
+    }
+
+    /// Same as `connect`, the credentials of the router `router_id` being fetched from `secrets` before every attempt
+    pub async fn connect_with_secrets<A>(
+        &self,
+        addr: A,
+        router_id: &str,
+        secrets: &dyn SecretsSource,
+    ) -> Result<MikrotikAPI<Authenticated>, ConnectFailure>
+    where
+        A: ToSocketAddrs + Clone,
+    {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+
+        loop {
+            let result = tokio::time::timeout(self.timeout, async {
+                let credentials = secrets.credentials(router_id).await?;
+
+                let _entered = match &self.gate {
+                    Some(gate) => Some(gate.enter(resolve(addr.clone()).await?).await),
+                    None => None,
+                };
+
+                crate::connect_with_options(addr.clone(), self.options.clone())
+                    .await?
+                    .authenticate(&credentials.user, &credentials.password)
+                    .await
+            })
+            .await
+            .unwrap_or_else(|_| Err(Error::Io(io::ErrorKind::TimedOut.into())));
+
+            let failure = match result.map_err(ConnectFailure::from) {
+                Err(
+                    failure @ (ConnectFailure::Network(_) | ConnectFailure::ConnectionLimit(_)),
+                ) => failure,
+
+                other => return other,
+            };
+
+            if self.max_attempts.is_some_and(|max| attempt >= max) {
+                return Err(failure);
+            }
+
+            let wait = match self.jitter {
+                true => rand::thread_rng().gen_range(backoff / 2..=backoff),
+                false => backoff,
+            };
+
+            warn!(
+                "connect: attempt {} failed ({}), retrying in {:?}",
+                attempt, failure, wait
+            );
+
+            tokio::time::sleep(wait).await;
+
+            backoff = (backoff * 2).min(self.max_backoff);
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use crate::{
+        bench_support::{decode_sentence, encode_sentence},
+        mock::{MockReply, MockRouter},
+    };
+
+    use super::{ConnectFailure, LoginGate, ReconnectPolicy};
+
+    fn policy() -> ReconnectPolicy {
+        ReconnectPolicy::new()
+            .initial_backoff(Duration::from_millis(1))
+            .max_attempts(3)
+    }
+
+    #[tokio::test]
+    async fn test_authentication_failure_is_not_retried() {
+        let router = MockRouter::new().on(
+            "/login",
+            MockReply::new().trap("invalid user name or password"),
+        );
+        let received = router.received();
+
+        let addr = router.listen().await.unwrap();
+
+        let failure = policy().connect(addr, "admin", "wrong").await.err();
+
+        assert!(matches!(failure, Some(ConnectFailure::Authentication(_))));
+        assert_eq!(1, received.sentences().len());
+    }
+
+    #[tokio::test]
+    async fn test_network_failure_is_retried() {
+        // Grab a free port, then close it so that connections are refused
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let failure = policy().connect(addr, "admin", "").await.err();
+
+        assert!(matches!(failure, Some(ConnectFailure::Network(_))));
+    }
+
+    #[tokio::test]
+    async fn test_connection_limit_is_distinct() {
+        let router = MockRouter::new().on(
+            "/login",
+            MockReply::new().fatal("too many API sessions, limit reached"),
+        );
+
+        let addr = router.listen().await.unwrap();
+
+        let failure = policy().connect(addr, "admin", "").await.err();
+
+        assert!(matches!(failure, Some(ConnectFailure::ConnectionLimit(_))));
+    }
+
+    #[tokio::test]
+    async fn test_login_gate_serializes_logins() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let active = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+
+        let (counter, high) = (active.clone(), most.clone());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let (counter, high) = (counter.clone(), high.clone());
+
+                tokio::spawn(async move {
+                    let mut buffer = vec![0u8; 1024];
+                    let read = socket.read(&mut buffer).await.unwrap();
+
+                    let (words, _) = decode_sentence(&buffer[..read]).unwrap();
+                    let tag = words.iter().find(|w| w.starts_with(".tag=")).unwrap();
+
+                    let logging_in = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                    high.fetch_max(logging_in, Ordering::SeqCst);
+
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    counter.fetch_sub(1, Ordering::SeqCst);
+
+                    socket
+                        .write_all(&encode_sentence(&["!done", tag]).unwrap())
+                        .await
+                        .unwrap();
+
+                    // Keep the session open
+                    let _ = socket.read(&mut buffer).await;
+                });
+            }
+        });
+
+        let policy = policy().login_gate(LoginGate::new());
+
+        let (a, b, c) = tokio::join!(
+            policy.connect(addr, "admin", ""),
+            policy.connect(addr, "admin", ""),
+            policy.connect(addr, "admin", "")
+        );
+
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(1, most.load(Ordering::SeqCst));
+    }
+}
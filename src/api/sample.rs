@@ -0,0 +1,96 @@
+use std::{pin::pin, time::Duration};
+
+use futures::{future, Stream, StreamExt};
+use tokio::time::Instant;
+
+use super::{
+    error::Error,
+    model::{Response, FATAL},
+    Authenticated, MikrotikAPI,
+};
+
+impl MikrotikAPI<Authenticated> {
+    /// Collects the first `n` replies of `stream`, then cancels the command through its `tag`.
+    /// Returns early if the command ends by itself, and fails on a `!trap`.
+    ///
+    /// ```rust,ignore
+    /// let mut tag = 0;
+    /// let stream = api.generic_streaming_call::<Torch>("/tool/torch", Some(&[("interface", "ether1")]), &mut tag).await?;
+    ///
+    /// let first = api.collect_n(stream, tag, 10).await?;
+    /// ```
+    pub async fn collect_n<T, S>(&mut self, stream: S, tag: u16, n: usize) -> Result<Vec<T>, Error>
+    where
+        S: Stream<Item = Response<T>>,
+    {
+        self.collect_until(stream, tag, Some(n), None).await
+    }
+
+    /// Collects the replies of `stream` for `duration`, ie a 10 seconds sample of `/tool/torch`,
+    /// then cancels the command through its `tag`. Returns early if the command ends by itself, and fails on a `!trap`.
+    pub async fn collect_for<T, S>(
+        &mut self,
+        stream: S,
+        tag: u16,
+        duration: Duration,
+    ) -> Result<Vec<T>, Error>
+    where
+        S: Stream<Item = Response<T>>,
+    {
+        self.collect_until(stream, tag, None, Some(Instant::now() + duration))
+            .await
+    }
+
+    async fn collect_until<T, S>(
+        &mut self,
+        stream: S,
+        tag: u16,
+        limit: Option<usize>,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<T>, Error>
+    where
+        S: Stream<Item = Response<T>>,
+    {
+        let mut stream = pin!(stream);
+        let mut replies = vec![];
+
+        let mut sleep = pin!(async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => future::pending().await,
+            }
+        });
+
+        while !matches!(limit, Some(limit) if replies.len() >= limit) {
+            let item = tokio::select! {
+                item = stream.next() => item,
+                _ = &mut sleep => break,
+            };
+
+            match item {
+                Some(Response::Reply(reply)) => replies.push(reply),
+
+                Some(Response::Done) | None => {
+                    self.forget_tag(tag);
+                    return Ok(replies);
+                }
+
+                Some(Response::Trap { message, .. }) => {
+                    self.forget_tag(tag);
+                    return Err(Error::Remote(message));
+                }
+
+                Some(Response::Fatal) => {
+                    self.forget_tag(tag);
+                    return Err(Error::ConnectionLost(FATAL.to_owned()));
+                }
+            }
+        }
+
+        // A stalled router is a reason to stop sampling: /cancel is sent without waiting for its answer
+        self.forget_tag(tag);
+        self.cancels.cancel(tag);
+
+        Ok(replies)
+    }
+}
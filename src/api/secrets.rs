@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use futures::future::{self, BoxFuture};
+
+use super::error::Error;
+
+/// User name and password of a router
+#[derive(Clone)]
+pub struct Credentials {
+    /// User name
+    pub user: String,
+
+    /// Password
+    pub password: String,
+}
+
+impl Credentials {
+    /// Credentials of `user`
+    pub fn new(user: &str, password: &str) -> Self {
+        Self {
+            user: user.to_owned(),
+            password: password.to_owned(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("user", &self.user)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Where the credentials of a fleet of routers come from, see `ReconnectPolicy::connect_with_secrets`.
+///
+/// Credentials are fetched before every connection attempt, so that rotated passwords are picked up.
+/// Implement this trait to plug in a secret store:
+///
+/// ```no_run
+/// use futures::future::BoxFuture;
+/// use mikrotik_api::{Credentials, Error, SecretsSource};
+///
+/// struct Vault {
+///     client: vault_client::Client,
+/// }
+/// # mod vault_client {
+/// #     pub struct Client;
+/// #     impl Client {
+/// #         pub async fn read(&self, _path: &str) -> Option<(String, String)> { None }
+/// #     }
+/// # }
+///
+/// impl SecretsSource for Vault {
+///     fn credentials<'a>(&'a self, router_id: &'a str) -> BoxFuture<'a, Result<Credentials, Error>> {
+///         Box::pin(async move {
+///             let path = format!("secret/routers/{}", router_id);
+///
+///             match self.client.read(&path).await {
+///                 Some((user, password)) => Ok(Credentials { user, password }),
+///                 None => Err(Error::MissingCredentials(router_id.to_owned())),
+///             }
+///         })
+///     }
+/// }
+/// ```
+pub trait SecretsSource: Send + Sync {
+    /// Credentials of the router identified by `router_id`, an id meaningful to the implementation (name, address, ...)
+    fn credentials<'a>(&'a self, router_id: &'a str) -> BoxFuture<'a, Result<Credentials, Error>>;
+}
+
+/// Credentials held in memory, by router id, with optional fallback credentials
+#[derive(Debug, Clone, Default)]
+pub struct StaticSecrets {
+    routers: HashMap<String, Credentials>,
+    fallback: Option<Credentials>,
+}
+
+impl StaticSecrets {
+    /// No credentials at all
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the credentials of the router `router_id`
+    pub fn with(mut self, router_id: &str, credentials: Credentials) -> Self {
+        self.routers.insert(router_id.to_owned(), credentials);
+        self
+    }
+
+    /// Sets the credentials of routers without specific ones
+    pub fn fallback(mut self, credentials: Credentials) -> Self {
+        self.fallback = Some(credentials);
+        self
+    }
+}
+
+impl SecretsSource for StaticSecrets {
+    fn credentials<'a>(&'a self, router_id: &'a str) -> BoxFuture<'a, Result<Credentials, Error>> {
+        let credentials = self
+            .routers
+            .get(router_id)
+            .or(self.fallback.as_ref())
+            .cloned()
+            .ok_or_else(|| Error::MissingCredentials(router_id.to_owned()));
+
+        Box::pin(future::ready(credentials))
+    }
+}
+
+/// Credentials read from environment variables: `{PREFIX}_{ROUTER}_USER` and `{PREFIX}_{ROUTER}_PASSWORD`,
+/// falling back to `{PREFIX}_USER` and `{PREFIX}_PASSWORD`.
+///
+/// `{ROUTER}` is the router id in upper case, characters other than letters and digits being replaced by `_`:
+/// the credentials of `core-1` are read from `MIKROTIK_CORE_1_USER` and `MIKROTIK_CORE_1_PASSWORD` with the default prefix.
+#[derive(Debug, Clone)]
+pub struct EnvSecrets {
+    prefix: String,
+}
+
+impl Default for EnvSecrets {
+    fn default() -> Self {
+        Self::new("MIKROTIK")
+    }
+}
+
+impl EnvSecrets {
+    /// Reads variables starting with `prefix`
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+        }
+    }
+
+    fn variable(&self, router_id: Option<&str>, name: &str) -> String {
+        match router_id {
+            Some(id) => {
+                let id: String = id
+                    .chars()
+                    .map(|c| match c.is_ascii_alphanumeric() {
+                        true => c.to_ascii_uppercase(),
+                        false => '_',
+                    })
+                    .collect();
+
+                format!("{}_{}_{}", self.prefix, id, name)
+            }
+
+            None => format!("{}_{}", self.prefix, name),
+        }
+    }
+
+    fn read(&self, router_id: Option<&str>) -> Option<Credentials> {
+        let user = std::env::var(self.variable(router_id, "USER")).ok()?;
+        let password = std::env::var(self.variable(router_id, "PASSWORD")).ok()?;
+
+        Some(Credentials { user, password })
+    }
+}
+
+impl SecretsSource for EnvSecrets {
+    fn credentials<'a>(&'a self, router_id: &'a str) -> BoxFuture<'a, Result<Credentials, Error>> {
+        let credentials = self
+            .read(Some(router_id))
+            .or_else(|| self.read(None))
+            .ok_or_else(|| Error::MissingCredentials(router_id.to_owned()));
+
+        Box::pin(future::ready(credentials))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Credentials, EnvSecrets, SecretsSource, StaticSecrets};
+
+    #[tokio::test]
+    async fn test_static_secrets() {
+        let secrets = StaticSecrets::new()
+            .with("core-1", Credentials::new("admin", "hunter2"))
+            .fallback(Credentials::new("readonly", "hunter3"));
+
+        assert_eq!("admin", secrets.credentials("core-1").await.unwrap().user);
+        assert_eq!(
+            "readonly",
+            secrets.credentials("edge-7").await.unwrap().user
+        );
+        assert!(StaticSecrets::new().credentials("core-1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_env_secrets() {
+        std::env::set_var("MKTEST_CORE_1_USER", "admin");
+        std::env::set_var("MKTEST_CORE_1_PASSWORD", "hunter2");
+
+        let secrets = EnvSecrets::new("MKTEST");
+
+        let credentials = secrets.credentials("core-1").await.unwrap();
+        assert_eq!("admin", credentials.user);
+        assert_eq!("hunter2", credentials.password);
+
+        assert!(secrets.credentials("edge-7").await.is_err());
+    }
+}
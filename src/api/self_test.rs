@@ -0,0 +1,140 @@
+use std::{collections::HashMap, future::Future, time::Duration};
+
+use futures::StreamExt;
+use serde::Deserialize;
+
+use super::{error::Error, model::Response, Authenticated, MikrotikAPI};
+
+/// Time allowed to each check before it is considered failed
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Command that no RouterOS version knows about, used to check `!trap` handling
+const BAD_COMMAND: &str = "/mikrotik-api/self-test/no-such-command";
+
+/// Outcome of `MikrotikAPI::self_test`
+#[derive(Debug)]
+pub struct SelfTestReport {
+    /// RouterOS version of the router, if it could be retrieved
+    pub version: Option<String>,
+
+    /// All the checks that were run, in order
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    /// Returns true if every check succeeded
+    pub fn success(&self) -> bool {
+        self.checks.iter().all(|check| check.outcome.is_ok())
+    }
+}
+
+/// A single check of the self-test
+#[derive(Debug)]
+pub struct SelfTestCheck {
+    /// Short name of the feature being checked
+    pub name: &'static str,
+
+    /// `Err` holds a description of the failure
+    pub outcome: Result<(), String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identity {
+    #[allow(dead_code)]
+    name: String,
+}
+
+async fn with_timeout<F>(check: F) -> Result<(), String>
+where
+    F: Future<Output = Result<(), String>>,
+{
+    tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("no answer within {:?}", CHECK_TIMEOUT)))
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Runs a battery of harmless calls against the router and reports which features of the crate work with it:
+    /// one-off and array calls, a `listen` followed by a `/cancel`, and the mapping of `!trap` replies into errors.
+    pub async fn self_test(&mut self) -> SelfTestReport {
+        let mut checks = vec![];
+        let mut version = None;
+
+        let outcome = with_timeout(async {
+            self.generic_oneshot_call::<Identity>("/system/identity/print", None)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .await;
+        checks.push(SelfTestCheck {
+            name: "one-off call (/system/identity/print)",
+            outcome,
+        });
+
+        let outcome = with_timeout(async {
+            let resources = self.system_resources().await.map_err(|e| e.to_string())?;
+            version = Some(resources.version);
+            Ok(())
+        })
+        .await;
+        checks.push(SelfTestCheck {
+            name: "system resources model",
+            outcome,
+        });
+
+        let outcome = with_timeout(async {
+            self.interfaces()
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .await;
+        checks.push(SelfTestCheck {
+            name: "array call & interface model (/interface/print)",
+            outcome,
+        });
+
+        let outcome = with_timeout(self.check_listen_cancel()).await;
+        checks.push(SelfTestCheck {
+            name: "listen & cancel (/interface/listen)",
+            outcome,
+        });
+
+        let outcome = with_timeout(async {
+            match self
+                .generic_oneshot_call::<HashMap<String, String>>(BAD_COMMAND, None)
+                .await
+            {
+                Err(Error::Remote(_)) => Ok(()),
+                Err(e) => Err(format!("unexpected error: {}", e)),
+                Ok(reply) => Err(format!("unexpected reply: {:?}", reply)),
+            }
+        })
+        .await;
+        checks.push(SelfTestCheck {
+            name: "trap mapping (bad command)",
+            outcome,
+        });
+
+        SelfTestReport { version, checks }
+    }
+
+    async fn check_listen_cancel(&mut self) -> Result<(), String> {
+        let mut tag = 0;
+
+        let stream = self
+            .generic_streaming_call::<HashMap<String, String>>("/interface/listen", None, &mut tag)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if let Response::Trap { message, .. } = self.cancel(tag).await {
+            return Err(format!("/cancel failed: {}", message));
+        }
+
+        // The stream ends with the !done following the cancellation.
+        stream.for_each(|_| async {}).await;
+
+        Ok(())
+    }
+}
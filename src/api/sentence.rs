@@ -0,0 +1,272 @@
+use std::{fmt::Write, io::Cursor};
+
+use super::{encode_len, error::Error, read::read_len};
+
+/// A word of a command sentence, other than the command itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attr<'a> {
+    /// Argument of the command: `=key=value`
+    Attribute(&'a str, &'a str),
+
+    /// Query word, filtering the items a `print` returns: `?key=value`, or `?key` alone when `value` is empty.
+    /// Operations such as `#|` are given as the key
+    Query(&'a str, &'a str),
+
+    /// API attribute, not part of the command: `.key=value`, such as `.proplist` or `.tag`
+    Api(&'a str, &'a str),
+
+    /// **Escape hatch**: a word written as is, length prefix included, see `RawWord`
+    Raw(RawWord<'a>),
+}
+
+/// A word encoded by the caller, length prefix included, for syntax the builder does not model.
+///
+/// Only its framing is checked: the prefix must announce exactly the bytes that follow, and at least one of them since
+/// an empty word ends the sentence. Its content is not looked at, so a raw word may well get the command rejected,
+/// or worse. Meant for protocol experiments, not for everyday calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawWord<'a>(&'a [u8]);
+
+impl<'a> RawWord<'a> {
+    /// Checks the framing of `encoded`, ie `b"\x06=a=b=c"`
+    pub fn new(encoded: &'a [u8]) -> Result<Self, Error> {
+        let mut cursor = Cursor::new(encoded);
+
+        let len = read_len(&mut cursor)
+            .map_err(|e| Error::InvalidArgument(format!("raw word: bad length prefix: {}", e)))?;
+
+        let content = encoded.len() as u64 - cursor.position();
+
+        match len as u64 {
+            0 => Err(Error::InvalidArgument(
+                "raw word: empty words end the sentence".into(),
+            )),
+            len if len != content => Err(Error::InvalidArgument(format!(
+                "raw word: length prefix announces {} bytes, {} follow",
+                len, content
+            ))),
+            _ => Ok(RawWord(encoded)),
+        }
+    }
+
+    /// The word, length prefix included
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl<'a> Attr<'a> {
+    /// Attribute from a `(key, value)` pair in the format taken by the generic calls:
+    /// keys starting with `?` are queries, keys starting with `.` are API attributes,
+    /// keys starting with `=` are written as is and any other key is an argument
+    pub fn from_pair(key: &'a str, value: &'a str) -> Self {
+        if let Some(key) = key.strip_prefix('?') {
+            Attr::Query(key, value)
+        } else if let Some(key) = key.strip_prefix('.') {
+            Attr::Api(key, value)
+        } else {
+            Attr::Attribute(key.strip_prefix('=').unwrap_or(key), value)
+        }
+    }
+}
+
+/// Encodes command sentences into a buffer reused from one sentence to the next.
+///
+/// ```
+/// use mikrotik_api::SentenceBuilder;
+///
+/// let mut builder = SentenceBuilder::new();
+///
+/// let bytes = builder
+///     .command("/interface/print")
+///     .query("type", "ether")
+///     .attr(".proplist", "name,running")
+///     .tag(7)
+///     .finish()?;
+///
+/// assert_eq!(b"\x10/interface/print\x0b?type=ether", &bytes[..29]);
+/// # Ok::<(), mikrotik_api::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct SentenceBuilder {
+    buffer: Vec<u8>,
+    word: String,
+
+    /// First word that could not be encoded, reported by `finish`
+    error: Option<Error>,
+}
+
+impl SentenceBuilder {
+    /// Builder with an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new sentence with `command`, ie `/interface/print`, discarding anything written before
+    pub fn command(&mut self, command: &str) -> &mut Self {
+        self.buffer.clear();
+        self.error = None;
+        self.write_word(command);
+        self
+    }
+
+    /// Adds an argument: `=key=value`. Words are length-prefixed so `value` needs no escaping, while `key` must not hold `=`
+    pub fn attr(&mut self, key: &str, value: &str) -> &mut Self {
+        self.push(Attr::Attribute(key, value))
+    }
+
+    /// Adds a query word: `?key=value`, or `?key` when `value` is empty
+    pub fn query(&mut self, key: &str, value: &str) -> &mut Self {
+        self.push(Attr::Query(key, value))
+    }
+
+    /// Adds the `.tag` API attribute
+    pub fn tag(&mut self, tag: u16) -> &mut Self {
+        self.word.clear();
+        let _ = write!(self.word, ".tag={}", tag);
+
+        self.flush_word()
+    }
+
+    /// Adds a word encoded by the caller, see `RawWord`
+    pub fn raw(&mut self, word: RawWord) -> &mut Self {
+        self.push(Attr::Raw(word))
+    }
+
+    /// Adds any kind of attribute
+    pub fn push(&mut self, attr: Attr) -> &mut Self {
+        self.word.clear();
+
+        let _ = match attr {
+            Attr::Attribute(key, value) => write!(self.word, "={}={}", key, value),
+
+            Attr::Query(key, "") => write!(self.word, "?{}", key),
+            Attr::Query(key, value) => write!(self.word, "?{}={}", key, value),
+
+            Attr::Api(key, value) => write!(self.word, ".{}={}", key, value),
+
+            Attr::Raw(word) => {
+                self.buffer.extend_from_slice(word.as_bytes());
+                return self;
+            }
+        };
+
+        self.flush_word()
+    }
+
+    /// Closes the sentence, returning its encoded bytes. They stay valid until the next call to `command`.
+    /// Fails with `Error::InvalidArgument` if one of its words is too long to be framed
+    pub fn finish(&mut self) -> Result<&[u8], Error> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        self.buffer.push(0x00);
+
+        Ok(&self.buffer)
+    }
+
+    fn write_word(&mut self, word: &str) {
+        match encode_len(word) {
+            Ok(prefix) => {
+                self.buffer.extend_from_slice(&prefix);
+                self.buffer.extend_from_slice(word.as_bytes());
+            }
+
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+    }
+
+    fn flush_word(&mut self) -> &mut Self {
+        let word = std::mem::take(&mut self.word);
+
+        self.write_word(&word);
+
+        self.word = word;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Attr, RawWord, SentenceBuilder};
+    use crate::api::{encode_sentence, error::Error};
+
+    #[test]
+    fn test_builder_matches_encode_sentence() {
+        let mut builder = SentenceBuilder::new();
+
+        builder
+            .command("/ip/address/print")
+            .tag(42)
+            .query("interface", "ether1")
+            .query("interface", "ether2")
+            .query("#|", "")
+            .attr(".proplist", "address");
+
+        let expected = encode_sentence(&[
+            "/ip/address/print",
+            ".tag=42",
+            "?interface=ether1",
+            "?interface=ether2",
+            "?#|",
+            "=.proplist=address",
+        ])
+        .unwrap();
+
+        assert_eq!(expected, builder.finish().unwrap());
+
+        // The buffer is reused by the next sentence
+        let expected = encode_sentence(&["/system/identity/print"]).unwrap();
+        assert_eq!(
+            expected,
+            builder.command("/system/identity/print").finish().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_pair() {
+        assert_eq!(Attr::Query("#|", ""), Attr::from_pair("?#|", ""));
+        assert_eq!(Attr::Api("tag", "3"), Attr::from_pair(".tag", "3"));
+        assert_eq!(
+            Attr::Attribute(".proplist", "name"),
+            Attr::from_pair("=.proplist", "name")
+        );
+        assert_eq!(
+            Attr::Attribute("numbers", "*1"),
+            Attr::from_pair("numbers", "*1")
+        );
+    }
+
+    #[test]
+    fn test_raw_word() {
+        // Two-byte length prefix for a word that would fit in one
+        let padded = [&[0x80, 0x05][..], b"=a=bc"].concat();
+
+        let mut builder = SentenceBuilder::new();
+        builder
+            .command("/test")
+            .raw(RawWord::new(&padded).unwrap())
+            .raw(RawWord::new(b"\x03?#!").unwrap());
+
+        let expected = [&b"\x05/test"[..], &padded, b"\x03?#!", b"\x00"].concat();
+        assert_eq!(expected, builder.finish().unwrap());
+
+        for bad in [
+            &b""[..],
+            b"\x00",
+            b"\x04=a=",
+            b"\x02=a=",
+            b"\x80",
+            b"\xF8abc",
+        ] {
+            assert!(
+                matches!(RawWord::new(bad), Err(Error::InvalidArgument(_))),
+                "{:x?}",
+                bad
+            );
+        }
+    }
+}
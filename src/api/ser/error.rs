@@ -0,0 +1,43 @@
+use std::{borrow::Cow, fmt::Display};
+
+/// Error raised while serializing a value into command attributes
+#[derive(Debug)]
+pub enum SerializerError {
+    /// Value of a kind that cannot be turned into attributes
+    Unsupported(&'static str),
+
+    /// Any other error
+    Custom(Cow<'static, str>),
+}
+
+impl SerializerError {
+    /// Creates a custom error
+    pub fn custom<T>(text: T) -> Self
+    where
+        T: Into<Cow<'static, str>>,
+    {
+        Self::Custom(text.into())
+    }
+}
+
+impl Display for SerializerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use SerializerError::*;
+        match self {
+            Unsupported(kind) => write!(f, "cannot serialize {} as attributes", kind),
+
+            Custom(msg) => f.write_str(msg.as_ref()),
+        }
+    }
+}
+
+impl std::error::Error for SerializerError {}
+
+impl serde::ser::Error for SerializerError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: std::fmt::Display,
+    {
+        Self::custom(msg.to_string())
+    }
+}
@@ -0,0 +1,339 @@
+use serde::{
+    ser::{Impossible, SerializeMap, SerializeSeq, SerializeStruct},
+    Serialize, Serializer,
+};
+
+mod error;
+
+pub use error::SerializerError;
+
+type Result<T> = std::result::Result<T, error::SerializerError>;
+
+/// Serializes a struct (or a map) into a list of attributes, ready to be sent along a command.
+/// `None` fields are skipped.
+pub fn to_attributes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<(String, String)>> {
+    value.serialize(AttributesSerializer)
+}
+
+pub struct AttributesSerializer;
+
+pub struct Attributes {
+    attributes: Vec<(String, String)>,
+    next_key: Option<String>,
+}
+
+impl Attributes {
+    fn new() -> Self {
+        Self {
+            attributes: vec![],
+            next_key: None,
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.attributes.push((key, value));
+        }
+
+        Ok(())
+    }
+}
+
+macro_rules! unsupported {
+    ($($method:ident($($arg:ty),*) -> $ret:ty,)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<$ret> {
+                Err(SerializerError::Unsupported(stringify!($method)))
+            }
+        )*
+    };
+}
+
+impl Serializer for AttributesSerializer {
+    type Ok = Vec<(String, String)>;
+    type Error = SerializerError;
+
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Attributes;
+    type SerializeStruct = Attributes;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    unsupported! {
+        serialize_bool(bool) -> Self::Ok,
+        serialize_i8(i8) -> Self::Ok,
+        serialize_i16(i16) -> Self::Ok,
+        serialize_i32(i32) -> Self::Ok,
+        serialize_i64(i64) -> Self::Ok,
+        serialize_u8(u8) -> Self::Ok,
+        serialize_u16(u16) -> Self::Ok,
+        serialize_u32(u32) -> Self::Ok,
+        serialize_u64(u64) -> Self::Ok,
+        serialize_f32(f32) -> Self::Ok,
+        serialize_f64(f64) -> Self::Ok,
+        serialize_char(char) -> Self::Ok,
+        serialize_str(&str) -> Self::Ok,
+        serialize_bytes(&[u8]) -> Self::Ok,
+        serialize_unit_struct(&'static str) -> Self::Ok,
+        serialize_unit_variant(&'static str, u32, &'static str) -> Self::Ok,
+        serialize_seq(Option<usize>) -> Self::SerializeSeq,
+        serialize_tuple(usize) -> Self::SerializeTuple,
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct,
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant,
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant,
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(vec![])
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(vec![])
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(Attributes::new())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(Attributes::new())
+    }
+}
+
+impl SerializeStruct for Attributes {
+    type Ok = Vec<(String, String)>;
+    type Error = SerializerError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.push(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.attributes)
+    }
+}
+
+impl SerializeMap for Attributes {
+    type Ok = Vec<(String, String)>;
+    type Error = SerializerError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = key
+            .serialize(ValueSerializer)?
+            .ok_or(SerializerError::Unsupported("empty key"))?;
+
+        self.next_key = Some(key);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| SerializerError::custom("value without key"))?;
+
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.attributes)
+    }
+}
+
+/// Serializes a single attribute value into its textual form, `None` meaning the attribute is to be skipped.
+struct ValueSerializer;
+
+macro_rules! to_string {
+    ($($method:ident($arg:ty),)*) => {
+        $(
+            fn $method(self, value: $arg) -> Result<Self::Ok> {
+                Ok(Some(value.to_string()))
+            }
+        )*
+    };
+}
+
+/// List values are sent as comma-separated items, ie `=ports=8080,8443`
+struct JoinedList(Vec<String>);
+
+impl SerializeSeq for JoinedList {
+    type Ok = Option<String>;
+    type Error = SerializerError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        if let Some(item) = value.serialize(ValueSerializer)? {
+            self.0.push(item);
+        }
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0.join(",")))
+    }
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = Option<String>;
+    type Error = SerializerError;
+
+    type SerializeSeq = JoinedList;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    to_string! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+    }
+
+    unsupported! {
+        serialize_bytes(&[u8]) -> Self::Ok,
+        serialize_tuple(usize) -> Self::SerializeTuple,
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct,
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant,
+        serialize_map(Option<usize>) -> Self::SerializeMap,
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct,
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant,
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(JoinedList(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(Some(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::to_attributes;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Model {
+        address: String,
+        interface: String,
+        comment: Option<String>,
+        disabled: bool,
+        distance: u8,
+        ports: Vec<u16>,
+    }
+
+    #[test]
+    fn test_struct_to_attributes() {
+        let model = Model {
+            address: "192.168.88.1/24".into(),
+            interface: "bridge".into(),
+            comment: None,
+            disabled: false,
+            distance: 1,
+            ports: vec![8080, 8443],
+        };
+
+        let attributes = to_attributes(&model).unwrap();
+
+        let expected: Vec<(String, String)> = [
+            ("address", "192.168.88.1/24"),
+            ("interface", "bridge"),
+            ("disabled", "false"),
+            ("distance", "1"),
+            ("ports", "8080,8443"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        assert_eq!(expected, attributes);
+    }
+
+    #[test]
+    fn test_primitive_is_unsupported() {
+        assert!(to_attributes(&42u8).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::StreamExt;
+use log::{debug, warn};
+
+use super::{
+    call::StreamingCall,
+    error::Error,
+    listener::close_connection,
+    model::{ActiveUser, Response},
+    Authenticated, MikrotikAPI,
+};
+
+/// Reason the connection is closed for once `SessionWatchdog` saw its session end
+pub(crate) const SESSION_ENDED: &str = "the API session was ended on the router";
+
+/// Watches `/user/active` for the end of the session of the connection, as returned by `MikrotikAPI::session_watchdog`.
+///
+/// Once the router reports it gone, ie an admin kicked it, the connection is closed: calls still waiting fail and
+/// later ones fail at once with `Error::ConnectionLost`, rather than after a TCP timeout. It is a future to be spawned,
+/// resolving once the session ended or the connection closed.
+#[must_use = "the watchdog does nothing unless polled, ie spawned"]
+pub struct SessionWatchdog {
+    driver: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl Future for SessionWatchdog {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.driver.as_mut().poll(cx)
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Subscribes to `/user/active/listen` to notice the session of the connection ending on the router, see `SessionWatchdog`.
+    ///
+    /// The router does not tell which session is ours: every API session of the user open from now on counts,
+    /// the connection being closed once they all ended.
+    pub async fn session_watchdog(&mut self) -> Result<SessionWatchdog, Error> {
+        let user = self
+            .user
+            .clone()
+            .ok_or_else(|| Error::InvalidArgument("the connection has no user".into()))?;
+
+        let mut tag = 0;
+
+        // Listening first, so that no logout goes unnoticed while listing the sessions
+        let mut logouts = self
+            .do_call(
+                "/user/active/listen",
+                None,
+                StreamingCall::<ActiveUser>::new(),
+                Some(&mut tag),
+            )
+            .await?;
+
+        let sessions = self
+            .generic_array_call::<ActiveUser>(
+                "/user/active/print",
+                Some(&[("?name", &user), ("?via", "api")]),
+            )
+            .await?;
+
+        let mut ours: HashSet<_> = sessions
+            .into_iter()
+            .filter_map(|session| match session {
+                ActiveUser::Active { id, .. } => Some(id),
+                ActiveUser::Dead(_) => None,
+            })
+            .collect();
+
+        if ours.is_empty() {
+            self.cancel(tag).await;
+            self.forget_tag(tag);
+
+            return Err(Error::InvalidArgument(format!(
+                "no API session of {} on the router",
+                user
+            )));
+        }
+
+        debug!("session_watchdog: watching sessions {:?}", ours);
+
+        let tags = self.tag_map.clone();
+        let closed = self.closed.clone();
+
+        let driver = async move {
+            while let Some(event) = logouts.next().await {
+                match event {
+                    Response::Reply(ActiveUser::Dead(id)) => {
+                        ours.remove(&id);
+                    }
+
+                    // A new session of the user may be ours as well, if its login was not done yet when listing
+                    Response::Reply(ActiveUser::Active { .. }) => continue,
+
+                    // The connection closed, or the listen failed
+                    _ => return,
+                }
+
+                if ours.is_empty() {
+                    warn!("session_watchdog: {}", SESSION_ENDED);
+
+                    close_connection(&tags, &closed, SESSION_ENDED);
+                    return;
+                }
+            }
+        };
+
+        Ok(SessionWatchdog {
+            driver: Box::pin(driver),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use crate::{
+        api::error::Error,
+        mock::{MockReply, MockRouter},
+    };
+
+    use super::SESSION_ENDED;
+
+    /// Router on which the API session `*7` is ended shortly after `/user/active/listen` is sent,
+    /// `/test/kick` being never answered
+    fn mock_router() -> MockRouter {
+        let active = MockReply::new().re(&[
+            (".id", "*7"),
+            ("when", "2026-10-15 09:12:54"),
+            ("name", "admin"),
+            ("address", "10.0.0.5"),
+            ("via", "api"),
+            ("group", "full"),
+            ("radius", "false"),
+        ]);
+
+        // The router closes the TCP connection later on
+        let listen = MockReply::new()
+            .delay(Duration::from_millis(100))
+            .re(&[(".id", "*7"), (".dead", "true")])
+            .endless();
+
+        MockRouter::new()
+            .on("/user/active/listen", listen)
+            .on("/user/active/print", active)
+            .on("/test/kick", MockReply::new().endless())
+    }
+
+    #[tokio::test]
+    async fn test_session_watchdog() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let watchdog = tokio::spawn(api.session_watchdog().await.unwrap());
+
+        // Never answered: failed once the watchdog closes the connection
+        let kick = api
+            .generic_oneshot_call::<HashMap<String, String>>("/test/kick", None)
+            .await;
+        assert!(kick.is_err());
+
+        watchdog.await.unwrap();
+
+        match api
+            .generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+            .await
+        {
+            Err(Error::ConnectionLost(reason)) => assert_eq!(SESSION_ENDED, reason),
+            other => panic!("expected the connection to be closed, got {:?}", other),
+        }
+    }
+}
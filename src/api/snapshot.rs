@@ -0,0 +1,240 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{error::Error, export::ConfigSnapshot, inventory::Inventory, topology::Topology};
+
+/// Value of `SnapshotHeader::format`, telling snapshot files apart from other JSON lines files
+const FORMAT: &str = "mikrotik-api-snapshot";
+
+/// Version of the file format written by `SnapshotFile::save`. Files of later versions are refused
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// First line of a snapshot file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SnapshotHeader {
+    /// Always `mikrotik-api-snapshot`
+    pub format: String,
+
+    /// Version of the file format
+    pub version: u32,
+
+    /// Router the dumps were taken from, ie its address
+    pub router: String,
+
+    /// When the dumps were taken, in seconds since the Unix epoch
+    pub taken_at: u64,
+}
+
+/// A dump held by a snapshot file, one per line after the header
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "kind", content = "data")]
+pub enum SnapshotRecord {
+    /// Dump of `MikrotikAPI::topology_snapshot`
+    Topology(Topology),
+
+    /// Dump of `MikrotikAPI::inventory`
+    Inventory(Inventory),
+
+    /// Dump of `MikrotikAPI::config_snapshot`
+    Config(ConfigSnapshot),
+}
+
+/// Dumps of a router stored for offline analysis: audits can be saved, diffed and re-analyzed without a live connection.
+///
+/// Files are JSON lines: a `SnapshotHeader` followed by one `SnapshotRecord` per line.
+#[derive(Debug)]
+pub struct SnapshotFile {
+    /// Where and when the dumps were taken
+    pub header: SnapshotHeader,
+
+    /// Dumps, in the order they were added
+    pub records: Vec<SnapshotRecord>,
+}
+
+impl SnapshotFile {
+    /// Empty snapshot of `router`, taken now
+    pub fn new(router: &str) -> Self {
+        let taken_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        SnapshotFile {
+            header: SnapshotHeader {
+                format: FORMAT.to_owned(),
+                version: SNAPSHOT_VERSION,
+                router: router.to_owned(),
+                taken_at,
+            },
+            records: vec![],
+        }
+    }
+
+    /// Adds a dump
+    pub fn with(mut self, record: impl Into<SnapshotRecord>) -> Self {
+        self.records.push(record.into());
+        self
+    }
+
+    /// First topology of the snapshot
+    pub fn topology(&self) -> Option<&Topology> {
+        self.records.iter().find_map(|record| match record {
+            SnapshotRecord::Topology(topology) => Some(topology),
+            _ => None,
+        })
+    }
+
+    /// First inventory of the snapshot
+    pub fn inventory(&self) -> Option<&Inventory> {
+        self.records.iter().find_map(|record| match record {
+            SnapshotRecord::Inventory(inventory) => Some(inventory),
+            _ => None,
+        })
+    }
+
+    /// First configuration snapshot of the snapshot, to be compared with `ConfigSnapshot::diff`
+    pub fn config(&self) -> Option<&ConfigSnapshot> {
+        self.records.iter().find_map(|record| match record {
+            SnapshotRecord::Config(config) => Some(config),
+            _ => None,
+        })
+    }
+
+    /// Writes the snapshot as JSON lines
+    pub fn save<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        write_line(&mut writer, &self.header)?;
+
+        for record in &self.records {
+            write_line(&mut writer, record)?;
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Reads a snapshot written by `save`. Fails with `Error::BadSnapshot` on files of another format or of a later version
+    pub fn load<R: BufRead>(reader: R) -> Result<Self, Error> {
+        let mut lines = reader.lines();
+
+        let header: SnapshotHeader = match lines.next() {
+            Some(line) => parse_line(&line?, 1)?,
+            None => return Err(Error::BadSnapshot("empty file".into())),
+        };
+
+        if header.format != FORMAT {
+            return Err(Error::BadSnapshot(format!(
+                "not a snapshot file: {}",
+                header.format
+            )));
+        }
+
+        if header.version > SNAPSHOT_VERSION {
+            return Err(Error::BadSnapshot(format!(
+                "version {} is not supported, expected at most {}",
+                header.version, SNAPSHOT_VERSION
+            )));
+        }
+
+        let mut records = vec![];
+
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            records.push(parse_line(&line, index + 2)?);
+        }
+
+        Ok(SnapshotFile { header, records })
+    }
+
+    /// Writes the snapshot to the file at `path`, replacing it
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.save(BufWriter::new(File::create(path)?))
+    }
+
+    /// Reads the snapshot file at `path`
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::load(BufReader::new(File::open(path)?))
+    }
+}
+
+impl From<Topology> for SnapshotRecord {
+    fn from(topology: Topology) -> Self {
+        SnapshotRecord::Topology(topology)
+    }
+}
+
+impl From<Inventory> for SnapshotRecord {
+    fn from(inventory: Inventory) -> Self {
+        SnapshotRecord::Inventory(inventory)
+    }
+}
+
+impl From<ConfigSnapshot> for SnapshotRecord {
+    fn from(config: ConfigSnapshot) -> Self {
+        SnapshotRecord::Config(config)
+    }
+}
+
+fn write_line<W: Write, T: Serialize>(writer: &mut W, value: &T) -> Result<(), Error> {
+    serde_json::to_writer(&mut *writer, value).map_err(|e| Error::BadSnapshot(e.to_string()))?;
+
+    writer.write_all(b"\n")?;
+
+    Ok(())
+}
+
+fn parse_line<T: serde::de::DeserializeOwned>(line: &str, number: usize) -> Result<T, Error> {
+    serde_json::from_str(line).map_err(|e| Error::BadSnapshot(format!("line {}: {}", number, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{error::Error, export::ConfigSnapshot};
+
+    use super::{SnapshotFile, SNAPSHOT_VERSION};
+
+    #[test]
+    fn test_save_and_load() {
+        let config: ConfigSnapshot = serde_json::from_str(
+            r#"{"/ip/address": [{"address": "10.0.0.1/24", "interface": "bridge"}]}"#,
+        )
+        .unwrap();
+
+        let snapshot = SnapshotFile::new("10.0.0.1").with(config.clone());
+
+        let mut bytes = vec![];
+        snapshot.save(&mut bytes).unwrap();
+
+        assert_eq!(2, bytes.iter().filter(|b| **b == b'\n').count());
+
+        let loaded = SnapshotFile::load(bytes.as_slice()).unwrap();
+
+        assert_eq!(snapshot.header, loaded.header);
+        assert_eq!(Some(&config), loaded.config());
+        assert!(loaded.topology().is_none());
+
+        let later = format!(
+            r#"{{"format":"mikrotik-api-snapshot","version":{},"router":"r1","taken-at":0}}"#,
+            SNAPSHOT_VERSION + 1
+        );
+        assert!(matches!(
+            SnapshotFile::load(later.as_bytes()),
+            Err(Error::BadSnapshot(_))
+        ));
+
+        let other = r#"{"format":"something-else","version":1,"router":"r1","taken-at":0}"#;
+        assert!(SnapshotFile::load(other.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::{collections::HashMap, fmt::Display};
+
+use super::subscription::StreamLag;
+
+/// Number of buckets of `ExpHistogram`, the last one holding every value of 2^30 and above
+const BUCKETS: usize = 32;
+
+/// Histogram with exponential buckets: bucket `0` counts zeroes, bucket `i` counts values in `[2^(i-1), 2^i)`
+#[derive(Debug, Clone)]
+pub struct ExpHistogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    sum: u64,
+    max: u64,
+}
+
+impl Default for ExpHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; BUCKETS],
+            count: 0,
+            sum: 0,
+            max: 0,
+        }
+    }
+}
+
+impl ExpHistogram {
+    /// Adds a value to the histogram
+    pub fn record(&mut self, value: u64) {
+        let index = (u64::BITS - value.leading_zeros()) as usize;
+
+        self.buckets[index.min(BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.sum += value;
+        self.max = self.max.max(value);
+    }
+
+    /// Number of recorded values
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of recorded values
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Largest recorded value
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Non-empty buckets, as `(upper bound (exclusive), count)` pairs
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(i, count)| (1u64 << i, *count))
+    }
+}
+
+/// Statistics on the replies to a single command path
+#[derive(Debug, Clone, Default)]
+pub struct CommandStats {
+    /// Number of completed calls
+    pub calls: u64,
+
+    /// Sentences per reply
+    pub sentences: ExpHistogram,
+
+    /// Bytes per reply
+    pub bytes: ExpHistogram,
+
+    /// Items waiting to be consumed, sampled periodically while a stream runs
+    pub stream_depth: ExpHistogram,
+
+    /// Age of the oldest item waiting to be consumed in milliseconds, sampled along with `stream_depth`
+    pub stream_lag_ms: ExpHistogram,
+}
+
+/// Reply statistics for every command path used on a connection
+#[derive(Debug, Clone, Default)]
+pub struct StatsReport {
+    /// Statistics, by command path
+    pub commands: HashMap<String, CommandStats>,
+}
+
+impl StatsReport {
+    pub(crate) fn record(&mut self, command: &str, sentences: u64, bytes: u64) {
+        let stats = self.commands.entry(command.to_owned()).or_default();
+
+        stats.calls += 1;
+        stats.sentences.record(sentences);
+        stats.bytes.record(bytes);
+    }
+
+    pub(crate) fn record_lag(&mut self, command: &str, lag: &StreamLag) {
+        let stats = self.commands.entry(command.to_owned()).or_default();
+
+        let oldest = lag.oldest.unwrap_or_default().as_millis();
+
+        stats.stream_depth.record(lag.depth as u64);
+        stats
+            .stream_lag_ms
+            .record(u64::try_from(oldest).unwrap_or(u64::MAX));
+    }
+}
+
+impl Display for StatsReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut commands: Vec<_> = self.commands.iter().collect();
+        commands.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.bytes.sum()));
+
+        for (command, stats) in commands {
+            writeln!(
+                f,
+                "{}: {} call(s), {} sentence(s), {} byte(s) (max per reply: {} sentence(s), {} byte(s))",
+                command,
+                stats.calls,
+                stats.sentences.sum(),
+                stats.bytes.sum(),
+                stats.sentences.max(),
+                stats.bytes.max()
+            )?;
+
+            for (bound, count) in stats.bytes.buckets() {
+                writeln!(f, "  < {:>10} bytes: {}", bound, count)?;
+            }
+
+            if stats.stream_depth.count() > 0 {
+                writeln!(
+                    f,
+                    "  stream lag: up to {} item(s) waiting, oldest up to {} ms",
+                    stats.stream_depth.max(),
+                    stats.stream_lag_ms.max()
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpHistogram;
+
+    #[test]
+    fn test_histogram_buckets() {
+        let mut histogram = ExpHistogram::default();
+
+        for value in [0, 1, 2, 3, 4, 1000] {
+            histogram.record(value);
+        }
+
+        let buckets: Vec<_> = histogram.buckets().collect();
+
+        assert_eq!(vec![(1, 1), (2, 1), (4, 2), (8, 1), (1024, 1)], buckets);
+        assert_eq!(6, histogram.count());
+        assert_eq!(1010, histogram.sum());
+        assert_eq!(1000, histogram.max());
+    }
+}
@@ -0,0 +1,546 @@
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use futures::Stream;
+use log::debug;
+use serde::de::DeserializeOwned;
+use tokio::sync::Notify;
+
+use super::{
+    call::{SequencedStreamingCall, StreamingCall},
+    cancel::Cancels,
+    error::Error,
+    model::{Response, TrapCategory},
+    options::CallOptions,
+    Authenticated, MikrotikAPI,
+};
+
+/// Why a subscription ended: a `!trap` from the router or the connection being lost, see `StreamHandle::failure`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamFailure {
+    /// Type of error, if the router sent one. `None` when the connection was lost
+    pub category: Option<TrapCategory>,
+
+    /// Error message sent by the router
+    pub message: String,
+}
+
+impl StreamFailure {
+    /// Whether the command itself is wrong (unknown menu or parameter, bad value), sending it again being pointless
+    pub fn is_configuration_error(&self) -> bool {
+        matches!(
+            self.category,
+            Some(TrapCategory::MissingItemOrCommand) | Some(TrapCategory::ArgumentValueFailure)
+        )
+    }
+
+    /// Whether resubscribing may succeed, ie the failure is not a configuration error
+    pub fn is_retryable(&self) -> bool {
+        !self.is_configuration_error()
+    }
+}
+
+/// How far the consumer of a stream is behind the router
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamLag {
+    /// Items received but not consumed yet
+    pub depth: usize,
+
+    /// Time since the oldest of them was received, `None` when there is none
+    pub oldest: Option<Duration>,
+
+    /// Replies dropped so far because the consumer fell too far behind, see `StreamBuffer`
+    pub dropped: u64,
+}
+
+/// A `listen` subscription that can be paused and resumed without the consumer noticing.
+///
+/// Pausing cancels the command on the router; resuming sends it again with the same parameters,
+/// new events being delivered to the very same stream.
+///
+/// The stream outlives the command: a `!trap` from the router is delivered as a `Response::Trap` item, the stream then
+/// staying pending until `resume` sends the command again, and likewise after a `!done`.
+/// The reason of a `!trap` is kept in `failure`, to decide whether `resume` is worth a try.
+/// The stream only ends once the handle is cancelled or dropped, either sending `/cancel` without waiting for its answer.
+pub struct StreamHandle<T> {
+    command: String,
+    attributes: Vec<(String, String)>,
+    tag: Option<u16>,
+    call: StreamingCall<T>,
+
+    /// Of the connection the command was last sent on
+    cancels: Arc<Cancels>,
+}
+
+impl<T> StreamHandle<T>
+where
+    T: DeserializeOwned + Debug + Sync + Send + 'static,
+{
+    /// Stream of the events of the subscription, spanning pauses and resumptions. Ends once the subscription is cancelled
+    /// or its handle dropped
+    pub fn stream(&self) -> impl Stream<Item = Response<T>> {
+        self.call.clone()
+    }
+
+    /// Same as `stream`, items carrying their sequence number
+    pub(crate) fn sequenced(&self) -> SequencedStreamingCall<T> {
+        self.call.clone().sequenced()
+    }
+
+    /// Sequence number the next item of the stream will get
+    pub(crate) fn next_seq(&self) -> u64 {
+        self.call.next_seq()
+    }
+
+    /// Whether the router ended the command, see `resume`
+    pub(crate) fn is_finished(&self) -> bool {
+        self.call.is_finished()
+    }
+
+    /// Notified once the router ends the command
+    pub(crate) fn finished(&self) -> Arc<Notify> {
+        self.call.finished()
+    }
+
+    /// Tag of the command currently running on the router, `None` while paused
+    pub fn tag(&self) -> Option<u16> {
+        self.tag
+    }
+
+    /// Whether the subscription is paused
+    pub fn is_paused(&self) -> bool {
+        self.tag.is_none()
+    }
+
+    /// Events waiting to be consumed from `stream`, and for how long the oldest has been waiting
+    pub fn lag(&self) -> StreamLag {
+        self.call.lag()
+    }
+
+    /// Why the router ended the subscription, `None` while it is running or paused
+    pub fn failure(&self) -> Option<StreamFailure> {
+        self.call.failure()
+    }
+
+    /// Stops receiving events, until `resume` is called
+    pub async fn pause(&mut self, api: &mut MikrotikAPI<Authenticated>) -> Result<(), Error> {
+        let tag = match self.tag.take() {
+            Some(tag) => tag,
+            None => return Ok(()),
+        };
+
+        debug!("pause: {} (tag {})", self.command, tag);
+
+        // The !trap & !done answering the /cancel must not reach the stream, which would end.
+        api.forget_tag(tag);
+
+        // Nothing left to cancel on the router
+        if self.call.is_finished() {
+            return Ok(());
+        }
+
+        // Without waiting for the answer, which a stalled router would never send
+        api.cancels.cancel(tag);
+
+        Ok(())
+    }
+
+    /// Sends the command again, its events being delivered to the same stream.
+    /// Once the router ended the command, with a `!trap` or a `!done`, the stream carries on with the new events.
+    pub async fn resume(&mut self, api: &mut MikrotikAPI<Authenticated>) -> Result<(), Error> {
+        if self.call.is_finished() {
+            // The command is over on the router, its !done must not end the resumed stream.
+            if let Some(tag) = self.tag.take() {
+                api.forget_tag(tag);
+            }
+
+            self.call.reset();
+        }
+
+        if self.tag.is_some() {
+            return Ok(());
+        }
+
+        let mut tag = 0;
+
+        let attributes: Vec<(&str, &str)> = self
+            .attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+
+        api.do_call_with_options(
+            &self.command,
+            Some(&attributes),
+            self.call.clone(),
+            Some(&mut tag),
+            &CallOptions::default(),
+        )
+        .await?;
+
+        debug!("resume: {} (tag {})", self.command, tag);
+
+        self.tag = Some(tag);
+        self.cancels = api.cancels.clone();
+
+        Ok(())
+    }
+
+    /// Ends the subscription for good, along with its stream.
+    /// Like `pause`, `/cancel` is sent without waiting for the router's answer
+    pub async fn cancel(mut self, api: &mut MikrotikAPI<Authenticated>) -> Result<(), Error> {
+        self.pause(api).await?;
+        self.call.close();
+
+        Ok(())
+    }
+}
+
+impl<T> Drop for StreamHandle<T> {
+    /// The subscription cannot be resumed anymore: its stream ends, the command being cancelled on the router
+    fn drop(&mut self) {
+        if let Some(tag) = self.tag.take() {
+            self.cancels.forget(tag);
+
+            if !self.call.is_finished() {
+                self.cancels.cancel(tag);
+            }
+        }
+
+        self.call.close();
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Starts a `listen` command that can later be paused and resumed, see `StreamHandle`
+    pub async fn subscribe<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+    ) -> Result<StreamHandle<T>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        let mut handle = StreamHandle {
+            command: command.to_owned(),
+            attributes: attributes
+                .unwrap_or_default()
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            tag: None,
+            call: StreamingCall::new().resumable(),
+            cancels: self.cancels.clone(),
+        };
+
+        handle.resume(self).await?;
+
+        Ok(handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use futures::StreamExt;
+
+    use crate::{
+        api::{
+            call::{AsyncCall, StreamingCall},
+            model::{Response, TrapCategory},
+        },
+        mock::{MockReply, MockRouter},
+    };
+
+    use super::StreamLag;
+
+    type Item = HashMap<String, String>;
+
+    /// Next item of `stream`, failing if it takes more than a second
+    async fn next<S: futures::Stream<Item = Response<Item>> + Unpin>(
+        stream: &mut S,
+    ) -> Option<Response<Item>> {
+        tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .expect("no item within a second")
+    }
+
+    /// Whether `stream` stays pending, ie neither yields an item nor ends
+    async fn is_pending<S: futures::Stream<Item = Response<Item>> + Unpin>(stream: &mut S) -> bool {
+        tokio::time::timeout(Duration::from_millis(100), stream.next())
+            .await
+            .is_err()
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume() {
+        let router = MockRouter::new().on(
+            "/interface/listen",
+            MockReply::new().re(&[("name", "ether1")]).endless(),
+        );
+        let received = router.received();
+
+        let mut api = router.connect().await.unwrap();
+
+        let mut handle = api
+            .subscribe::<Item>("/interface/listen", None)
+            .await
+            .unwrap();
+        let mut stream = Box::pin(handle.stream());
+
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+
+        handle.pause(&mut api).await.unwrap();
+        assert!(handle.is_paused());
+
+        handle.resume(&mut api).await.unwrap();
+        assert!(!handle.is_paused());
+
+        // The interrupted command does not end the stream, which carries on with the new one
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+
+        // The first command was cancelled, its /cancel going out while the second one may already be sent
+        let cancelled = async {
+            while !received.contains("/cancel") {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), cancelled)
+            .await
+            .unwrap();
+
+        let sentences = received.sentences();
+        let sent = |command: &str| -> Vec<&Vec<String>> {
+            sentences
+                .iter()
+                .filter(|sentence| sentence[0] == command)
+                .collect()
+        };
+
+        let listens = sent("/interface/listen");
+        let cancels = sent("/cancel");
+        assert_eq!((2, 1), (listens.len(), cancels.len()));
+
+        let first_tag = listens[0]
+            .iter()
+            .find(|word| word.starts_with(".tag="))
+            .unwrap();
+        assert!(cancels[0].contains(&first_tag.replacen(".tag=", "=tag=", 1)));
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_trap() {
+        let reply = MockReply::new()
+            .re(&[("name", "ether1")])
+            .trap_with_category(4, "interface removed");
+
+        let mut api = MockRouter::new()
+            .on("/interface/listen", reply)
+            .connect()
+            .await
+            .unwrap();
+
+        let mut handle = api
+            .subscribe::<Item>("/interface/listen", None)
+            .await
+            .unwrap();
+        let mut stream = Box::pin(handle.stream());
+
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+        assert!(matches!(
+            next(&mut stream).await,
+            Some(Response::Trap { .. })
+        ));
+        // Not ended, the handle being able to resume it
+        assert!(is_pending(&mut stream).await);
+        assert!(handle.failure().unwrap().is_retryable());
+
+        handle.resume(&mut api).await.unwrap();
+        assert!(handle.failure().is_none());
+
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_done() {
+        let reply = MockReply::new().re(&[("name", "ether1")]);
+
+        let mut api = MockRouter::new()
+            .on("/interface/listen", reply)
+            .connect()
+            .await
+            .unwrap();
+
+        let mut handle = api
+            .subscribe::<Item>("/interface/listen", None)
+            .await
+            .unwrap();
+        let mut stream = Box::pin(handle.stream());
+
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+        assert!(is_pending(&mut stream).await);
+        assert!(handle.failure().is_none());
+
+        // Sent again, although the router ended the command without a failure
+        handle.resume(&mut api).await.unwrap();
+
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_ends_stream() {
+        // A router never answering /cancel must not keep `cancel` waiting
+        let router = MockRouter::new()
+            .on(
+                "/interface/listen",
+                MockReply::new().re(&[("name", "ether1")]).endless(),
+            )
+            .on("/cancel", MockReply::new().endless());
+        let received = router.received();
+
+        let mut api = router.connect().await.unwrap();
+
+        let handle = api
+            .subscribe::<Item>("/interface/listen", None)
+            .await
+            .unwrap();
+        let mut stream = Box::pin(handle.stream());
+
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+
+        tokio::time::timeout(Duration::from_secs(1), handle.cancel(&mut api))
+            .await
+            .unwrap()
+            .unwrap();
+
+        while let Some(item) = next(&mut stream).await {
+            assert!(matches!(item, Response::Reply(_)));
+        }
+
+        let cancelled = async {
+            while !received.contains("/cancel") {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), cancelled)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drop_ends_stream() {
+        let reply = MockReply::new()
+            .re(&[("name", "ether1")])
+            .trap_with_category(4, "interface removed");
+
+        let router = MockRouter::new()
+            .on(
+                "/interface/listen",
+                MockReply::new().re(&[("name", "ether1")]).endless(),
+            )
+            .on("/ip/address/listen", reply);
+        let received = router.received();
+
+        let mut api = router.connect().await.unwrap();
+
+        // Running: the stream ends, the command being cancelled
+        let handle = api
+            .subscribe::<Item>("/interface/listen", None)
+            .await
+            .unwrap();
+        let mut stream = Box::pin(handle.stream());
+
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+        drop(handle);
+
+        while let Some(item) = next(&mut stream).await {
+            assert!(matches!(item, Response::Reply(_)));
+        }
+
+        let cancelled = async {
+            while !received.contains("/cancel") {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), cancelled)
+            .await
+            .unwrap();
+
+        // Ended by the router: the stream pending until then ends too
+        let handle = api
+            .subscribe::<Item>("/ip/address/listen", None)
+            .await
+            .unwrap();
+        let mut stream = Box::pin(handle.stream());
+
+        assert!(matches!(next(&mut stream).await, Some(Response::Reply(_))));
+        assert!(matches!(
+            next(&mut stream).await,
+            Some(Response::Trap { .. })
+        ));
+        assert!(is_pending(&mut stream).await);
+
+        drop(handle);
+        assert!(next(&mut stream).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_trap_ends_stream() {
+        let mut call = StreamingCall::<String>::new();
+
+        let sentence = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+
+        call.push_reply(sentence(&[
+            "!trap",
+            ".tag=1",
+            "=category=1",
+            "=message=unknown parameter",
+            "",
+        ]))
+        .unwrap();
+        call.push_reply(sentence(&["!done", ".tag=1", ""])).unwrap();
+        call.done().unwrap();
+
+        let items: Vec<_> = call.clone().collect().await;
+
+        assert!(matches!(
+            items.as_slice(),
+            [Response::Trap {
+                category: Some(TrapCategory::ArgumentValueFailure),
+                ..
+            }]
+        ));
+
+        let failure = call.failure().unwrap();
+        assert_eq!("unknown parameter", failure.message);
+        assert!(!failure.is_retryable());
+
+        call.reset();
+        assert!(call.failure().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lag() {
+        let mut call = StreamingCall::<HashMap<String, String>>::new();
+
+        assert_eq!(StreamLag::default(), call.lag());
+
+        for name in ["ether1", "ether2"] {
+            let name = format!("=name={}", name);
+            call.push_reply(vec!["!re".into(), ".tag=1".into(), name, "".into()])
+                .unwrap();
+        }
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let lag = call.lag();
+        assert_eq!(2, lag.depth);
+        assert!(lag.oldest.unwrap() >= Duration::from_millis(20));
+
+        let mut stream = call.clone();
+        stream.next().await.unwrap();
+        stream.next().await.unwrap();
+
+        assert_eq!(StreamLag::default(), call.lag());
+    }
+}
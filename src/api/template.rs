@@ -0,0 +1,488 @@
+use std::{collections::HashSet, fmt::Display, str::FromStr};
+
+use serde::de::DeserializeOwned;
+
+use super::{error::Error, Authenticated, MikrotikAPI};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// A word of a template: key in the format of the generic calls (`list`, `?type`, `.proplist`) and value to fill in
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TemplateWord {
+    key: String,
+    value: Vec<Segment>,
+}
+
+/// A command with named placeholders in the values of its words, filled in with `render`:
+///
+/// ```
+/// use mikrotik_api::CommandTemplate;
+///
+/// let template: CommandTemplate = "/ip/firewall/address-list/add =list={list} =address={ip}"
+///     .parse()
+///     .unwrap();
+///
+/// let command = template
+///     .render(&[("list", "blocked"), ("ip", "198.51.100.7")])
+///     .unwrap();
+///
+/// assert_eq!("/ip/firewall/address-list/add", command.command());
+/// assert_eq!(vec![("list", "blocked"), ("address", "198.51.100.7")], command.attributes());
+///
+/// // Every placeholder must be given a value, and only them
+/// assert!(template.render(&[("list", "blocked")]).is_err());
+/// assert!(template.render(&[("list", "blocked"), ("ip", "198.51.100.7"), ("ttl", "1d")]).is_err());
+/// ```
+///
+/// Words are separated by whitespace and are either arguments (`=key=value`), queries (`?key=value`, `?key`)
+/// or API attributes (`.proplist=name`). Placeholders are only allowed in values, where `{{` and `}}` stand for braces.
+/// Values given to placeholders are taken as is, spaces included: each ends up within a single word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandTemplate {
+    command: String,
+    words: Vec<TemplateWord>,
+}
+
+/// A command rendered from a `CommandTemplate`, ready to be given to the generic calls
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedCommand {
+    command: String,
+    attributes: Vec<(String, String)>,
+}
+
+impl RenderedCommand {
+    /// The command, ie `/ip/firewall/address-list/add`
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Words of the command in the format of the generic calls, ie `[("list", "blocked"), ("?type", "ether")]`
+    pub fn attributes(&self) -> Vec<(&str, &str)> {
+        self.attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect()
+    }
+}
+
+impl Display for RenderedCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.command)?;
+
+        for (key, value) in &self.attributes {
+            match key.starts_with(['?', '.']) {
+                true if value.is_empty() => write!(f, " {}", key)?,
+                true => write!(f, " {}={}", key, value)?,
+                false => write!(f, " ={}={}", key, value)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn template_error(template: &str, reason: &str) -> Error {
+    Error::InvalidArgument(format!("command template '{}': {}", template, reason))
+}
+
+/// Splits `value` into literals and placeholders
+fn parse_value(template: &str, value: &str) -> Result<Vec<Segment>, Error> {
+    let mut segments = vec![];
+    let mut literal = String::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                literal.push(c);
+                chars.next();
+            }
+
+            ('{', _) => {
+                let mut name = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-' => {
+                            name.push(c)
+                        }
+                        Some(c) => {
+                            return Err(template_error(
+                                template,
+                                &format!("unexpected '{}' in a placeholder", c),
+                            ))
+                        }
+                        None => return Err(template_error(template, "unclosed placeholder")),
+                    }
+                }
+
+                if name.is_empty() {
+                    return Err(template_error(template, "placeholder without a name"));
+                }
+
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Placeholder(name));
+            }
+
+            ('}', _) => return Err(template_error(template, "'}' outside of a placeholder")),
+
+            _ => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+
+    Ok(segments)
+}
+
+impl CommandTemplate {
+    /// Parses `template`, ie `/ip/firewall/address-list/add =list={list} =address={ip}`
+    pub fn new(template: &str) -> Result<Self, Error> {
+        let mut words = template.split_whitespace();
+
+        let command = match words.next() {
+            Some(command) if command.starts_with('/') && !command.contains(['{', '}']) => command,
+            Some(_) => return Err(template_error(template, "must start with a command path")),
+            None => return Err(template_error(template, "empty template")),
+        };
+
+        let words = words
+            .map(|word| {
+                let (key, value) = match word.strip_prefix('=') {
+                    Some(argument) => argument.split_once('=').unwrap_or((argument, "")),
+                    None if word.starts_with(['?', '.']) => {
+                        word.split_once('=').unwrap_or((word, ""))
+                    }
+                    None => {
+                        return Err(template_error(
+                            template,
+                            &format!("'{}' is neither =argument, ?query nor .attribute", word),
+                        ))
+                    }
+                };
+
+                if key.is_empty() || key == "?" || key == "." || key.contains(['{', '}']) {
+                    return Err(template_error(
+                        template,
+                        &format!(
+                            "bad key in '{}', placeholders are only allowed in values",
+                            word
+                        ),
+                    ));
+                }
+
+                Ok(TemplateWord {
+                    key: key.to_owned(),
+                    value: parse_value(template, value)?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            command: command.to_owned(),
+            words,
+        })
+    }
+
+    /// Names of the placeholders, in order of first appearance
+    pub fn placeholders(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+
+        self.words
+            .iter()
+            .flat_map(|word| &word.value)
+            .filter_map(|segment| match segment {
+                Segment::Placeholder(name) => Some(name.as_str()),
+                Segment::Literal(_) => None,
+            })
+            .filter(|name| seen.insert(*name))
+            .collect()
+    }
+
+    /// Fills in the placeholders with `params`, as `(name, value)` pairs.
+    /// Fails with `Error::InvalidArgument` if a placeholder is left without a value, or if `params` holds unknown
+    /// or repeated names
+    pub fn render<K, V>(&self, params: &[(K, V)]) -> Result<RenderedCommand, Error>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let placeholders = self.placeholders();
+        let mut given = HashSet::new();
+
+        for (name, _) in params {
+            let name = name.as_ref();
+
+            if !placeholders.contains(&name) {
+                return Err(Error::InvalidArgument(format!(
+                    "{}: no placeholder named '{}'",
+                    self.command, name
+                )));
+            }
+
+            if !given.insert(name) {
+                return Err(Error::InvalidArgument(format!(
+                    "{}: '{}' given twice",
+                    self.command, name
+                )));
+            }
+        }
+
+        if let Some(missing) = placeholders.iter().find(|name| !given.contains(*name)) {
+            return Err(Error::InvalidArgument(format!(
+                "{}: no value for placeholder '{}'",
+                self.command, missing
+            )));
+        }
+
+        let lookup = |name: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k.as_ref() == name)
+                .map_or("", |(_, v)| v.as_ref())
+        };
+
+        let attributes = self
+            .words
+            .iter()
+            .map(|word| {
+                let value = word
+                    .value
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Literal(text) => text.as_str(),
+                        Segment::Placeholder(name) => lookup(name),
+                    })
+                    .collect();
+
+                (word.key.clone(), value)
+            })
+            .collect();
+
+        Ok(RenderedCommand {
+            command: self.command.clone(),
+            attributes,
+        })
+    }
+
+    /// Renders the template once per set of `params`, ie for every line of a CSV file
+    pub fn expand<'a, I, P, K, V>(
+        &'a self,
+        sets: I,
+    ) -> impl Iterator<Item = Result<RenderedCommand, Error>> + 'a
+    where
+        I: IntoIterator<Item = P>,
+        I::IntoIter: 'a,
+        P: AsRef<[(K, V)]>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        sets.into_iter()
+            .map(move |params| self.render(params.as_ref()))
+    }
+}
+
+impl FromStr for CommandTemplate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Renders `template` with `params` and calls it, see `CommandTemplate::render`
+    pub async fn run_template<T, K, V>(
+        &mut self,
+        template: &CommandTemplate,
+        params: &[(K, V)],
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned + std::fmt::Debug + Sync + Send + 'static,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let command = template.render(params)?;
+
+        self.generic_array_call(command.command(), Some(&command.attributes()))
+            .await
+    }
+
+    /// Renders `template` for every set of `params`, then calls each rendered command in turn.
+    /// Nothing is sent unless every set renders; the result of each call is given in the order of `sets`
+    pub async fn run_template_batch<T, I, P, K, V>(
+        &mut self,
+        template: &CommandTemplate,
+        sets: I,
+    ) -> Result<Vec<Result<Vec<T>, Error>>, Error>
+    where
+        T: DeserializeOwned + std::fmt::Debug + Sync + Send + 'static,
+        I: IntoIterator<Item = P>,
+        P: AsRef<[(K, V)]>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let commands = template.expand(sets).collect::<Result<Vec<_>, _>>()?;
+
+        let mut results = Vec::with_capacity(commands.len());
+
+        for command in commands {
+            let result = self
+                .generic_array_call(command.command(), Some(&command.attributes()))
+                .await;
+
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        api::{error::Error, MikrotikAPI},
+        mock::{MockReply, MockRouter},
+    };
+
+    use super::CommandTemplate;
+
+    #[test]
+    fn test_template_parse() {
+        let template = CommandTemplate::new(
+            "/interface/print ?type={type} ?running .proplist=name,{{mtu}} =comment=at {site}-{rack}",
+        );
+
+        // Words are separated by whitespace, values given in the template cannot hold any
+        assert!(matches!(template, Err(Error::InvalidArgument(_))));
+
+        let template = CommandTemplate::new(
+            "/interface/print ?type={type} ?running .proplist=name,{{mtu}} =comment={site}-{rack}-{site}",
+        )
+        .unwrap();
+
+        assert_eq!(vec!["type", "site", "rack"], template.placeholders());
+
+        let command = template
+            .render(&[("site", "par 1"), ("type", "ether"), ("rack", "b2")])
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                ("?type", "ether"),
+                ("?running", ""),
+                (".proplist", "name,{mtu}"),
+                ("comment", "par 1-b2-par 1"),
+            ],
+            command.attributes()
+        );
+        assert_eq!(
+            "/interface/print ?type=ether ?running .proplist=name,{mtu} =comment=par 1-b2-par 1",
+            command.to_string()
+        );
+
+        for bad in [
+            "",
+            "interface/print",
+            "/interface/{menu}/print",
+            "/interface/print name=ether1",
+            "=/interface/print",
+            "/interface/print ={key}=1",
+            "/interface/print =name={unclosed",
+            "/interface/print =name={}",
+            "/interface/print =name={na me}",
+            "/interface/print =name=}",
+        ] {
+            assert!(CommandTemplate::new(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_template_render() {
+        let template: CommandTemplate = "/ip/firewall/address-list/add =list={list} =address={ip}"
+            .parse()
+            .unwrap();
+
+        let no_params: &[(&str, &str)] = &[];
+
+        assert!(template.render(no_params).is_err());
+        assert!(template
+            .render(&[("list", "a"), ("ip", "1.1.1.1"), ("list", "b")])
+            .is_err());
+
+        let rows = vec![
+            vec![
+                ("list".to_owned(), "blocked".to_owned()),
+                ("ip".to_owned(), "198.51.100.7".to_owned()),
+            ],
+            vec![
+                ("list".to_owned(), "blocked".to_owned()),
+                ("ip".to_owned(), "198.51.100.8".to_owned()),
+            ],
+        ];
+
+        let commands: Vec<_> = template.expand(&rows).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(2, commands.len());
+        assert_eq!(
+            vec![("list", "blocked"), ("address", "198.51.100.8")],
+            commands[1].attributes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_template_batch() {
+        let template: CommandTemplate = "/ip/firewall/address-list/add =list=blocked =address={ip}"
+            .parse()
+            .unwrap();
+
+        let router = MockRouter::new()
+            .on("/ip/firewall/address-list/add", MockReply::new().ret("*1"))
+            .on_query(
+                "/ip/firewall/address-list/add",
+                &["=address=198.51.100.8"],
+                MockReply::new().trap("failure: already have such entry"),
+            );
+
+        let mut api = MikrotikAPI::from_transport(router.spawn())
+            .authenticate("admin", "")
+            .await
+            .unwrap();
+
+        // A set that does not render keeps the whole batch from being sent
+        let invalid = api
+            .run_template_batch::<HashMap<String, String>, _, _, _, _>(
+                &template,
+                [
+                    vec![("ip", "198.51.100.7")],
+                    vec![("address", "198.51.100.8")],
+                ],
+            )
+            .await;
+
+        assert!(matches!(invalid, Err(Error::InvalidArgument(_))));
+
+        let results = api
+            .run_template_batch::<HashMap<String, String>, _, _, _, _>(
+                &template,
+                ["198.51.100.7", "198.51.100.8", "198.51.100.9"].map(|ip| [("ip", ip)]),
+            )
+            .await
+            .unwrap();
+
+        assert!(results[0].is_ok());
+        assert!(matches!(&results[1], Err(Error::Remote(_))));
+        assert!(results[2].is_ok());
+    }
+}
@@ -0,0 +1,358 @@
+use std::{collections::BTreeMap, fmt::Debug};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    call::ArrayListCall,
+    error::Error,
+    model::{BridgePort, Interface, Neighbor, Response, Route, Vlan},
+    Authenticated, MikrotikAPI,
+};
+
+/// Graph of a router's layer 2 and layer 3 surroundings, as returned by `MikrotikAPI::topology_snapshot`.
+///
+/// Node ids are prefixed by their kind (`interface:ether1`, `neighbor:4C:5E:0C:AA:BB:CC`, `network:10.0.0.0/8`, `gateway:192.168.88.1`)
+/// and unique within the graph; edges refer to nodes by id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Topology {
+    /// Every node, sorted by id
+    pub nodes: Vec<TopologyNode>,
+
+    /// Every edge, in no particular order
+    pub edges: Vec<TopologyEdge>,
+}
+
+/// A node of a `Topology`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TopologyNode {
+    /// Unique id of the node
+    pub id: String,
+
+    /// Human readable name: interface name, neighbor identity, network address, ...
+    pub label: String,
+
+    /// What the node stands for
+    pub kind: NodeKind,
+}
+
+/// Kinds of `TopologyNode`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum NodeKind {
+    /// Local interface. Type and state are unknown for interfaces only referenced by other tables
+    Interface {
+        /// `ether`, `bridge`, `vlan`, ...
+        iface_type: Option<String>,
+
+        /// Whether the interface is up
+        running: Option<bool>,
+    },
+
+    /// Device discovered on one of the local interfaces
+    Neighbor {
+        /// Management address of the device
+        address: Option<String>,
+
+        /// `MikroTik`, `Cisco IOS`, ...
+        platform: Option<String>,
+    },
+
+    /// Destination of a route
+    Network,
+
+    /// Next hop of a route
+    Gateway,
+}
+
+/// A directed edge of a `Topology`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TopologyEdge {
+    /// Id of the source node
+    pub from: String,
+
+    /// Id of the target node
+    pub to: String,
+
+    /// What the edge stands for
+    pub kind: EdgeKind,
+}
+
+/// Kinds of `TopologyEdge`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum EdgeKind {
+    /// From a port to its bridge
+    BridgePort {
+        /// Untagged VLAN of the port
+        pvid: Option<u16>,
+    },
+
+    /// From a VLAN interface to its parent interface
+    Vlan {
+        /// VLAN id
+        vlan_id: u16,
+    },
+
+    /// From a local interface to a neighbor seen on it
+    Neighbor,
+
+    /// From a network to the gateway (or interface) it is routed through
+    Route {
+        /// Administrative distance
+        distance: Option<u8>,
+
+        /// Whether the route is in use
+        active: bool,
+    },
+}
+
+#[derive(Default)]
+struct TopologyBuilder {
+    nodes: BTreeMap<String, TopologyNode>,
+    edges: Vec<TopologyEdge>,
+}
+
+impl TopologyBuilder {
+    fn node(&mut self, prefix: &str, label: &str, kind: NodeKind) -> String {
+        let id = format!("{}:{}", prefix, label);
+
+        self.nodes.entry(id.clone()).or_insert(TopologyNode {
+            id: id.clone(),
+            label: label.to_owned(),
+            kind,
+        });
+
+        id
+    }
+
+    fn interface(&mut self, name: &str) -> String {
+        self.node(
+            "interface",
+            name,
+            NodeKind::Interface {
+                iface_type: None,
+                running: None,
+            },
+        )
+    }
+
+    fn edge(&mut self, from: String, to: String, kind: EdgeKind) {
+        self.edges.push(TopologyEdge { from, to, kind });
+    }
+
+    fn build(
+        interfaces: &[Interface],
+        ports: &[BridgePort],
+        vlans: &[Vlan],
+        neighbors: &[Neighbor],
+        routes: &[Route],
+    ) -> Topology {
+        let mut graph = Self::default();
+
+        for iface in interfaces {
+            graph.node(
+                "interface",
+                &iface.name,
+                NodeKind::Interface {
+                    iface_type: Some(iface.iface_type.clone()),
+                    running: Some(iface.running),
+                },
+            );
+        }
+
+        for port in ports.iter().filter(|port| !port.disabled) {
+            let from = graph.interface(&port.interface);
+            let to = graph.interface(&port.bridge);
+
+            graph.edge(from, to, EdgeKind::BridgePort { pvid: port.pvid });
+        }
+
+        for vlan in vlans.iter().filter(|vlan| !vlan.disabled) {
+            let from = graph.interface(&vlan.name);
+            let to = graph.interface(&vlan.interface);
+
+            graph.edge(
+                from,
+                to,
+                EdgeKind::Vlan {
+                    vlan_id: vlan.vlan_id,
+                },
+            );
+        }
+
+        for neighbor in neighbors {
+            let label = neighbor
+                .mac_address
+                .map(|mac| mac.to_string())
+                .or_else(|| neighbor.identity.clone())
+                .unwrap_or_else(|| neighbor.id.to_string());
+
+            let to = graph.node(
+                "neighbor",
+                &label,
+                NodeKind::Neighbor {
+                    address: neighbor.address.clone(),
+                    platform: neighbor.platform.clone(),
+                },
+            );
+
+            for iface in &neighbor.interface {
+                let from = graph.interface(iface);
+                graph.edge(from, to.clone(), EdgeKind::Neighbor);
+            }
+        }
+
+        for route in routes.iter().filter(|route| !route.disabled) {
+            let gateway = match route.gateway.as_deref() {
+                Some(gateway) => gateway,
+                None => continue,
+            };
+
+            let from = graph.node("network", &route.dst_address, NodeKind::Network);
+
+            // v7 reports gateways as `192.168.88.1%ether1` when the interface is explicit
+            let gateway = gateway.split('%').next().unwrap_or(gateway);
+
+            let to = if gateway.parse::<std::net::IpAddr>().is_ok() {
+                graph.node("gateway", gateway, NodeKind::Gateway)
+            } else {
+                graph.interface(gateway)
+            };
+
+            graph.edge(
+                from,
+                to,
+                EdgeKind::Route {
+                    distance: route.distance,
+                    active: route.active,
+                },
+            );
+        }
+
+        Topology {
+            nodes: graph.nodes.into_values().collect(),
+            edges: graph.edges,
+        }
+    }
+}
+
+pub(super) fn collect<T>(replies: Result<Vec<Response<T>>, Error>) -> Result<Vec<T>, Error> {
+    replies?.into_iter().collect::<Response<Vec<T>>>().into()
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Gathers interfaces, bridge ports, VLANs, neighbors and routes into a single graph, suitable for network visualization tools.
+    ///
+    /// All five prints are sent at once and their replies awaited together.
+    pub async fn topology_snapshot(&mut self) -> Result<Topology, Error> {
+        let interfaces = self.send_print::<Interface>("/interface/print").await?;
+        let ports = self
+            .send_print::<BridgePort>("/interface/bridge/port/print")
+            .await?;
+        let vlans = self.send_print::<Vlan>("/interface/vlan/print").await?;
+        let neighbors = self.send_print::<Neighbor>("/ip/neighbor/print").await?;
+        let routes = self.send_print::<Route>("/ip/route/print").await?;
+
+        let (interfaces, ports, vlans, neighbors, routes) =
+            tokio::join!(interfaces, ports, vlans, neighbors, routes);
+
+        Ok(TopologyBuilder::build(
+            &collect(interfaces)?,
+            &collect(ports)?,
+            &collect(vlans)?,
+            &collect(neighbors)?,
+            &collect(routes)?,
+        ))
+    }
+
+    pub(super) async fn send_print<T>(
+        &mut self,
+        command: &str,
+    ) -> Result<Box<ArrayListCall<T>>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        self.do_call(command, None, ArrayListCall::new(), None)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::model::{BridgePort, MacAddress, Neighbor, RosId, Route, Vlan};
+
+    use super::{EdgeKind, NodeKind, TopologyBuilder};
+
+    #[test]
+    fn test_build_topology() {
+        let ports = [BridgePort {
+            id: Some(RosId(1)),
+            interface: "ether2".into(),
+            bridge: "bridge".into(),
+            pvid: Some(1),
+            inactive: false,
+            disabled: false,
+            comment: None,
+        }];
+
+        let vlans = [Vlan {
+            id: Some(RosId(2)),
+            name: "vlan10".into(),
+            vlan_id: 10,
+            interface: "bridge".into(),
+            running: true,
+            disabled: false,
+            comment: None,
+        }];
+
+        let neighbors = [Neighbor {
+            id: RosId(3),
+            interface: vec!["ether2".into(), "bridge".into()],
+            address: Some("192.168.88.2".into()),
+            mac_address: Some(MacAddress([0x4C, 0x5E, 0x0C, 0xAA, 0xBB, 0xCC])),
+            identity: Some("switch".into()),
+            platform: Some("MikroTik".into()),
+            board: None,
+            version: None,
+        }];
+
+        let routes = [Route {
+            id: Some(RosId(4)),
+            dst_address: "0.0.0.0/0".into(),
+            gateway: Some("10.0.0.1%ether1".into()),
+            distance: Some(1),
+            pref_src: None,
+            routing_table: None,
+            active: true,
+            dynamic: false,
+            is_static: true,
+            disabled: false,
+            comment: None,
+        }];
+
+        let topology = TopologyBuilder::build(&[], &ports, &vlans, &neighbors, &routes);
+
+        let ids: Vec<_> = topology.nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(
+            vec![
+                "gateway:10.0.0.1",
+                "interface:bridge",
+                "interface:ether2",
+                "interface:vlan10",
+                "neighbor:4C:5E:0C:AA:BB:CC",
+                "network:0.0.0.0/0",
+            ],
+            ids
+        );
+
+        assert!(matches!(topology.nodes[0].kind, NodeKind::Gateway));
+
+        assert_eq!(5, topology.edges.len());
+        assert!(topology
+            .edges
+            .iter()
+            .any(|edge| edge.from == "interface:vlan10"
+                && edge.to == "interface:bridge"
+                && matches!(edge.kind, EdgeKind::Vlan { vlan_id: 10 })));
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::{HashMap, HashSet};
+
+use futures::Stream;
+use serde::Serialize;
+
+use super::{
+    call::StreamingCall,
+    error::Error,
+    model::{Response, Torch},
+    Authenticated, MikrotikAPI,
+};
+
+/// Traffic between two addresses, averaged over a sample of `/tool/torch`, see `top_talkers`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TopTalker {
+    #[allow(missing_docs)]
+    pub src_address: String,
+    #[allow(missing_docs)]
+    pub dst_address: String,
+
+    /// Bits per second sent
+    pub tx: u64,
+    /// Bits per second received
+    pub rx: u64,
+
+    /// Packets per second sent
+    pub tx_packets: u64,
+    /// Packets per second received
+    pub rx_packets: u64,
+}
+
+impl TopTalker {
+    /// Bits per second in both directions
+    pub fn total(&self) -> u64 {
+        self.tx + self.rx
+    }
+}
+
+/// Averages a sample of `/tool/torch` by source and destination address, ie taken with `collect_for`,
+/// and returns the `n` busiest pairs, busiest first. Interface totals are left out.
+pub fn top_talkers(sample: &[Torch], n: usize) -> Vec<TopTalker> {
+    let sections = sample
+        .iter()
+        .map(|entry| entry.section)
+        .collect::<HashSet<_>>()
+        .len()
+        .max(1) as u64;
+
+    let mut pairs: HashMap<(&str, &str), [u64; 4]> = HashMap::new();
+
+    for entry in sample {
+        let (Some(src), Some(dst)) = (&entry.src_address, &entry.dst_address) else {
+            continue;
+        };
+
+        let sums = pairs.entry((src, dst)).or_default();
+
+        sums[0] += entry.tx;
+        sums[1] += entry.rx;
+        sums[2] += entry.tx_packets;
+        sums[3] += entry.rx_packets;
+    }
+
+    let mut talkers: Vec<_> = pairs
+        .into_iter()
+        .map(|((src, dst), [tx, rx, tx_packets, rx_packets])| TopTalker {
+            src_address: src.to_owned(),
+            dst_address: dst.to_owned(),
+            tx: tx / sections,
+            rx: rx / sections,
+            tx_packets: tx_packets / sections,
+            rx_packets: rx_packets / sections,
+        })
+        .collect();
+
+    talkers.sort_by(|a, b| {
+        b.total()
+            .cmp(&a.total())
+            .then_with(|| (&a.src_address, &a.dst_address).cmp(&(&b.src_address, &b.dst_address)))
+    });
+    talkers.truncate(n);
+
+    talkers
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Runs `/tool/torch` on `interface`, broken down by source and destination IPv4 address.
+    /// The router reports every second until the command is cancelled through `tag`, see `collect_for`
+    pub async fn torch(
+        &mut self,
+        interface: &str,
+        tag: &mut u16,
+    ) -> Result<impl Stream<Item = Response<Torch>>, Error> {
+        self.do_call(
+            "/tool/torch",
+            Some(&[
+                ("interface", interface),
+                ("src-address", "0.0.0.0/0"),
+                ("dst-address", "0.0.0.0/0"),
+            ]),
+            StreamingCall::new(),
+            Some(tag),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{de::deserialize_sentence, model::Response};
+
+    use super::{top_talkers, Torch};
+
+    fn entry(words: &[&str]) -> Torch {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+
+        match deserialize_sentence::<Torch>(&words).unwrap() {
+            Response::Reply(entry) => entry,
+            other => panic!("expected a reply, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_top_talkers() {
+        let sample = [
+            entry(&[
+                "!re",
+                "=.section=0",
+                "=src-address=10.0.0.2",
+                "=dst-address=1.1.1.1",
+                "=tx=8000",
+                "=rx=2000",
+                "=tx-packets=10",
+                "=rx-packets=4",
+                "",
+            ]),
+            entry(&[
+                "!re",
+                "=.section=0",
+                "=src-address=10.0.0.3",
+                "=dst-address=9.9.9.9",
+                "=tx=1000",
+                "=rx=1000",
+                "",
+            ]),
+            entry(&["!re", "=.section=0", "=tx=12000", "=rx=3000", ""]),
+            entry(&[
+                "!re",
+                "=.section=1",
+                "=src-address=10.0.0.2",
+                "=dst-address=1.1.1.1",
+                "=tx=4000",
+                "=rx=0",
+                "=tx-packets=6",
+                "",
+            ]),
+        ];
+
+        let talkers = top_talkers(&sample, 20);
+
+        assert_eq!(2, talkers.len());
+
+        assert_eq!("10.0.0.2", talkers[0].src_address);
+        assert_eq!((6000, 1000, 8, 2), {
+            let t = &talkers[0];
+            (t.tx, t.rx, t.tx_packets, t.rx_packets)
+        });
+
+        assert_eq!(1000, talkers[1].total());
+
+        assert_eq!(1, top_talkers(&sample, 1).len());
+    }
+}
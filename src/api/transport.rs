@@ -0,0 +1,48 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A bidirectional byte stream the API can run over: a `TcpStream`, a TLS stream, a serial-to-TCP bridge,
+/// one end of a `tokio::io::duplex` pipe in tests, ...
+///
+/// Implemented for every `AsyncRead + AsyncWrite` type, see `MikrotikAPI::from_transport`.
+pub trait Transport: AsyncRead + AsyncWrite + Send + 'static {}
+
+impl<T> Transport for T where T: AsyncRead + AsyncWrite + Send + 'static {}
+
+pub(crate) type TransportReader = Box<dyn AsyncRead + Send + Unpin>;
+
+pub(crate) type TransportWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+pub(crate) fn split<T: Transport>(transport: T) -> (TransportReader, TransportWriter) {
+    let (reader, writer) = tokio::io::split(transport);
+
+    (Box::new(reader), Box::new(writer))
+}
+
+/// Reads and dispatches the replies of the router, for a `MikrotikAPI` created with `MikrotikAPI::new_manual`.
+///
+/// Resolves once the connection is closed. Calls only complete while it is being polled, on whatever executor
+/// the caller picks; it still relies on tokio's time driver for its periodic idle checks.
+#[must_use = "calls never complete unless the connection is polled"]
+pub struct Connection {
+    pub(crate) driver: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl Future for Connection {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.driver.as_mut().poll(cx)
+    }
+}
+
+impl std::fmt::Debug for Connection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Connection").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,405 @@
+use std::{collections::HashMap, time::Duration};
+
+use log::{info, warn};
+use serde::Deserialize;
+use tokio::time::Instant;
+
+use super::{error::Error, Authenticated, MikrotikAPI};
+
+/// A condition the router must fulfil to be considered healthy
+#[derive(Debug, Clone)]
+pub enum HealthCheck {
+    /// The router must get at least `min_received` answers out of `count` pings sent to `address`
+    Ping {
+        /// Address to ping, usually the default gateway
+        address: String,
+
+        /// Number of pings to send, one per second
+        count: u32,
+
+        /// Number of answers required
+        min_received: u32,
+    },
+
+    /// The router must have at least `min_bytes` of free memory
+    FreeMemory {
+        /// Required free memory, in bytes
+        min_bytes: u64,
+    },
+
+    /// The interface `name` must be running
+    InterfaceRunning {
+        /// Name of the interface
+        name: String,
+    },
+}
+
+/// An action run against the router when its health checks keep failing
+#[derive(Debug, Clone)]
+pub enum Remediation {
+    /// `/system/reboot`. The connection is lost, ending `Watchdog::run`
+    Reboot,
+
+    /// Disables then enables the interface `name`
+    BounceInterface {
+        /// Name of the interface
+        name: String,
+
+        /// Time the interface stays disabled
+        down_for: Duration,
+    },
+
+    /// Any other command, ie `/ip/dhcp-client/renew` with `numbers=ether1`
+    Command {
+        /// Command path
+        command: String,
+
+        /// Attributes of the command
+        attributes: Vec<(String, String)>,
+    },
+}
+
+/// What the watchdog did after a round of health checks, see `Watchdog::tick`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// Every check passed
+    None,
+
+    /// Some checks failed, but not enough times in a row to remediate yet
+    Waiting,
+
+    /// Remediation is due, but the previous one is too recent
+    CoolingDown,
+
+    /// Remediation is due, but the watchdog is in dry-run mode: nothing was sent
+    DryRun,
+
+    /// Remediation commands were sent to the router
+    Remediated,
+}
+
+/// Outcome of a round of health checks
+#[derive(Debug, Clone)]
+pub struct WatchdogTick {
+    /// Description of every failed check
+    pub failures: Vec<String>,
+
+    /// What the watchdog did about it
+    pub action: WatchdogAction,
+}
+
+/// Supervises a router through periodic health checks, running remediation commands (reboot, interface bounce, ...)
+/// when they fail too many times in a row. Meant for unattended edge deployments.
+///
+/// Remediations are separated by a cooldown, so that a router that stays unhealthy is not rebooted over and over.
+/// Dry-run mode only logs what would be done, which is the safe way to tune thresholds.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    checks: Vec<HealthCheck>,
+    remediations: Vec<Remediation>,
+    interval: Duration,
+    cooldown: Duration,
+    failures_before_action: u32,
+    dry_run: bool,
+
+    consecutive_failures: u32,
+    last_action: Option<Instant>,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            checks: vec![],
+            remediations: vec![],
+            interval: Duration::from_secs(60),
+            cooldown: Duration::from_secs(30 * 60),
+            failures_before_action: 3,
+            dry_run: false,
+            consecutive_failures: 0,
+            last_action: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PingReply {
+    #[serde(default)]
+    received: u32,
+}
+
+impl Watchdog {
+    /// Watchdog checking every minute, remediating after 3 failed rounds in a row, at most once every 30 minutes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a health check
+    pub fn check(mut self, check: HealthCheck) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Adds a remediation, run after the previous ones
+    pub fn remediate(mut self, remediation: Remediation) -> Self {
+        self.remediations.push(remediation);
+        self
+    }
+
+    /// Time between two rounds of health checks
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Minimum time between two remediations
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Number of failed rounds in a row needed to remediate
+    pub fn failures_before_action(mut self, failures: u32) -> Self {
+        self.failures_before_action = failures.max(1);
+        self
+    }
+
+    /// Only log remediations instead of running them
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Runs health checks every `interval` until a call fails (the connection being lost, for instance)
+    /// or a reboot is issued. The caller is expected to reconnect and run the watchdog again.
+    pub async fn run(&mut self, api: &mut MikrotikAPI<Authenticated>) -> Result<(), Error> {
+        let mut interval = tokio::time::interval(self.interval);
+
+        loop {
+            interval.tick().await;
+
+            let tick = self.tick(api).await?;
+
+            let rebooted = tick.action == WatchdogAction::Remediated
+                && self
+                    .remediations
+                    .iter()
+                    .any(|remediation| matches!(remediation, Remediation::Reboot));
+
+            if rebooted {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Runs a single round of health checks, remediating if needed
+    pub async fn tick(
+        &mut self,
+        api: &mut MikrotikAPI<Authenticated>,
+    ) -> Result<WatchdogTick, Error> {
+        let mut failures = vec![];
+
+        for check in &self.checks {
+            if let Some(failure) = run_check(api, check).await? {
+                failures.push(failure);
+            }
+        }
+
+        let action = self.next_action(!failures.is_empty(), Instant::now());
+
+        match action {
+            WatchdogAction::Waiting | WatchdogAction::CoolingDown => warn!(
+                "watchdog: unhealthy ({} failures in a row): {}",
+                self.consecutive_failures,
+                failures.join(", ")
+            ),
+
+            WatchdogAction::DryRun => warn!(
+                "watchdog: dry run, would run {:?} because of: {}",
+                self.remediations,
+                failures.join(", ")
+            ),
+
+            WatchdogAction::Remediated => {
+                warn!("watchdog: remediating because of: {}", failures.join(", "));
+
+                for remediation in &self.remediations {
+                    info!("watchdog: running {:?}", remediation);
+                    run_remediation(api, remediation).await?;
+                }
+            }
+
+            WatchdogAction::None => (),
+        }
+
+        Ok(WatchdogTick { failures, action })
+    }
+
+    fn next_action(&mut self, unhealthy: bool, now: Instant) -> WatchdogAction {
+        if !unhealthy {
+            self.consecutive_failures = 0;
+            return WatchdogAction::None;
+        }
+
+        self.consecutive_failures += 1;
+
+        if self.consecutive_failures < self.failures_before_action {
+            return WatchdogAction::Waiting;
+        }
+
+        let cooling_down = self
+            .last_action
+            .map(|last| now.duration_since(last) < self.cooldown)
+            .unwrap_or(false);
+
+        if cooling_down {
+            return WatchdogAction::CoolingDown;
+        }
+
+        self.last_action = Some(now);
+        self.consecutive_failures = 0;
+
+        if self.dry_run {
+            WatchdogAction::DryRun
+        } else {
+            WatchdogAction::Remediated
+        }
+    }
+}
+
+async fn run_check(
+    api: &mut MikrotikAPI<Authenticated>,
+    check: &HealthCheck,
+) -> Result<Option<String>, Error> {
+    use HealthCheck::*;
+    match check {
+        Ping {
+            address,
+            count,
+            min_received,
+        } => {
+            let count = count.to_string();
+
+            let replies = api
+                .generic_array_call::<PingReply>(
+                    "/ping",
+                    Some(&[("address", address), ("count", &count)]),
+                )
+                .await?;
+
+            let received = replies
+                .iter()
+                .map(|reply| reply.received)
+                .max()
+                .unwrap_or(0);
+
+            Ok((received < *min_received)
+                .then(|| format!("{} answered {}/{} pings", address, received, count)))
+        }
+
+        FreeMemory { min_bytes } => {
+            let resources = api.system_resources().await?;
+
+            Ok((u64::from(resources.free_memory) < *min_bytes)
+                .then(|| format!("free memory is {} bytes", resources.free_memory)))
+        }
+
+        InterfaceRunning { name } => {
+            let running = api
+                .interfaces()
+                .await?
+                .iter()
+                .any(|iface| &iface.name == name && iface.running);
+
+            Ok((!running).then(|| format!("interface {} is not running", name)))
+        }
+    }
+}
+
+async fn run_remediation(
+    api: &mut MikrotikAPI<Authenticated>,
+    remediation: &Remediation,
+) -> Result<(), Error> {
+    type Reply = HashMap<String, String>;
+
+    use Remediation::*;
+    match remediation {
+        Reboot => {
+            api.generic_array_call::<Reply>("/system/reboot", None)
+                .await?;
+        }
+
+        BounceInterface { name, down_for } => {
+            api.generic_array_call::<Reply>("/interface/disable", Some(&[("numbers", name)]))
+                .await?;
+
+            tokio::time::sleep(*down_for).await;
+
+            api.generic_array_call::<Reply>("/interface/enable", Some(&[("numbers", name)]))
+                .await?;
+        }
+
+        Command {
+            command,
+            attributes,
+        } => {
+            let attributes: Vec<(&str, &str)> = attributes
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect();
+
+            api.generic_array_call::<Reply>(command, Some(&attributes))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::{Watchdog, WatchdogAction};
+
+    #[test]
+    fn test_next_action() {
+        let mut watchdog = Watchdog::new()
+            .failures_before_action(2)
+            .cooldown(Duration::from_secs(600));
+
+        let start = Instant::now();
+
+        assert_eq!(WatchdogAction::Waiting, watchdog.next_action(true, start));
+        assert_eq!(WatchdogAction::None, watchdog.next_action(false, start));
+        assert_eq!(WatchdogAction::Waiting, watchdog.next_action(true, start));
+        assert_eq!(
+            WatchdogAction::Remediated,
+            watchdog.next_action(true, start)
+        );
+
+        let later = start + Duration::from_secs(60);
+        assert_eq!(WatchdogAction::Waiting, watchdog.next_action(true, later));
+        assert_eq!(
+            WatchdogAction::CoolingDown,
+            watchdog.next_action(true, later)
+        );
+
+        let much_later = start + Duration::from_secs(601);
+        assert_eq!(
+            WatchdogAction::Remediated,
+            watchdog.next_action(true, much_later)
+        );
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let mut watchdog = Watchdog::new().failures_before_action(1).dry_run(true);
+
+        assert_eq!(
+            WatchdogAction::DryRun,
+            watchdog.next_action(true, Instant::now())
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use futures::Stream;
+
+use super::{
+    error::Error,
+    model::{Response, WifiInterface, WirelessScan},
+    options::CallOptions,
+    Authenticated, MikrotikAPI,
+};
+
+/// Time given to the router to end a scan by itself once its duration is over, before it is cancelled
+const SCAN_GRACE: Duration = Duration::from_secs(5);
+
+impl MikrotikAPI<Authenticated> {
+    /// Scans for access points with `interface` during `duration`, streaming them as they are found.
+    ///
+    /// Goes through `/interface/wifi/scan` if `interface` belongs to the `wifi` package of RouterOS 7,
+    /// `/interface/wireless/scan` otherwise. The interface does not serve its clients while it scans: the router is given
+    /// `duration` and ends the scan by itself, the stream ending with it. Should it keep scanning, the command is cancelled
+    /// through `tag` a few seconds later.
+    pub async fn wireless_scan(
+        &mut self,
+        interface: &str,
+        duration: Duration,
+        tag: &mut u16,
+    ) -> Result<impl Stream<Item = Response<WirelessScan>>, Error> {
+        let (command, number) = match self.is_wifi_interface(interface).await? {
+            true => ("/interface/wifi/scan", "numbers"),
+            false => ("/interface/wireless/scan", "number"),
+        };
+
+        let seconds = format!("{}s", duration.as_secs().max(1));
+
+        let options = CallOptions::default().with_timeout(duration + SCAN_GRACE);
+
+        self.generic_streaming_call_with_options(
+            command,
+            Some(&[(number, interface), ("duration", &seconds)]),
+            tag,
+            &options,
+        )
+        .await
+    }
+
+    /// Whether `interface` is managed by the `wifi` package, absent from RouterOS 6 and older versions of 7
+    async fn is_wifi_interface(&mut self, interface: &str) -> Result<bool, Error> {
+        let interfaces = self
+            .list_where::<WifiInterface>(&[("name", interface)])
+            .await;
+
+        match interfaces {
+            Ok(interfaces) => Ok(!interfaces.is_empty()),
+            Err(Error::Remote(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        api::model::Response,
+        mock::{MockReply, MockRouter},
+    };
+    use futures::StreamExt;
+
+    /// RouterOS 6 router finding two access points, the scan ending after its duration
+    fn mock_router() -> MockRouter {
+        let scan = MockReply::new()
+            .re(&[
+                (".id", "*1"),
+                ("address", "4C:5E:0C:11:22:33"),
+                ("ssid", "office"),
+                ("channel", "2412/20-Ce/gn"),
+                ("sig", "-58"),
+                ("nf", "-107"),
+                ("radio-name", "ap-lobby"),
+            ])
+            .re(&[
+                (".id", "*2"),
+                ("address", "B8:69:F4:44:55:66"),
+                ("ssid", ""),
+                ("channel", "2437/20/gn"),
+                ("sig", "-81"),
+            ]);
+
+        // `/interface/wifi/print` is unknown, and so is a scan with other arguments
+        MockRouter::new().on_query(
+            "/interface/wireless/scan",
+            &["=number=wlan1", "=duration=2s"],
+            scan,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_wireless_scan() {
+        let mut api = mock_router().connect().await.unwrap();
+
+        let mut tag = 0;
+
+        let replies: Vec<_> = api
+            .wireless_scan("wlan1", Duration::from_secs(2), &mut tag)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        let found: Vec<_> = replies
+            .into_iter()
+            .filter_map(|reply| match reply {
+                Response::Reply(bss) => Some(bss),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(2, found.len());
+        assert_eq!("4C:5E:0C:11:22:33", found[0].bssid.to_string());
+        assert_eq!("office", found[0].ssid);
+        assert_eq!(Some(-58), found[0].signal_strength);
+        assert_eq!(Some(-107), found[0].noise_floor);
+        assert_eq!("", found[1].ssid);
+        assert_eq!(Some(-81), found[1].signal_strength);
+    }
+}
@@ -0,0 +1,38 @@
+//! Wire-level entry points, for the benchmarks and fuzz targets only. Not part of the public API.
+
+use std::io::Cursor;
+
+use serde::de::DeserializeOwned;
+
+use crate::{api, Response};
+
+/// Encodes `words` as a sentence, the closing empty word included. `None` if a word is too long to be framed
+pub fn encode_sentence(words: &[&str]) -> Option<Vec<u8>> {
+    api::encode_sentence(words).ok()
+}
+
+/// Decodes the length prefix of a word, along with the number of bytes it spans
+pub fn decode_len(bytes: &[u8]) -> Option<(u32, usize)> {
+    let mut cursor = Cursor::new(bytes);
+
+    let len = api::read::read_len(&mut cursor).ok()?;
+
+    Some((len, cursor.position() as usize))
+}
+
+/// Reads the first sentence held by `bytes`, along with the number of bytes it spans
+pub fn decode_sentence(bytes: &[u8]) -> Option<(Vec<String>, usize)> {
+    let mut cursor = Cursor::new(bytes);
+
+    let sentence = api::read::read_sentence(&mut cursor).ok()?;
+
+    Some((
+        sentence.iter().map(|w| w.to_string()).collect(),
+        cursor.position() as usize,
+    ))
+}
+
+/// Deserializes a sentence as received from the router
+pub fn deserialize_sentence<T: DeserializeOwned>(sentence: &[String]) -> Option<Response<T>> {
+    api::de::deserialize_sentence(sentence).ok()
+}
@@ -0,0 +1,88 @@
+use std::{collections::BTreeMap, path::Path};
+
+use log::{info, warn};
+use mikrotik_api::{Authenticated, MikrotikAPI, RosEntity, RosId};
+use serde::{Deserialize, Serialize};
+
+use crate::failure::Failure;
+
+/// Just enough of `/interface` to resolve names and set comments
+#[derive(Debug, Deserialize, Serialize)]
+struct InterfaceComment {
+    #[serde(rename = ".id", skip_serializing)]
+    id: RosId,
+
+    #[serde(skip_serializing)]
+    name: String,
+
+    comment: Option<String>,
+}
+
+impl RosEntity for InterfaceComment {
+    const PATH: &'static str = "/interface";
+
+    const PROPLIST: Option<&'static [&'static str]> = Some(&[".id", "name", "comment"]);
+
+    fn id(&self) -> Option<String> {
+        Some(self.id.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    name: String,
+    comment: String,
+}
+
+#[derive(Serialize)]
+struct Comment<'a> {
+    comment: &'a str,
+}
+
+pub async fn annotate(api: &mut MikrotikAPI<Authenticated>, csv: &Path) -> Result<(), Failure> {
+    let rows: Vec<Row> = csv::Reader::from_path(csv)
+        .and_then(|mut reader| reader.deserialize().collect())
+        .map_err(|e| Failure::Usage(format!("{}: {}", csv.display(), e)))?;
+
+    let interfaces = api.list::<InterfaceComment>().await?;
+
+    // Interfaces sharing the same new comment are set in a single call
+    let mut batches: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+
+    for row in &rows {
+        let interface = match interfaces.iter().find(|iface| iface.name == row.name) {
+            Some(interface) => interface,
+            None => {
+                warn!("No interface named '{}', skipping.", row.name);
+                continue;
+            }
+        };
+
+        if interface.comment.as_deref().unwrap_or("") == row.comment {
+            continue;
+        }
+
+        batches
+            .entry(&row.comment)
+            .or_default()
+            .push(interface.id.to_string());
+    }
+
+    let mut failure = None;
+
+    for (comment, ids) in batches {
+        match api
+            .set::<InterfaceComment, _>(&ids.join(","), &Comment { comment })
+            .await
+        {
+            Ok(()) => info!("Set comment '{}' on {} interface(s).", comment, ids.len()),
+
+            Err(e) => {
+                warn!("Failed to set comment '{}': {}", comment, e);
+                failure.get_or_insert(Failure::from(e));
+            }
+        }
+    }
+
+    failure.map_or(Ok(()), Err)
+}
@@ -1,19 +1,51 @@
+use std::{path::PathBuf, time::Duration};
+
 use clap::Parser;
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(name = "mk-client", author, version, about, long_about = None)]
 pub struct Args {
+    // Not required by `completions` nor `--generate-man` and possibly given by a profile, checked by `main`
     #[clap(short = 'A', long, help = "<HOST>:<PORT>")]
-    pub address: String,
+    pub address: Option<String>,
 
     #[clap(short = 'L', long)]
-    pub login: String,
+    pub login: Option<String>,
 
     #[clap(short = 'P', long)]
     pub password: Option<String>,
 
+    #[clap(
+        long,
+        help = "take the address, credentials and output format left out from this profile of the configuration file"
+    )]
+    pub profile: Option<String>,
+
+    #[clap(
+        long,
+        help = "configuration file [default: ~/.config/mk-client/config.toml]"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[clap(
+        short,
+        long,
+        help = "no logs nor error messages, the exit code telling what went wrong"
+    )]
+    pub quiet: bool,
+
+    #[clap(long, value_enum, default_value = "text")]
+    pub error_format: ErrorFormat,
+
+    #[cfg(feature = "metrics")]
+    #[clap(long, help = "print reply statistics at exit")]
+    pub stats: bool,
+
+    #[clap(long, exclusive = true, help = "print the man page on stdout and exit")]
+    pub generate_man: bool,
+
     #[clap(subcommand)]
-    pub command: Command,
+    pub command: Option<Command>,
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -25,6 +57,55 @@ pub enum Command {
 
     ActiveUsers,
 
+    #[clap(about = "check which features of the library work against the router")]
+    SelfTest,
+
+    #[clap(about = "list DHCP server leases")]
+    DhcpLeases {
+        #[clap(long, help = "only show leases of this DHCP server")]
+        server: Option<String>,
+
+        #[clap(long, help = "only show bound leases")]
+        bound_only: bool,
+
+        #[clap(long, value_enum, help = "[default: table]")]
+        format: Option<Format>,
+    },
+
+    #[clap(about = "set interface comments from a CSV file")]
+    Annotate {
+        #[clap(long, help = "CSV file with a name,comment header")]
+        from_csv: PathBuf,
+    },
+
+    #[clap(about = "sample the traffic of an interface and print its top talkers")]
+    Torch {
+        #[clap(long)]
+        interface: String,
+
+        #[clap(long, default_value = "10s", value_parser = parse_duration, help = "ie 10s, 2m or 500ms")]
+        duration: Duration,
+
+        #[clap(
+            long,
+            default_value_t = 20,
+            help = "number of source/destination pairs to show"
+        )]
+        top: usize,
+    },
+
+    #[clap(about = "run commands across several routers")]
+    Fleet {
+        #[clap(subcommand)]
+        command: FleetCommand,
+    },
+
+    #[clap(about = "print the completion script of <SHELL> on stdout")]
+    Completions {
+        #[clap(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
     Custom {
         #[clap(long, help = "run one-off command")]
         one_off: bool,
@@ -41,3 +122,61 @@ pub enum Command {
         command: String,
     },
 }
+
+#[derive(clap::Subcommand, Debug)]
+pub enum FleetCommand {
+    #[clap(
+        about = "run a command on every router, ie `fleet run --targets hosts.toml -- /system/identity/print`"
+    )]
+    Run {
+        #[clap(long, help = "TOML file listing the routers as [[target]] tables")]
+        targets: PathBuf,
+
+        #[clap(
+            long,
+            default_value_t = 8,
+            help = "number of routers talked to at once"
+        )]
+        concurrency: usize,
+
+        #[clap(long, default_value = "10s", value_parser = parse_duration, help = "time given to each router")]
+        timeout: Duration,
+
+        #[clap(
+            required = true,
+            last = true,
+            help = "command and its attributes, ie /interface/print ?type=ether"
+        )]
+        command: Vec<String>,
+    },
+}
+
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Table,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum ErrorFormat {
+    Text,
+    Json,
+}
+
+/// Duration given on the command line: a number of seconds, optionally followed by `ms`, `s`, `m` or `h`
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+    let value: u64 = s[..digits]
+        .parse()
+        .map_err(|_| format!("'{}' is not a duration", s))?;
+
+    match &s[digits..] {
+        "ms" => Ok(Duration::from_millis(value)),
+        "" | "s" => Ok(Duration::from_secs(value)),
+        "m" => Ok(Duration::from_secs(value * 60)),
+        "h" => Ok(Duration::from_secs(value * 3600)),
+        unit => Err(format!("unknown unit '{}', expected ms, s, m or h", unit)),
+    }
+}
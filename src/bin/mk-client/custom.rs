@@ -4,6 +4,8 @@ use futures::StreamExt;
 use log::info;
 use mikrotik_api::{Authenticated, MikrotikAPI, Response};
 
+use crate::failure::Failure;
+
 pub enum CommandType {
     OneOff,
     ArrayList,
@@ -15,7 +17,7 @@ pub async fn custom_command(
     cmd_type: CommandType,
     command: &str,
     proplist: Option<String>,
-) {
+) -> Result<(), Failure> {
     let mut attributes = Vec::new();
 
     if let Some(list) = proplist.as_deref() {
@@ -29,16 +31,14 @@ pub async fn custom_command(
         OneOff => {
             let map = api
                 .generic_oneshot_call::<HashMap<String, String>>(command, attributes)
-                .await
-                .unwrap();
+                .await?;
 
             info!("Reply:\n{:#?}", map)
         }
         ArrayList => {
             let map = api
                 .generic_array_call::<HashMap<String, String>>(command, attributes)
-                .await
-                .unwrap();
+                .await?;
 
             info!("Received {} replies:\n{:#?}", map.len(), map)
         }
@@ -47,7 +47,7 @@ pub async fn custom_command(
             let mut _tag = 0;
             let stream = api
                 .generic_streaming_call::<HashMap<String, String>>(command, attributes, &mut _tag)
-                .await;
+                .await?;
 
             info!("Listening for events...");
             tokio::spawn(stream.for_each(move |item| async {
@@ -56,7 +56,9 @@ pub async fn custom_command(
                 }
             }))
             .await
-            .unwrap();
+            .map_err(|e| Failure::Other(e.to_string()))?;
         }
     }
+
+    Ok(())
 }
@@ -0,0 +1,125 @@
+use mikrotik_api::{Authenticated, DhcpLease, MikrotikAPI};
+use serde::Serialize;
+
+use crate::{config::Format, failure::Failure};
+
+const HEADERS: [&str; 6] = [
+    "ADDRESS",
+    "MAC ADDRESS",
+    "HOST NAME",
+    "SERVER",
+    "STATUS",
+    "EXPIRES AFTER",
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Lease {
+    id: String,
+    address: String,
+    mac_address: String,
+    host_name: Option<String>,
+    server: Option<String>,
+    status: Option<String>,
+    expires_after: Option<String>,
+    dynamic: bool,
+    disabled: bool,
+    comment: Option<String>,
+}
+
+impl From<DhcpLease> for Lease {
+    fn from(lease: DhcpLease) -> Self {
+        Lease {
+            id: lease.id.map(|id| id.to_string()).unwrap_or_default(),
+            address: lease.address,
+            mac_address: lease.mac_address.to_string(),
+            host_name: lease.host_name,
+            server: lease.server,
+            status: lease.status,
+            expires_after: lease.expires_after,
+            dynamic: lease.dynamic,
+            disabled: lease.disabled,
+            comment: lease.comment,
+        }
+    }
+}
+
+impl Lease {
+    fn columns(&self) -> [&str; 6] {
+        fn or_blank(value: &Option<String>) -> &str {
+            value.as_deref().unwrap_or("")
+        }
+
+        [
+            &self.address,
+            &self.mac_address,
+            or_blank(&self.host_name),
+            or_blank(&self.server),
+            or_blank(&self.status),
+            or_blank(&self.expires_after),
+        ]
+    }
+}
+
+fn print_table(leases: &[Lease]) {
+    let mut widths = HEADERS.map(str::len);
+
+    for lease in leases {
+        for (width, column) in widths.iter_mut().zip(lease.columns()) {
+            *width = (*width).max(column.len());
+        }
+    }
+
+    let print_row = |columns: [&str; 6]| {
+        let row: Vec<String> = columns
+            .iter()
+            .zip(widths)
+            .map(|(column, width)| format!("{:width$}", column, width = width))
+            .collect();
+
+        println!("{}", row.join("  ").trim_end());
+    };
+
+    print_row(HEADERS);
+
+    for lease in leases {
+        print_row(lease.columns());
+    }
+}
+
+pub async fn leases(
+    api: &mut MikrotikAPI<Authenticated>,
+    server: Option<&str>,
+    bound_only: bool,
+    format: Format,
+) -> Result<(), Failure> {
+    let mut query = Vec::new();
+
+    if let Some(server) = server {
+        query.push(("server", server));
+    }
+
+    if bound_only {
+        query.push(("status", "bound"));
+    }
+
+    let leases: Vec<Lease> = api
+        .list_where::<DhcpLease>(&query)
+        .await?
+        .into_iter()
+        .map(Lease::from)
+        .collect();
+
+    match format {
+        Format::Table => print_table(&leases),
+
+        Format::Json => {
+            let json =
+                serde_json::to_string_pretty(&leases).map_err(|e| Failure::Other(e.to_string()))?;
+
+            println!("{}", json)
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use mikrotik_api::Error;
+use serde::Serialize;
+
+use crate::config::ErrorFormat;
+
+/// Why the client failed, each kind having its own exit code
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "error", content = "message")]
+pub enum Failure {
+    /// Anything not covered below, such as a failed self-test
+    Other(String),
+
+    /// Bad arguments or input files, or a command denied by the library
+    Usage(String),
+
+    /// The router could not be reached or the connection dropped
+    Connection(String),
+
+    /// The router rejected the credentials
+    Authentication(String),
+
+    /// The router answered a command with a `!trap`
+    Trap(String),
+}
+
+impl Failure {
+    pub fn exit_code(&self) -> i32 {
+        use Failure::*;
+        match self {
+            Other(_) => 1,
+            Usage(_) => 2,
+            Connection(_) => 3,
+            Authentication(_) => 4,
+            Trap(_) => 5,
+        }
+    }
+
+    fn message(&self) -> &str {
+        use Failure::*;
+        match self {
+            Other(m) | Usage(m) | Connection(m) | Authentication(m) | Trap(m) => m,
+        }
+    }
+
+    /// Prints the failure on stderr, nothing being printed in quiet mode
+    pub fn report(&self, format: ErrorFormat, quiet: bool) {
+        if quiet {
+            return;
+        }
+
+        match format {
+            ErrorFormat::Text => eprintln!("Error: {}", self.message()),
+
+            ErrorFormat::Json => {
+                #[derive(Serialize)]
+                struct Report<'a> {
+                    #[serde(flatten)]
+                    failure: &'a Failure,
+                    exit_code: i32,
+                }
+
+                let report = Report {
+                    failure: self,
+                    exit_code: self.exit_code(),
+                };
+
+                eprintln!("{}", serde_json::to_string(&report).unwrap_or_default());
+            }
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(e: Error) -> Self {
+        let message = e.to_string();
+
+        match e {
+            Error::Remote(message) => Failure::Trap(message),
+
+            Error::Io(_)
+            | Error::EndOfStream
+            | Error::Malformed(_)
+            | Error::ConnectionLost(_)
+            | Error::ConnectionLimit(_)
+            | Error::DeadlineExceeded => Failure::Connection(message),
+
+            Error::Denied(_) | Error::InvalidArgument(_) => Failure::Usage(message),
+
+            _ => Failure::Other(message),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use std::{collections::HashMap, io::Write, path::Path, time::Duration};
+
+use dialoguer::Password;
+use futures::{stream, StreamExt};
+use log::info;
+use mikrotik_api::Error;
+use serde::Deserialize;
+
+use crate::failure::Failure;
+
+const HEADERS: [&str; 3] = ["TARGET", "RESULT", "DETAIL"];
+
+/// Routers listed by `--targets`, credentials given at the top level applying to every target lacking its own.
+/// `--login` and `--password` take precedence over both, as they do over profiles
+#[derive(Debug, Deserialize)]
+struct Targets {
+    login: Option<String>,
+    password: Option<String>,
+
+    #[serde(rename = "target")]
+    targets: Vec<Target>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    /// `<HOST>:<PORT>`
+    address: String,
+    login: Option<String>,
+    password: Option<String>,
+}
+
+impl Targets {
+    /// Login and password of every target, in order. Fails if a target is left without a login
+    fn credentials(
+        &self,
+        login: Option<&str>,
+        password: Option<&str>,
+    ) -> Result<Vec<(String, Option<String>)>, Failure> {
+        let pick = |flag: Option<&str>, own: &Option<String>, shared: &Option<String>| {
+            flag.or(own.as_deref())
+                .or(shared.as_deref())
+                .map(str::to_owned)
+        };
+
+        self.targets
+            .iter()
+            .map(|target| {
+                let login = pick(login, &target.login, &self.login).ok_or_else(|| {
+                    Failure::Usage(format!(
+                        "no login for {}, give --login or a `login` in the targets file",
+                        target.address
+                    ))
+                })?;
+
+                Ok((login, pick(password, &target.password, &self.password)))
+            })
+            .collect()
+    }
+}
+
+enum Outcome {
+    Success(Vec<HashMap<String, String>>),
+    Trap(String),
+    AuthFailure(String),
+    Unreachable(String),
+}
+
+impl Outcome {
+    fn columns(&self) -> [String; 2] {
+        match self {
+            Outcome::Success(replies) => {
+                let detail = match replies.as_slice() {
+                    [reply] => {
+                        let mut properties: Vec<_> = reply
+                            .iter()
+                            .filter(|(name, _)| *name != ".id")
+                            .map(|(name, value)| format!("{}={}", name, value))
+                            .collect();
+
+                        properties.sort();
+                        properties.join(" ")
+                    }
+
+                    replies => format!("{} replies", replies.len()),
+                };
+
+                ["success".into(), detail]
+            }
+
+            Outcome::Trap(message) => ["trap".into(), message.clone()],
+
+            Outcome::AuthFailure(message) => ["auth failure".into(), message.clone()],
+
+            Outcome::Unreachable(message) => ["unreachable".into(), message.clone()],
+        }
+    }
+
+    /// Index of the outcome in the counts
+    fn slot(&self) -> usize {
+        match self {
+            Outcome::Success(_) => 0,
+            Outcome::Trap(_) => 1,
+            Outcome::AuthFailure(_) => 2,
+            Outcome::Unreachable(_) => 3,
+        }
+    }
+}
+
+/// Splits `?type=ether`, `=name=office` or `name=office` into the `(key, value)` pairs taken by the generic calls
+fn attribute(word: &str) -> (String, String) {
+    let (prefix, rest) = match word.strip_prefix('=') {
+        Some(rest) => ("=", rest),
+        None => ("", word),
+    };
+
+    match rest.split_once('=') {
+        Some((key, value)) => (format!("{}{}", prefix, key), value.to_owned()),
+        None => (word.to_owned(), String::new()),
+    }
+}
+
+async fn run_on(
+    target: &Target,
+    login: &str,
+    password: &str,
+    command: &str,
+    attributes: &[(&str, &str)],
+) -> Outcome {
+    let api = match mikrotik_api::connect(target.address.as_str()).await {
+        Ok(api) => api,
+        Err(e) => return Outcome::Unreachable(e.to_string()),
+    };
+
+    let mut api = match api.authenticate(login, password).await {
+        Ok(api) => api,
+        Err(Error::Remote(message)) => return Outcome::AuthFailure(message),
+        Err(e) => return Outcome::Unreachable(e.to_string()),
+    };
+
+    match api.generic_array_call(command, Some(attributes)).await {
+        Ok(replies) => Outcome::Success(replies),
+        Err(Error::Remote(message)) => Outcome::Trap(message),
+        Err(e) => Outcome::Unreachable(e.to_string()),
+    }
+}
+
+fn print_table(targets: &[Target], outcomes: &[Outcome]) {
+    let rows: Vec<[String; 3]> = targets
+        .iter()
+        .zip(outcomes)
+        .map(|(target, outcome)| {
+            let [result, detail] = outcome.columns();
+            [target.address.clone(), result, detail]
+        })
+        .collect();
+
+    let mut widths = HEADERS.map(str::len);
+
+    for row in &rows {
+        for (width, column) in widths.iter_mut().zip(row) {
+            *width = (*width).max(column.len());
+        }
+    }
+
+    let print_row = |columns: [&str; 3]| {
+        let row: Vec<String> = columns
+            .iter()
+            .zip(widths)
+            .map(|(column, width)| format!("{:width$}", column, width = width))
+            .collect();
+
+        println!("{}", row.join("  ").trim_end());
+    };
+
+    print_row(HEADERS);
+
+    for row in &rows {
+        print_row(row.each_ref().map(String::as_str));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    targets: &Path,
+    login: Option<String>,
+    password: Option<String>,
+    concurrency: usize,
+    timeout: Duration,
+    words: &[String],
+    quiet: bool,
+) -> Result<(), Failure> {
+    let file = std::fs::read_to_string(targets)
+        .map_err(|e| Failure::Usage(format!("{}: {}", targets.display(), e)))?;
+
+    let file: Targets = toml::from_str(&file)
+        .map_err(|e| Failure::Usage(format!("{}: {}", targets.display(), e)))?;
+
+    let mut credentials = file.credentials(login.as_deref(), password.as_deref())?;
+
+    if credentials.iter().any(|(_, password)| password.is_none()) {
+        let typed = Password::new()
+            .with_prompt("Password")
+            .interact()
+            .map_err(|e| Failure::Usage(e.to_string()))?;
+
+        for (_, password) in &mut credentials {
+            password.get_or_insert_with(|| typed.clone());
+        }
+    }
+
+    let (command, attributes) = words
+        .split_first()
+        .ok_or_else(|| Failure::Usage("no command given".into()))?;
+
+    let attributes: Vec<(String, String)> = attributes.iter().map(|w| attribute(w)).collect();
+    let attributes: Vec<(&str, &str)> = attributes
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+
+    info!(
+        "Running {} on {} routers, {} at a time...",
+        command,
+        file.targets.len(),
+        concurrency
+    );
+
+    let runs = file.targets.iter().zip(&credentials).enumerate().map(
+        |(index, (target, (login, password)))| {
+            let password = password.as_deref().unwrap_or_default();
+            let run = run_on(target, login, password, command, &attributes);
+
+            async move {
+                let outcome = match tokio::time::timeout(timeout, run).await {
+                    Ok(outcome) => outcome,
+                    Err(_) => Outcome::Unreachable(format!("no answer within {:?}", timeout)),
+                };
+
+                (index, outcome)
+            }
+        },
+    );
+
+    let mut outcomes: Vec<Option<Outcome>> = file.targets.iter().map(|_| None).collect();
+    let mut counts = [0; 4];
+
+    let mut runs = stream::iter(runs).buffer_unordered(concurrency.max(1));
+
+    while let Some((index, outcome)) = runs.next().await {
+        counts[outcome.slot()] += 1;
+
+        outcomes[index] = Some(outcome);
+
+        if !quiet {
+            let done: usize = counts.iter().sum();
+
+            eprint!(
+                "\r[{}/{}] success: {}, trap: {}, auth failure: {}, unreachable: {}",
+                done,
+                file.targets.len(),
+                counts[0],
+                counts[1],
+                counts[2],
+                counts[3]
+            );
+            let _ = std::io::stderr().flush();
+        }
+    }
+
+    if !quiet {
+        eprintln!();
+    }
+
+    let outcomes: Vec<Outcome> = outcomes.into_iter().flatten().collect();
+
+    print_table(&file.targets, &outcomes);
+
+    println!(
+        "\n{} success, {} trap, {} auth failure, {} unreachable",
+        counts[0], counts[1], counts[2], counts[3]
+    );
+
+    match counts[1..].iter().sum() {
+        0 => Ok(()),
+        failed => Err(Failure::Other(format!(
+            "{} of {} routers failed",
+            failed,
+            file.targets.len()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Targets;
+    use crate::failure::Failure;
+
+    fn targets(text: &str) -> Targets {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_flags_take_precedence() {
+        let file = targets(
+            r#"
+            login = "shared"
+            password = "shared-secret"
+
+            [[target]]
+            address = "10.0.0.1:8728"
+
+            [[target]]
+            address = "10.0.0.2:8728"
+            login = "own"
+            "#,
+        );
+
+        let credentials = file.credentials(None, None).unwrap();
+        assert_eq!(
+            vec![
+                ("shared".to_owned(), Some("shared-secret".to_owned())),
+                ("own".to_owned(), Some("shared-secret".to_owned())),
+            ],
+            credentials
+        );
+
+        let credentials = file.credentials(Some("root"), Some("flag")).unwrap();
+        assert!(credentials
+            .iter()
+            .all(|(login, password)| login == "root" && password.as_deref() == Some("flag")));
+    }
+
+    #[test]
+    fn test_missing_login() {
+        let file = targets(
+            r#"
+            [[target]]
+            address = "10.0.0.1:8728"
+            login = "admin"
+
+            [[target]]
+            address = "10.0.0.2:8728"
+            "#,
+        );
+
+        match file.credentials(None, None) {
+            Err(Failure::Usage(message)) => assert!(message.contains("10.0.0.2:8728")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        assert!(file.credentials(Some("admin"), None).is_ok());
+    }
+}
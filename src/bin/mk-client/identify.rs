@@ -4,6 +4,8 @@ use human_bytes::human_bytes;
 use mikrotik_api::{Authenticated, MikrotikAPI, SystemResources};
 use serde::Deserialize;
 
+use crate::failure::Failure;
+
 struct PrettyRessources(SystemResources);
 
 impl Display for PrettyRessources {
@@ -35,16 +37,20 @@ struct Identity {
     pub name: String,
 }
 
-pub async fn identify(api: &mut MikrotikAPI<Authenticated>, ressources: bool) {
+pub async fn identify(
+    api: &mut MikrotikAPI<Authenticated>,
+    ressources: bool,
+) -> Result<(), Failure> {
     let identity = api
         .generic_oneshot_call::<Identity>("/system/identity/print", None)
-        .await
-        .unwrap();
+        .await?;
 
     println!("Name: '{}'", identity.name);
 
     if ressources {
-        let ressources = api.system_resources().await.unwrap();
+        let ressources = api.system_resources().await?;
         println!("{}", PrettyRessources(ressources))
     }
+
+    Ok(())
 }
@@ -5,37 +5,157 @@ use log::info;
 
 use mikrotik_api::{self, Response};
 
-use crate::{config::Args, custom::CommandType};
+use crate::{
+    config::{Args, Format},
+    custom::CommandType,
+    failure::Failure,
+};
 
+mod annotate;
 mod config;
 mod custom;
+mod dhcp;
+mod failure;
+mod fleet;
 mod identify;
+mod profile;
+mod self_test;
+mod torch;
 
 #[tokio::main]
 pub async fn main() {
-    simple_logger::init_with_level(log::Level::Info).unwrap();
+    let mut args = Args::parse();
 
-    let args = Args::parse();
+    if args.generate_man {
+        let man = clap_mangen::Man::new(Args::command());
 
-    let api = mikrotik_api::connect(args.address).await.unwrap();
+        if let Err(e) = man.render(&mut std::io::stdout()) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    let password = args.password.unwrap_or_else(|| {
-        Password::new().with_prompt("Password").interact().unwrap()
-    });
+    if let Some(config::Command::Completions { shell }) = args.command {
+        let mut cmd = Args::command();
+        let name = cmd.get_name().to_owned();
 
-    let mut api = match api.authenticate(&args.login, &password).await {
-        Ok(api) => api,
+        clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        return;
+    }
+
+    let level = match args.quiet {
+        true => log::Level::Error,
+        false => log::Level::Info,
+    };
+
+    simple_logger::init_with_level(level).unwrap();
 
-        Err(e) => {
-            println!("{:?}", e);
+    let (format, quiet) = (args.error_format, args.quiet);
+
+    let output_format = match profile::apply(&mut args) {
+        Ok(output_format) => output_format,
+
+        Err(failure) => {
+            failure.report(format, quiet);
+            std::process::exit(failure.exit_code());
+        }
+    };
 
-            return;
+    // Fleet commands connect to the routers of their targets file, not to `--address`
+    let result = match args.command {
+        Some(config::Command::Fleet {
+            command:
+                config::FleetCommand::Run {
+                    targets,
+                    concurrency,
+                    timeout,
+                    command,
+                },
+        }) => {
+            fleet::run(
+                &targets,
+                args.login,
+                args.password,
+                concurrency,
+                timeout,
+                &command,
+                quiet,
+            )
+            .await
         }
+
+        _ => run(args, output_format).await,
+    };
+
+    if let Err(failure) = result {
+        failure.report(format, quiet);
+
+        std::process::exit(failure.exit_code());
+    }
+}
+
+/// Value of an argument only required by commands talking to a router, exits like clap does when it is missing
+fn required<T>(value: Option<T>, what: &str) -> T {
+    value.unwrap_or_else(|| {
+        Args::command()
+            .error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                format!("{} is required", what),
+            )
+            .exit()
+    })
+}
+
+/// Runs a command against `--address`, `output_format` being the one of the profile, if any
+async fn run(args: Args, output_format: Option<Format>) -> Result<(), Failure> {
+    let command = required(args.command, "a subcommand");
+    let login = required(args.login, "--login <LOGIN>");
+
+    let api = mikrotik_api::connect(required(args.address, "--address <ADDRESS>"))
+        .await
+        .map_err(|e| Failure::Connection(e.to_string()))?;
+
+    let password = match args.password {
+        Some(password) => password,
+
+        None => Password::new()
+            .with_prompt("Password")
+            .interact()
+            .map_err(|e| Failure::Usage(e.to_string()))?,
+    };
+
+    let mut api = match api.authenticate(&login, &password).await {
+        Ok(api) => api,
+
+        Err(mikrotik_api::Error::Remote(message)) => return Err(Failure::Authentication(message)),
+
+        Err(e) => return Err(e.into()),
     };
 
     use config::Command::*;
-    match args.command {
-        Identify { full } => identify::identify(&mut api, full).await,
+    match command {
+        Identify { full } => identify::identify(&mut api, full).await?,
+
+        SelfTest => self_test::self_test(&mut api).await?,
+
+        Annotate { from_csv } => annotate::annotate(&mut api, &from_csv).await?,
+
+        DhcpLeases {
+            server,
+            bound_only,
+            format,
+        } => {
+            let format = format.or(output_format).unwrap_or(Format::Table);
+
+            dhcp::leases(&mut api, server.as_deref(), bound_only, format).await?
+        }
+
+        Torch {
+            interface,
+            duration,
+            top,
+        } => torch::top_talkers(&mut api, &interface, duration, top).await?,
 
         Custom {
             one_off,
@@ -61,13 +181,15 @@ pub async fn main() {
                 }
             };
 
-            custom::custom_command(&mut api, cmd_type, &command, proplist).await;
+            custom::custom_command(&mut api, cmd_type, &command, proplist).await?;
         }
 
+        Completions { .. } | Fleet { .. } => unreachable!("handled before connecting"),
+
         ActiveUsers => {
             let mut tag = 0;
 
-            let stream = api.active_users(&mut tag).await;
+            let stream = api.active_users(&mut tag).await?;
 
             info!("Listening for active users...");
 
@@ -92,7 +214,14 @@ pub async fn main() {
                 }
             }))
             .await
-            .unwrap();
+            .map_err(|e| Failure::Other(e.to_string()))?;
         }
     };
+
+    #[cfg(feature = "metrics")]
+    if args.stats {
+        println!("{}", api.stats_report());
+    }
+
+    Ok(())
 }
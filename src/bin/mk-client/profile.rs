@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    config::{Args, Format},
+    failure::Failure,
+};
+
+/// The configuration file, holding `[profile.<name>]` tables
+#[derive(Debug, Deserialize)]
+struct ConfigFile {
+    #[serde(default, rename = "profile")]
+    profiles: HashMap<String, Profile>,
+}
+
+/// Settings of a router or a group of routers, command line flags taking precedence over them
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// `<HOST>:<PORT>`
+    pub address: Option<String>,
+    pub login: Option<String>,
+    pub password: Option<String>,
+
+    /// Output format of the commands having one
+    pub format: Option<Format>,
+
+    /// Not supported: the client only speaks the plain API (port 8728), so a profile with TLS settings is rejected
+    /// rather than connecting in clear text
+    tls: Option<toml::Value>,
+}
+
+/// `$XDG_CONFIG_HOME/mk-client/config.toml`, `~/.config/mk-client/config.toml` by default
+pub fn default_path() -> Option<PathBuf> {
+    config_path(
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::var_os("HOME"),
+    )
+}
+
+fn config_path(xdg_config_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    let config_home = xdg_config_home
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| Path::new(&home).join(".config")))?;
+
+    Some(config_home.join("mk-client").join("config.toml"))
+}
+
+/// Profile `name` of the configuration file at `path`
+fn load(path: &Path, name: &str) -> Result<Profile, Failure> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| Failure::Usage(format!("{}: {}", path.display(), e)))?;
+
+    let mut file: ConfigFile =
+        toml::from_str(&text).map_err(|e| Failure::Usage(format!("{}: {}", path.display(), e)))?;
+
+    let profile = file.profiles.remove(name).ok_or_else(|| {
+        Failure::Usage(format!("{}: no profile named '{}'", path.display(), name))
+    })?;
+
+    if profile.tls.is_some() {
+        return Err(Failure::Usage(format!(
+            "{}: profile '{}': TLS is not supported, use the plain API port (8728)",
+            path.display(),
+            name
+        )));
+    }
+
+    Ok(profile)
+}
+
+/// Fills the flags left out on the command line from the profile given with `--profile`, if any.
+/// Returns the output format of the profile
+pub fn apply(args: &mut Args) -> Result<Option<Format>, Failure> {
+    let Some(name) = args.profile.as_deref() else {
+        return Ok(None);
+    };
+
+    let path = match args.config.clone().or_else(default_path) {
+        Some(path) => path,
+        None => return Err(Failure::Usage("no configuration file, see --config".into())),
+    };
+
+    let profile = load(&path, name)?;
+
+    args.address = args.address.take().or(profile.address);
+    args.login = args.login.take().or(profile.login);
+    args.password = args.password.take().or(profile.password);
+
+    Ok(profile.format)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use clap::Parser;
+
+    use super::{apply, config_path};
+    use crate::{config::Args, failure::Failure};
+
+    /// Configuration file named after the test, holding `text`
+    fn config_file(name: &str, text: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("mk-client-{}-{}.toml", name, std::process::id()));
+
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn args(config: &Path, flags: &[&str]) -> Args {
+        let mut args = vec!["mk-client", "--config", config.to_str().unwrap()];
+
+        args.extend(["--profile", "office"]);
+        args.extend(flags);
+        args.push("identify");
+
+        Args::parse_from(args)
+    }
+
+    #[test]
+    fn test_flags_take_precedence() {
+        let path = config_file(
+            "precedence",
+            r#"
+            [profile.office]
+            address = "192.168.88.1:8728"
+            login = "admin"
+            password = "from-profile"
+            format = "json"
+            "#,
+        );
+
+        let mut args = args(&path, &["-L", "root"]);
+        let format = apply(&mut args).unwrap();
+
+        assert_eq!(Some("192.168.88.1:8728"), args.address.as_deref());
+        assert_eq!(Some("root"), args.login.as_deref());
+        assert_eq!(Some("from-profile"), args.password.as_deref());
+        assert!(format.is_some());
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_unknown_profile_and_tls() {
+        let path = config_file(
+            "tls",
+            r#"
+            [profile.office]
+            address = "192.168.88.1:8729"
+            tls = { verify = true }
+            "#,
+        );
+
+        match apply(&mut args(&path, &[])) {
+            Err(Failure::Usage(message)) => assert!(message.contains("TLS is not supported")),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        let _ = std::fs::remove_file(path);
+
+        let path = config_file("unknown", "[profile.lab]\naddress = \"10.0.0.1:8728\"\n");
+
+        assert!(matches!(
+            apply(&mut args(&path, &[])),
+            Err(Failure::Usage(_))
+        ));
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_config_path() {
+        let expected = |dir: &str| Some(PathBuf::from(dir).join("mk-client").join("config.toml"));
+
+        assert_eq!(
+            expected("/etc/xdg"),
+            config_path(Some("/etc/xdg".into()), Some("/home/admin".into()))
+        );
+
+        // An empty XDG_CONFIG_HOME is ignored, as the XDG spec asks
+        assert_eq!(
+            expected("/home/admin/.config"),
+            config_path(Some("".into()), Some("/home/admin".into()))
+        );
+        assert_eq!(
+            expected("/home/admin/.config"),
+            config_path(None, Some("/home/admin".into()))
+        );
+
+        assert_eq!(None, config_path(None, None));
+    }
+}
@@ -0,0 +1,24 @@
+use mikrotik_api::{Authenticated, MikrotikAPI};
+
+use crate::failure::Failure;
+
+pub async fn self_test(api: &mut MikrotikAPI<Authenticated>) -> Result<(), Failure> {
+    let report = api.self_test().await;
+
+    println!(
+        "RouterOS version: {}",
+        report.version.as_deref().unwrap_or("unknown")
+    );
+
+    for check in &report.checks {
+        match &check.outcome {
+            Ok(()) => println!("[ OK ] {}", check.name),
+            Err(e) => println!("[FAIL] {}: {}", check.name, e),
+        }
+    }
+
+    match report.success() {
+        true => Ok(()),
+        false => Err(Failure::Other("some checks failed".to_owned())),
+    }
+}
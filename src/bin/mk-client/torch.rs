@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use log::info;
+use mikrotik_api::{Authenticated, MikrotikAPI, TopTalker};
+
+use crate::failure::Failure;
+
+const HEADERS: [&str; 6] = [
+    "SOURCE",
+    "DESTINATION",
+    "TX",
+    "RX",
+    "TX PACKETS",
+    "RX PACKETS",
+];
+
+/// Rate in bits per second, ie `12.3 Mbps`
+fn rate(bits: u64) -> String {
+    const UNITS: [&str; 4] = ["bps", "kbps", "Mbps", "Gbps"];
+
+    let mut value = bits as f64;
+    let mut unit = 0;
+
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} {}", bits, UNITS[0]),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
+
+fn columns(talker: &TopTalker) -> [String; 6] {
+    [
+        talker.src_address.clone(),
+        talker.dst_address.clone(),
+        rate(talker.tx),
+        rate(talker.rx),
+        talker.tx_packets.to_string(),
+        talker.rx_packets.to_string(),
+    ]
+}
+
+fn print_table(talkers: &[TopTalker]) {
+    let rows: Vec<_> = talkers.iter().map(columns).collect();
+
+    let mut widths = HEADERS.map(str::len);
+
+    for row in &rows {
+        for (width, column) in widths.iter_mut().zip(row) {
+            *width = (*width).max(column.len());
+        }
+    }
+
+    let print_row = |columns: [&str; 6]| {
+        let row: Vec<String> = columns
+            .iter()
+            .zip(widths)
+            .map(|(column, width)| format!("{:width$}", column, width = width))
+            .collect();
+
+        println!("{}", row.join("  ").trim_end());
+    };
+
+    print_row(HEADERS);
+
+    for row in &rows {
+        print_row(row.each_ref().map(String::as_str));
+    }
+}
+
+pub async fn top_talkers(
+    api: &mut MikrotikAPI<Authenticated>,
+    interface: &str,
+    duration: Duration,
+    top: usize,
+) -> Result<(), Failure> {
+    let mut tag = 0;
+
+    let stream = api.torch(interface, &mut tag).await?;
+
+    info!("Sampling traffic of {} for {:?}...", interface, duration);
+
+    let sample = api.collect_for(stream, tag, duration).await?;
+
+    print_table(&mikrotik_api::top_talkers(&sample, top));
+
+    Ok(())
+}
@@ -15,51 +15,56 @@
 //!
 //! ### Usage
 //!
-//! The library exposes only one function: `connect`, that makes a TCP connection to the provided address.
-//! If successful, a `MikrotikAPI<Disconnected>` object is returned.
-//! It is then necessary to `authenticate` to get a `MikrotikAPI<Authenticated>` object.
-
-//! Eight functions are then available:
-//! - `system_resources` will make a call to `/system/resource/print`
-//! - `interfaces` will retrieve a list of interfaces with all their properties (``/interfaces/print``)
-//! - `active_users` returns a `Stream` of events regarding user activity (login & logout)
-//! - `interface_changes` returns a `Stream` of events regarding changes to interfaces (up, down, ...)
-//! - `cancel` cancels a streaming command given its tag
-//! - `generic_oneshot_call` allows to call any endpoint providing a one-off answer. Thanks to type inference, answer is returned in the user's object of choice. Example:
-
-//! ```rust
+//! `connect` (or `connect_with_options`, see `ConnectOptions`) opens a TCP connection to the router and returns a
+//! `MikrotikAPI<Disconnected>`, which `authenticate` turns into a `MikrotikAPI<Authenticated>`. `ReconnectPolicy`
+//! does both with retries, and `from_transport` runs the API over any other byte stream.
+//!
+//! An authenticated connection offers:
+//! - typed calls for common menus, such as `system_resources`, `interfaces`, `routerboard` or `license`,
+//!   along with those of the feature-gated modules described below,
+//! - typed tables through `RosEntity`: `list`, `list_where`, `add`, `set` and `remove`,
+//! - streams of events, such as `active_users` or `interfaces_changes`, ended with `cancel` given their tag,
+//! - generic calls for any other command, their replies being deserialized into a type of the caller's choice.
+//!
+//! `generic_oneshot_call` is for commands answering once:
+//!
+//! ```rust,ignore
 //! #[derive(Debug, Deserialize)]
 //! struct Identity {
 //!   pub name: String,
 //! }
-
+//!
 //! let identity = api
 //!   .generic_oneshot_call::<Identity>("/system/identity/print", None)
-//!   .await
-//!   .unwrap();
-
+//!   .await?;
+//!
 //! println!("Name: '{}'", identity.name);
 //! ```
-
-//! - `generic_array_call` will do the same job but for endpoints providing multiples (but finite) answers
-//! - `generic_streaming_call` will provide a `Stream` of `Response` for any endpoint supporting the `listen` command. Example:
-//! ```rust
+//!
+//! `generic_array_call` does the same for commands answering with a finite list, and `generic_streaming_call` provides a
+//! `Stream` of `Response` for commands that keep answering, such as `listen`. Each of them has a `_with_options`
+//! variant taking `CallOptions`: deadline, cancellation, priority, progress, pre-filter and strictness.
+//! `generic_array_call_detached` sends a print and hands back a future of its replies, which borrows nothing from the
+//! connection: other calls can be made while a large print comes in.
+//!
+//! ```rust,ignore
 //! #[derive(Debug, Deserialize)]
 //! struct Interface {
 //!   pub name: String,
 //!
 //!   #[serde(default)]
 //!   pub running: bool
-//!}
+//! }
 //!
 //! let mut tag: u16 = 0;
 //!
 //! let changes = api
-//!   .generic_streaming_call::<Interface>("/interface/listen", None, &mut tag); //`tag` allows us to cancel the stream later on.
+//!   .generic_streaming_call::<Interface>("/interface/listen", None, &mut tag) //`tag` allows us to cancel the stream later on.
+//!   .await?;
 //!
 //! tokio::spawn(changes.for_each(|item| async move {
 //!
-//!   if let Reponse::Reply(iface) = item {
+//!   if let Response::Reply(iface) = item {
 //!
 //!       let up_down = if iface.running { "up" } else { "down" };
 //!
@@ -68,6 +73,132 @@
 //!
 //! })).await;
 //! ```
+//!
+//! The rest of this page goes through the other facilities of the library.
+//!
+//! `collect_n` and `collect_for` take a sample of a stream, ie the first 10 replies or 10 seconds of `/tool/torch`,
+//! then cancel the command and return the replies collected. `torch` starts such a stream, `top_talkers` averaging its
+//! sample by source and destination address.
+//!
+//! Some configuration changes make RouterOS end every `listen` command at once. Subscriptions started through a
+//! `KeepAlive` are sent again by `KeepAlive::watch` once the router settled, their streams carrying on after a
+//! `SubscriptionEvent::Resubscribed` marker, as events may have been missed in the meantime.
+//!
+//! Bursts of events, such as an interface flapping, can be merged with `ResponseStreamExt::coalesce_by_key`:
+//! the latest state of every item is emitted once per window, while `Trap` and `Done` markers pass through at once.
+//!
+//! Code that does not run on tokio can use the blocking facade found in the `sync` module, which mirrors this API.
+//!
+//! `ReconnectPolicy::connect` connects and authenticates, retrying with a backoff while the router is unreachable but
+//! giving up at once when the credentials are rejected, so that unattended services do not lock accounts out.
+//! With `connect_with_secrets`, credentials come from a `SecretsSource`: in memory, environment variables or any secret store.
+//! Retries are jittered, and a `LoginGate` shared by many policies lets one login at a time through per router, so that
+//! instances starting together do not exhaust its session limit, reported as `ConnectFailure::ConnectionLimit`.
+//! A spawned `session_watchdog` notices the API session being kicked on the router and closes the connection at once,
+//! calls failing with `Error::ConnectionLost` so that services reconnect without waiting for a TCP timeout.
+//!
+//! `MikrotikAPI::from_transport` runs the API over any `AsyncRead + AsyncWrite` byte stream (see `Transport`) instead of
+//! a TCP connection: tunnels, serial bridges, or a `tokio::io::duplex` pipe to a fake router in tests.
+//! Where the library should not spawn its reader task, `MikrotikAPI::new_manual` hands it back as a `Connection`
+//! future, to be polled by the caller on the executor of its choice.
+//!
+//! Types implementing `RosEntity` (such as the bundled `IpAddress`, `Route`, `DhcpLease` and `DhcpClient`) know the path of the table they are mapped onto,
+//! and can be handled with `list::<T>()`, `add::<T>(&item)` and `remove::<T>(id)`. `entity_registry` lists the bundled ones
+//! along with the commands they support, ie for a GUI to build its menus from.
+//!
+//! Typed modules are gated behind cargo features, all enabled by default: `clock`, `dhcp`, `email`, `ethernet` (ethernet
+//! and bonding interfaces), `ipsec`, `mac` (`locate_device`, which enables `wireless`), `torch` and `wireless`. Minimal deployments can disable default features and pick the ones they use, `full` enabling
+//! every module and optional facility. `use mikrotik_api::prelude::*` imports the commonly used types and traits,
+//! along with the typed models of the enabled features.
+//!
+//! With the `metrics` feature enabled, `stats_report` provides histograms of reply sizes by command path, to help tune proplists.
+//! It also samples how far the consumers of streams lag behind: items waiting and age of the oldest. `StreamHandle::lag`
+//! gives the same for a single subscription, and `SlowReplyWarnings::lag` reports consumers lagging beyond a threshold.
+//!
+//! With the `watchdog` feature enabled, a `Watchdog` runs health checks (pings, free memory, interface state) and remediation
+//! commands such as `/system/reboot` when they keep failing, with cooldowns and a dry-run mode.
+//!
+//! Replies can also be deserialized into self-describing types such as `serde_json::Value`, every property being a string.
+//! `generic_array_call_borrowed` returns the replies as received, for structs with `&str` fields borrowing from them.
+//!
+//! `clock_drift` compares the router's clock with the host's, and `clock_drift_alarms` checks it periodically,
+//! only reporting drifts above a threshold.
+//!
+//! IPsec tunnels can be monitored through `ipsec_active_peers`, `ipsec_policies` and `ipsec_installed_sas`, or
+//! `ipsec_peer_changes` which polls the peers and reports them going up, down or changing state.
+//!
+//! With the `dhcp` feature, `dhcp_clients` and `dhcp_client` report the leases obtained by the router on its uplinks (status,
+//! address, gateway, time left) and `renew_dhcp_client` bounces one of them, for WAN failover scripts.
+//!
+//! `routerboard` and `license` return the hardware and license details of the router, `firmware_upgrade_pending` telling
+//! whether its RouterBOOT lags behind the installed RouterOS.
+//!
+//! `inventory` gathers the identity, model, serial number, RouterOS version, packages and license level of a router,
+//! and `fleet_inventory` does so for many routers at once, producing a single serializable report.
+//!
+//! `wireless_scan` streams the access points heard by a wireless interface during a site survey, through either the
+//! `wireless` or the `wifi` package, the scan being stopped once its duration is over.
+//!
+//! `locate_device` answers "where is this MAC address?": given a `MacQuery`, it looks the address up in the ARP table,
+//! DHCP leases, bridge hosts and wireless registrations at once and merges them into a `DeviceLocation` (IP address,
+//! bridge port, SSID, lease host name).
+//!
+//! `drift` compares the router with a declarative `DriftSpec` (typically loaded from a JSON or TOML file) and reports
+//! missing, extra and mismatched items, without changing anything.
+//!
+//! `diff_export` compares the same menus of two routers, such as both members of an HA pair, and reports per menu the items
+//! only found on one side and the properties that differ. Either side can be a `ConfigSnapshot` saved earlier with
+//! `config_snapshot`, ie the reference router of a template.
+//!
+//! A `SnapshotFile` stores topology, inventory and configuration dumps of a router in a versioned JSON lines file,
+//! for audits to be kept and re-analyzed without a live connection.
+//!
+//! `ConnectOptions::with_memory_budget` bounds the bytes of replies a connection holds for its calls, array calls in progress
+//! and streams not consumed yet alike. Past the budget, reads from the socket pause until streams are drained, or with
+//! `MemoryBudget::failing_greediest` the call holding the most fails with `Error::MemoryBudgetExceeded`. Streams nobody
+//! consumes anymore, dropped without being cancelled, are failed either way.
+//!
+//! `CallOptions::with_cancellation` ties a call to a `CancellationToken`, such as the one of the web request it serves:
+//! once the token fires, `/cancel` is sent and the call fails with `Error::Cancelled`, while streams simply end.
+//!
+//! Streams hold at most 1024 items for their consumer by default, dropping the oldest replies past it, see
+//! `CallOptions::with_stream_buffer`. `generic_sequenced_streaming_call` numbers every item, dropped replies leaving gaps.
+//!
+//! `CallOptions::with_progress` reports how many replies a call received so far as they come in, so that command line
+//! tools can render a progress bar while a busy router prints `/ip/firewall/connection`.
+//!
+//! A `Policy` set with `set_policy` is checked before any command hits the wire, e.g. `Policy::read_only()` turns every
+//! `add`, `set` or `remove` into an `Error::Denied`. On the router's side, `verify_policies` tells which of the policies
+//! a workflow needs (`read`, `write`, ...) the group of the user lacks, so that provisioning tools can fail before any change.
+//!
+//! `SentenceBuilder` encodes command sentences (command, arguments, queries, API attributes) into a reusable buffer,
+//! for code speaking the wire protocol directly. It is what the connection uses to send commands. For syntax it does not
+//! model, `Attr::Raw` injects a `RawWord` encoded by the caller, only its length framing being checked.
+//!
+//! `CommandTemplate` sits between raw strings and typed models: `/ip/firewall/address-list/add =list={list} =address={ip}`
+//! is parsed once, then rendered with named values, missing or unknown ones being rejected. `run_template_batch` renders it
+//! for a whole list of parameter sets, ie the lines of a CSV file, and sends nothing unless every one of them renders.
+//!
+//! Words of replies that are not valid UTF-8, such as identities typed in a Windows code page, are decoded as Latin-1
+//! rather than failing the connection, `latin1_bytes` giving the original bytes back. `unescape` decodes the escapes
+//! found in exports and scripts (`\"`, `\D0\9F`, ...) for display.
+//!
+//! A generic call given a `.proplist` that leaves out fields its reply type requires fails at once with
+//! `Error::ProplistMismatch`, naming those fields, instead of sending the command and failing on every reply.
+//!
+//! `send_email` has the router send an e-mail through the SMTP server of `/tool/e-mail` (see `email_server` and
+//! `set_email_server`), so that alerts go out over the session used for monitoring. Delivery failures are `Error::Email`.
+//!
+//! With the `test-util` feature, `mock::MockRouter` is a scripted router for the integration tests of applications:
+//! replies per command path and query, traps, delays and partial writes, over an in-memory pipe or a local TCP port.
+//!
+//! Any of the generic calls accepts a `Lenient<T>` in place of `T`: unknown properties are skipped and missing fields are given a default value,
+//! the list of both being reported alongside the value. Handy when the same model is used against both v6 and v7 routers.
+//! Bundled models already accept both names of properties renamed between v6 and v7, such as `routing-mark` and `routing-table`
+//! for `Route`, or `nlevel` and `level` for `License`.
+//!
+//! The other way around, `CallOptions::with_strict` fails a call with `Error::UnknownFields` when a reply holds properties its
+//! type would silently skip, listing them: test suites can tell when a RouterOS upgrade adds or renames properties of their models.
 
 #![deny(missing_docs)]
 use std::io;
@@ -75,16 +206,101 @@ use std::io;
 use tokio::net::{TcpStream, ToSocketAddrs};
 
 mod api;
+#[cfg(any(test, feature = "bench-internals"))]
+#[doc(hidden)]
+pub mod bench_support;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+pub mod prelude;
+pub mod sync;
 
+pub use api::audit::AuditEvent;
+#[cfg(feature = "clock")]
+pub use api::clock::ClockDrift;
+pub use api::coalesce::{Coalesce, ResponseStreamExt};
+pub use api::de::Lenient;
+pub use api::drift::{
+    AttributeDrift, Drift, DriftKind, DriftReport, DriftSpec, SpecValue, TableSpec,
+};
+pub use api::entity::{entity_registry, EntityInfo, RosEntity};
+pub use api::error::Error;
+pub use api::escape::{latin1_bytes, unescape};
+pub use api::export::{
+    diff_export, ConfigSnapshot, ExportDiff, ExportSource, ItemDiff, PathDiff, PropertyDiff,
+    SnapshotItem,
+};
+pub use api::inventory::{
+    fleet_inventory, FleetInventory, InstalledPackage, Inventory, RouterInventory,
+};
+#[cfg(feature = "ipsec")]
+pub use api::ipsec::IpsecPeerChange;
+pub use api::keep_alive::{KeepAlive, KeepAliveOptions, KeptStream, SubscriptionEvent};
+#[cfg(feature = "mac")]
+pub use api::mac::{DeviceLocation, MacQuery};
+#[cfg(feature = "clock")]
+pub use api::model::SystemClock;
+#[cfg(feature = "torch")]
+pub use api::model::Torch;
 pub use api::model::{
-    ActiveUser, Interface, InterfaceChange, InterfaceMTU, Response, SystemResources,
+    ActiveUser, ArpEntry, BridgeHost, BridgePort, DdnsMode, Interface, InterfaceChange,
+    InterfaceMTU, IpAddress, IpCloud, IpCloudSettings, IpService, IpServiceSettings, License,
+    MacAddress, Neighbor, ParseValueError, Response, RosId, Route, Routerboard, StreamItem,
+    SystemResources, TrapCategory, Vlan,
 };
+#[cfg(feature = "ethernet")]
+pub use api::model::{
+    Bonding, BondingMonitor, BondingSlave, BondingStatus, CablePair, CableTest, EthernetMonitor,
+};
+#[cfg(feature = "dhcp")]
+pub use api::model::{DhcpClient, DhcpLease};
+#[cfg(feature = "email")]
+pub use api::model::{EmailServer, EmailSettings};
+#[cfg(feature = "ipsec")]
+pub use api::model::{
+    IpsecActivePeer, IpsecInstalledSa, IpsecPeerState, IpsecPh2State, IpsecPolicy, IpsecSaState,
+};
+#[cfg(feature = "wireless")]
+pub use api::model::{WifiInterface, WirelessRegistration, WirelessScan};
+pub use api::monitor::{SlowReply, SlowReplyCallback, SlowReplyWarnings};
+pub use api::neighbor::{connect_by_mac, NeighborSource};
+pub use api::options::{
+    ActiveUserFilter, BudgetOverflow, CallOptions, ConnectOptions, LoginMethod, MemoryBudget,
+    PreFilter, Priority, Progress, StreamBuffer, StreamOverflow,
+};
+pub use api::policy::Policy;
+pub use api::privileges::PolicyCheck;
+pub use api::raw::RawReplies;
+pub use api::reconnect::{ConnectFailure, LoginGate, ReconnectPolicy};
+pub use api::secrets::{Credentials, EnvSecrets, SecretsSource, StaticSecrets};
+pub use api::self_test::{SelfTestCheck, SelfTestReport};
+pub use api::sentence::{Attr, RawWord, SentenceBuilder};
+pub use api::session::SessionWatchdog;
+pub use api::snapshot::{SnapshotFile, SnapshotHeader, SnapshotRecord, SNAPSHOT_VERSION};
+#[cfg(feature = "metrics")]
+pub use api::stats::{CommandStats, ExpHistogram, StatsReport};
+pub use api::subscription::{StreamFailure, StreamHandle, StreamLag};
+pub use api::template::{CommandTemplate, RenderedCommand};
+pub use api::topology::{EdgeKind, NodeKind, Topology, TopologyEdge, TopologyNode};
+#[cfg(feature = "torch")]
+pub use api::torch::{top_talkers, TopTalker};
+pub use api::transport::{Connection, Transport};
+#[cfg(feature = "watchdog")]
+pub use api::watchdog::{HealthCheck, Remediation, Watchdog, WatchdogAction, WatchdogTick};
 pub use api::{Authenticated, Disconnected, MikrotikAPI};
+pub use tokio_util::sync::CancellationToken;
 
 /// Given an address, opens a connection to the remote API service
 /// the returned object is in a Disconnected state
 pub async fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<MikrotikAPI<Disconnected>> {
+    connect_with_options(addr, ConnectOptions::default()).await
+}
+
+/// Same as `connect`, with specific connection options
+pub async fn connect_with_options<A: ToSocketAddrs>(
+    addr: A,
+    options: ConnectOptions,
+) -> io::Result<MikrotikAPI<Disconnected>> {
     let socket = TcpStream::connect(addr).await?;
 
-    Ok(MikrotikAPI::new(socket))
+    Ok(MikrotikAPI::new(socket, options))
 }
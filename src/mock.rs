@@ -0,0 +1,716 @@
+//! A scripted RouterOS API server, for integration tests of code built on the library: retries, timeouts,
+//! handling of `!trap`s, ... Requires the `test-util` feature.
+//!
+//! Replies are registered per command path, and optionally per query words. Logins always succeed unless `/login` is
+//! given a script, commands without one get a `!trap`, and `/cancel` interrupts the reply it targets the way a router does.
+//!
+//! ```rust
+//! use std::{collections::HashMap, time::Duration};
+//!
+//! use mikrotik_api::{
+//!     mock::{MockReply, MockRouter},
+//!     CallOptions, Error,
+//! };
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let router = MockRouter::new()
+//!     .on("/system/identity/print", MockReply::new().re(&[("name", "core-1")]))
+//!     .on(
+//!         "/interface/print",
+//!         MockReply::new().delay(Duration::from_secs(5)).re(&[("name", "ether1")]),
+//!     );
+//!
+//! let received = router.received();
+//! let mut api = router.connect().await.unwrap();
+//!
+//! let identity = api
+//!     .generic_oneshot_call::<HashMap<String, String>>("/system/identity/print", None)
+//!     .await
+//!     .unwrap();
+//!
+//! assert_eq!("core-1", identity["name"]);
+//!
+//! let options = CallOptions::default().with_timeout(Duration::from_millis(50));
+//!
+//! let slow = api
+//!     .generic_array_call_with_options::<HashMap<String, String>>("/interface/print", None, &options)
+//!     .await;
+//!
+//! assert!(matches!(slow, Err(Error::DeadlineExceeded)));
+//!
+//! // The late call was cancelled on the router
+//! while !received.contains("/cancel") {
+//!     tokio::task::yield_now().await;
+//! }
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+    net::SocketAddr,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, WriteHalf},
+    net::TcpListener,
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    api::{encode_sentence, error::Error, read::read_sentence},
+    Authenticated, MikrotikAPI,
+};
+
+#[derive(Debug, Clone)]
+enum Step {
+    Sentence(Vec<String>),
+    Untagged(Vec<String>),
+    Echo,
+    Delay(Duration),
+    Fatal(String),
+}
+
+/// Script of the reply to a command: sentences, pauses, and how they are written.
+/// Ends with a `!done`, unless `endless`
+#[derive(Debug, Clone, Default)]
+pub struct MockReply {
+    steps: Vec<Step>,
+    ret: Option<String>,
+    endless: bool,
+    split: Option<usize>,
+    ending: Vec<String>,
+}
+
+impl MockReply {
+    /// Reply made of a `!done` alone
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a `!re` sentence holding `properties`, ie `&[("name", "ether1"), ("running", "true")]`
+    pub fn re(mut self, properties: &[(&str, &str)]) -> Self {
+        let mut words = vec!["!re".to_owned()];
+        words.extend(
+            properties
+                .iter()
+                .map(|(key, value)| format!("={}={}", key, value)),
+        );
+
+        self.steps.push(Step::Sentence(words));
+        self
+    }
+
+    /// Adds a `!trap` with `message`
+    pub fn trap(mut self, message: &str) -> Self {
+        let words = vec!["!trap".to_owned(), format!("=message={}", message)];
+
+        self.steps.push(Step::Sentence(words));
+        self
+    }
+
+    /// Adds a `!trap` of `category`, ie 2 for an interrupted command
+    pub fn trap_with_category(mut self, category: u8, message: &str) -> Self {
+        let words = vec![
+            "!trap".to_owned(),
+            format!("=category={}", category),
+            format!("=message={}", message),
+        ];
+
+        self.steps.push(Step::Sentence(words));
+        self
+    }
+
+    /// Adds any sentence, ie `&["!re", "=.section=0", "=tx=8000"]`. The `.tag` is added to it
+    pub fn sentence(mut self, words: &[&str]) -> Self {
+        let words = words.iter().map(|word| word.to_string()).collect();
+
+        self.steps.push(Step::Sentence(words));
+        self
+    }
+
+    /// Adds a sentence written as is, without the `.tag` of the command, as a confused router would
+    pub fn untagged(mut self, words: &[&str]) -> Self {
+        let words = words.iter().map(|word| word.to_string()).collect();
+
+        self.steps.push(Step::Untagged(words));
+        self
+    }
+
+    /// Adds a `!re` sentence holding the `=key=value` arguments of the command, as received
+    pub fn echo(mut self) -> Self {
+        self.steps.push(Step::Echo);
+        self
+    }
+
+    /// Before anything else, ends the replies still running for `command` with a `!done`,
+    /// as a configuration reload ends `listen` commands
+    pub fn ending(mut self, command: &str) -> Self {
+        self.ending.push(command.to_owned());
+        self
+    }
+
+    /// Waits for `duration` before writing what follows
+    pub fn delay(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Delay(duration));
+        self
+    }
+
+    /// Sends `!fatal` with `message` and closes the connection, nothing being written afterwards
+    pub fn fatal(mut self, message: &str) -> Self {
+        self.steps.push(Step::Fatal(message.to_owned()));
+        self
+    }
+
+    /// Ends with `!done` holding `=ret=value`, as `add` commands do
+    pub fn ret(mut self, value: &str) -> Self {
+        self.ret = Some(value.to_owned());
+        self
+    }
+
+    /// Never sends the `!done`, the command running until cancelled, as `listen` commands do
+    pub fn endless(mut self) -> Self {
+        self.endless = true;
+        self
+    }
+
+    /// Writes every sentence in pieces of `bytes`, for the client to receive partial sentences
+    pub fn split_writes(mut self, bytes: usize) -> Self {
+        self.split = Some(bytes.max(1));
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Script {
+    command: String,
+    words: Vec<String>,
+    reply: MockReply,
+}
+
+/// Sentences received by a `MockRouter`, over all of its connections, see `MockRouter::received`
+#[derive(Debug, Clone, Default)]
+pub struct Received(Arc<Mutex<Vec<Vec<String>>>>);
+
+impl Received {
+    /// Every sentence received so far, in order, without the empty word closing them
+    pub fn sentences(&self) -> Vec<Vec<String>> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Whether `command`, ie `/cancel`, was received
+    pub fn contains(&self, command: &str) -> bool {
+        self.sentences()
+            .iter()
+            .any(|sentence| sentence[0] == command)
+    }
+
+    fn push(&self, sentence: Vec<String>) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sentence);
+    }
+}
+
+/// A scripted router, see the module documentation
+#[derive(Debug, Clone, Default)]
+pub struct MockRouter {
+    scripts: Vec<Script>,
+    received: Received,
+}
+
+impl MockRouter {
+    /// Router answering nothing but logins
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers `command`, ie `/interface/print`, with `reply`
+    pub fn on(self, command: &str, reply: MockReply) -> Self {
+        self.on_query(command, &[], reply)
+    }
+
+    /// Answers `command` with `reply` when it holds all of `words`, as sent on the wire: `?name=ether1`, `=numbers=*1`, ...
+    /// The script matching the most words wins
+    pub fn on_query(mut self, command: &str, words: &[&str], reply: MockReply) -> Self {
+        self.scripts.push(Script {
+            command: command.to_owned(),
+            words: words.iter().map(|word| word.to_string()).collect(),
+            reply,
+        });
+        self
+    }
+
+    /// Logs in as `admin` to the router, over an in-memory pipe
+    pub async fn connect(self) -> Result<MikrotikAPI<Authenticated>, Error> {
+        MikrotikAPI::from_transport(self.spawn())
+            .authenticate("admin", "")
+            .await
+    }
+
+    /// Log of the sentences the router receives, to check what the client sent
+    pub fn received(&self) -> Received {
+        self.received.clone()
+    }
+
+    /// Serves a single connection over an in-memory pipe, returning the end to give to `MikrotikAPI::from_transport`
+    pub fn spawn(self) -> DuplexStream {
+        let (client, router) = tokio::io::duplex(64 * 1024);
+
+        tokio::spawn(serve(Arc::new(self.scripts), self.received, router));
+
+        client
+    }
+
+    /// Serves every connection made to a local TCP port, returning its address for `connect`
+    pub async fn listen(self) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let address = listener.local_addr()?;
+
+        let scripts = Arc::new(self.scripts);
+        let received = self.received;
+
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(serve(scripts.clone(), received.clone(), socket));
+            }
+        });
+
+        Ok(address)
+    }
+}
+
+/// Reply of `scripts` to `sentence`
+fn find_reply<'a>(scripts: &'a [Script], sentence: &[String]) -> Option<&'a MockReply> {
+    scripts
+        .iter()
+        .filter(|script| script.command == sentence[0])
+        .filter(|script| script.words.iter().all(|word| sentence.contains(word)))
+        .max_by_key(|script| script.words.len())
+        .map(|script| &script.reply)
+}
+
+type SharedWriter<S> = Arc<tokio::sync::Mutex<WriteHalf<S>>>;
+
+async fn write_sentence<S: AsyncWrite>(
+    writer: &SharedWriter<S>,
+    words: &[String],
+    split: Option<usize>,
+) -> io::Result<()> {
+    let bytes = encode_sentence(words)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    let mut writer = writer.lock().await;
+
+    match split {
+        Some(size) => {
+            for piece in bytes.chunks(size) {
+                writer.write_all(piece).await?;
+                writer.flush().await?;
+
+                tokio::task::yield_now().await;
+            }
+        }
+
+        None => writer.write_all(&bytes).await?,
+    }
+
+    writer.flush().await
+}
+
+/// Writes `reply`, stopping between two sentences once `cancelled` fires
+async fn play<S: AsyncWrite>(
+    writer: SharedWriter<S>,
+    reply: MockReply,
+    tag: Option<String>,
+    cancelled: CancellationToken,
+) -> io::Result<()> {
+    let tagged = |mut words: Vec<String>| {
+        if let Some(tag) = &tag {
+            words.insert(1, tag.clone());
+        }
+        words
+    };
+
+    for step in reply.steps {
+        if cancelled.is_cancelled() {
+            return Ok(());
+        }
+
+        match step {
+            Step::Sentence(words) => write_sentence(&writer, &tagged(words), reply.split).await?,
+
+            Step::Untagged(words) => write_sentence(&writer, &words, reply.split).await?,
+
+            // Resolved by `serve`, see `MockReply::resolve`
+            Step::Echo => (),
+
+            Step::Delay(duration) => {
+                tokio::select! {
+                    _ = tokio::time::sleep(duration) => {}
+                    _ = cancelled.cancelled() => return Ok(()),
+                }
+            }
+
+            Step::Fatal(message) => {
+                write_sentence(&writer, &["!fatal".to_owned(), message], reply.split).await?;
+
+                return writer.lock().await.shutdown().await;
+            }
+        }
+    }
+
+    if reply.endless {
+        cancelled.cancelled().await;
+    }
+
+    if cancelled.is_cancelled() {
+        return Ok(());
+    }
+
+    let mut done = vec!["!done".to_owned()];
+    done.extend(reply.ret.map(|value| format!("=ret={}", value)));
+
+    write_sentence(&writer, &tagged(done), reply.split).await
+}
+
+impl MockReply {
+    /// The reply to `sentence`, its `echo` steps turned into sentences
+    fn resolve(mut self, sentence: &[String]) -> Self {
+        for step in self.steps.iter_mut() {
+            if let Step::Echo = step {
+                let arguments = sentence.iter().filter(|word| word.starts_with('='));
+
+                *step = Step::Sentence(
+                    ["!re".to_owned()]
+                        .into_iter()
+                        .chain(arguments.cloned())
+                        .collect(),
+                );
+            }
+        }
+
+        self
+    }
+}
+
+/// Reply being written for a command, by tag
+struct Running {
+    command: String,
+    token: CancellationToken,
+    task: JoinHandle<io::Result<()>>,
+}
+
+impl Running {
+    /// Stops writing the reply, returning whether it was still running
+    async fn stop(self) -> bool {
+        if self.task.is_finished() {
+            return false;
+        }
+
+        self.token.cancel();
+        let _ = self.task.await;
+
+        true
+    }
+}
+
+/// Reply of the router to `/cancel`, interrupting the reply to `sentence`'s `=tag=` if still running
+async fn cancel<S: AsyncWrite>(
+    writer: &SharedWriter<S>,
+    running: &mut HashMap<String, Running>,
+    sentence: &[String],
+) -> MockReply {
+    let target = sentence
+        .iter()
+        .find_map(|word| word.strip_prefix("=tag="))
+        .map(|tag| format!(".tag={}", tag));
+
+    let Some((target, reply)) = target.and_then(|tag| running.remove_entry(&tag)) else {
+        return MockReply::new();
+    };
+
+    if reply.stop().await {
+        let interrupted = MockReply::new().trap_with_category(2, "interrupted");
+        let _ = play(
+            writer.clone(),
+            interrupted,
+            Some(target),
+            CancellationToken::new(),
+        )
+        .await;
+    }
+
+    MockReply::new()
+}
+
+/// Ends the replies running for `command` with a `!done`
+async fn end<S: AsyncWrite>(
+    writer: &SharedWriter<S>,
+    running: &mut HashMap<String, Running>,
+    command: &str,
+) {
+    let tags: Vec<String> = running
+        .iter()
+        .filter(|(_, reply)| reply.command == command)
+        .map(|(tag, _)| tag.clone())
+        .collect();
+
+    for tag in tags {
+        let Some(reply) = running.remove(&tag) else {
+            continue;
+        };
+
+        if reply.stop().await {
+            let _ = play(
+                writer.clone(),
+                MockReply::new(),
+                Some(tag),
+                CancellationToken::new(),
+            )
+            .await;
+        }
+    }
+}
+
+async fn serve<S>(scripts: Arc<Vec<Script>>, received: Received, socket: S)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut reader, writer) = tokio::io::split(socket);
+    let writer = Arc::new(tokio::sync::Mutex::new(writer));
+
+    let mut running: HashMap<String, Running> = HashMap::new();
+
+    let mut buffer = vec![];
+    let mut chunk = [0u8; 4096];
+
+    while let Ok(read @ 1..) = reader.read(&mut chunk).await {
+        buffer.extend_from_slice(&chunk[..read]);
+
+        loop {
+            let mut cursor = Cursor::new(&buffer[..]);
+
+            let Ok(sentence) = read_sentence(&mut cursor) else {
+                break;
+            };
+
+            // Without the empty word closing it
+            let sentence: Vec<String> = sentence
+                .iter()
+                .filter(|word| !word.is_empty())
+                .map(|word| word.to_string())
+                .collect();
+
+            let consumed = cursor.position() as usize;
+            buffer.drain(..consumed);
+
+            if sentence.is_empty() {
+                continue;
+            }
+
+            received.push(sentence.clone());
+
+            let tag = sentence
+                .iter()
+                .find(|word| word.starts_with(".tag="))
+                .cloned();
+
+            let reply = match (sentence[0].as_str(), find_reply(&scripts, &sentence)) {
+                (_, Some(reply)) => reply.clone().resolve(&sentence),
+                ("/login", None) => MockReply::new(),
+                ("/cancel", None) => cancel(&writer, &mut running, &sentence).await,
+                (_, None) => MockReply::new().trap("no such command"),
+            };
+
+            for command in &reply.ending {
+                end(&writer, &mut running, command).await;
+            }
+
+            running.retain(|_, reply| !reply.task.is_finished());
+
+            let token = CancellationToken::new();
+            let task = tokio::spawn(play(writer.clone(), reply, tag.clone(), token.clone()));
+
+            if let Some(tag) = tag {
+                let command = sentence[0].clone();
+
+                running.insert(
+                    tag,
+                    Running {
+                        command,
+                        token,
+                        task,
+                    },
+                );
+            }
+        }
+    }
+
+    for reply in running.values() {
+        reply.token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use futures::StreamExt;
+
+    use crate::api::{error::Error, model::Response, options::CallOptions};
+
+    use super::{MockReply, MockRouter};
+
+    type Item = HashMap<String, String>;
+
+    fn names(items: Vec<Item>) -> Vec<String> {
+        items
+            .into_iter()
+            .map(|mut item| item.remove("name").unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_query_replies() {
+        let router = MockRouter::new()
+            .on(
+                "/interface/print",
+                MockReply::new()
+                    .re(&[("name", "ether1")])
+                    .re(&[("name", "wlan1")]),
+            )
+            .on_query(
+                "/interface/print",
+                &["?type=ether"],
+                MockReply::new().re(&[("name", "ether1")]),
+            )
+            .on_query(
+                "/interface/print",
+                &["?type=ether", "?running=true"],
+                MockReply::new().trap("failure: no running interface"),
+            );
+
+        let mut api = router.connect().await.unwrap();
+
+        let all = api
+            .generic_array_call::<Item>("/interface/print", None)
+            .await
+            .unwrap();
+        assert_eq!(vec!["ether1", "wlan1"], names(all));
+
+        let ether = api
+            .generic_array_call::<Item>("/interface/print", Some(&[("?type", "ether")]))
+            .await
+            .unwrap();
+        assert_eq!(vec!["ether1"], names(ether));
+
+        let running = api
+            .generic_array_call::<Item>(
+                "/interface/print",
+                Some(&[("?running", "true"), ("?type", "ether")]),
+            )
+            .await;
+        assert!(matches!(running, Err(Error::Remote(msg)) if msg.contains("no running interface")));
+
+        let unknown = api.generic_array_call::<Item>("/nothing/print", None).await;
+        assert!(matches!(unknown, Err(Error::Remote(_))));
+    }
+
+    #[tokio::test]
+    async fn test_delayed_reply() {
+        let router = MockRouter::new()
+            .on(
+                "/interface/print",
+                MockReply::new()
+                    .delay(Duration::from_secs(5))
+                    .re(&[("name", "ether1")]),
+            )
+            .on(
+                "/system/identity/print",
+                MockReply::new().re(&[("name", "core-1")]),
+            );
+
+        let mut api = router.connect().await.unwrap();
+
+        let options = CallOptions::default().with_timeout(Duration::from_millis(50));
+
+        let slow = api
+            .generic_array_call_with_options::<Item>("/interface/print", None, &options)
+            .await;
+        assert!(matches!(slow, Err(Error::DeadlineExceeded)));
+
+        // The connection is still usable, as a retry would expect
+        let identity = api
+            .generic_array_call::<Item>("/system/identity/print", None)
+            .await
+            .unwrap();
+        assert_eq!(vec!["core-1"], names(identity));
+    }
+
+    #[tokio::test]
+    async fn test_split_writes() {
+        let reply = (0..10)
+            .fold(MockReply::new(), |reply, i| {
+                reply.re(&[("name", &i.to_string())])
+            })
+            .split_writes(3);
+
+        let mut api = MockRouter::new()
+            .on("/ip/route/print", reply)
+            .connect()
+            .await
+            .unwrap();
+
+        let routes = api
+            .generic_array_call::<Item>("/ip/route/print", None)
+            .await
+            .unwrap();
+
+        let expected: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        assert_eq!(expected, names(routes));
+    }
+
+    #[tokio::test]
+    async fn test_endless_reply() {
+        let reply = MockReply::new().re(&[("name", "ether1")]).endless();
+
+        let mut api = MockRouter::new()
+            .on("/interface/listen", reply)
+            .connect()
+            .await
+            .unwrap();
+
+        let mut tag = 0;
+        let mut stream = Box::pin(
+            api.generic_streaming_call::<Item>("/interface/listen", None, &mut tag)
+                .await
+                .unwrap(),
+        );
+
+        assert!(matches!(stream.next().await, Some(Response::Reply(_))));
+
+        assert!(matches!(api.cancel(tag).await, Response::Done));
+
+        while let Some(item) = stream.next().await {
+            assert!(!matches!(item, Response::Reply(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fatal() {
+        let router = MockRouter::new().on("/system/reboot", MockReply::new().fatal("rebooting"));
+
+        let mut api = router.connect().await.unwrap();
+
+        let reboot = api.generic_array_call::<Item>("/system/reboot", None).await;
+        assert!(matches!(reboot, Err(Error::ConnectionLost(_))));
+    }
+}
@@ -0,0 +1,25 @@
+//! Types and traits needed by most programs using the library, to be glob-imported:
+//!
+//! ```
+//! use mikrotik_api::prelude::*;
+//! ```
+//!
+//! Typed models are only re-exported when their feature is enabled, so that the glob import compiles
+//! with any combination of features.
+
+pub use crate::{
+    connect, Authenticated, CallOptions, Disconnected, Error, Interface, MikrotikAPI,
+    ReconnectPolicy, Response, ResponseStreamExt, RosEntity, RosId, SystemResources,
+};
+
+#[cfg(feature = "clock")]
+pub use crate::{ClockDrift, SystemClock};
+
+#[cfg(feature = "dhcp")]
+pub use crate::{DhcpClient, DhcpLease};
+
+#[cfg(feature = "ethernet")]
+pub use crate::{BondingStatus, CableTest, EthernetMonitor};
+
+#[cfg(feature = "ipsec")]
+pub use crate::{IpsecActivePeer, IpsecPeerChange, IpsecPolicy};
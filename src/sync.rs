@@ -0,0 +1,570 @@
+//! Blocking facade over the asynchronous API, for codebases that do not use tokio.
+//!
+//! Each `MikrotikAPI` owns a current-thread runtime, that is driven for the duration of every call.
+//! ```rust,ignore
+//! let api = mikrotik_api::sync::connect("192.168.88.1:8728")?;
+//!
+//! let mut api = api.authenticate("admin", "password")?;
+//!
+//! println!("{:?}", api.system_resources()?);
+//! ```
+
+use std::{
+    collections::HashMap, fmt::Debug, io, net::ToSocketAddrs, pin::Pin, sync::Arc, time::Duration,
+};
+
+use futures::{Stream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    api::{self, error::Error, State},
+    ActiveUser, ActiveUserFilter, Authenticated, CallOptions, CommandTemplate, ConfigSnapshot,
+    ConnectOptions, Disconnected, DriftReport, DriftSpec, Interface, InterfaceChange, InterfaceMTU,
+    Inventory, IpCloud, IpCloudSettings, IpService, IpServiceSettings, License, Policy,
+    PolicyCheck, RawReplies, Response, RosEntity, Routerboard, SlowReplyWarnings, SystemResources,
+    Topology,
+};
+
+#[cfg(feature = "dhcp")]
+use crate::DhcpClient;
+#[cfg(feature = "torch")]
+use crate::Torch;
+#[cfg(feature = "wireless")]
+use crate::WirelessScan;
+#[cfg(feature = "ethernet")]
+use crate::{BondingStatus, CableTest, EthernetMonitor};
+#[cfg(feature = "clock")]
+use crate::{ClockDrift, SystemClock};
+#[cfg(feature = "mac")]
+use crate::{DeviceLocation, MacQuery};
+#[cfg(feature = "email")]
+use crate::{EmailServer, EmailSettings};
+#[cfg(feature = "ipsec")]
+use crate::{IpsecActivePeer, IpsecInstalledSa, IpsecPolicy};
+
+/// Given an address, opens a connection to the remote API service
+/// the returned object is in a Disconnected state
+pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<MikrotikAPI<Disconnected>> {
+    connect_with_options(addr, ConnectOptions::default())
+}
+
+/// Same as `connect`, with specific connection options
+pub fn connect_with_options<A: ToSocketAddrs>(
+    addr: A,
+    options: ConnectOptions,
+) -> io::Result<MikrotikAPI<Disconnected>> {
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+
+    let addrs: Vec<_> = addr.to_socket_addrs()?.collect();
+
+    let inner = runtime.block_on(crate::connect_with_options(addrs.as_slice(), options))?;
+
+    Ok(MikrotikAPI {
+        runtime: Arc::new(runtime),
+        inner,
+    })
+}
+
+/// Blocking counterpart of `mikrotik_api::MikrotikAPI`
+pub struct MikrotikAPI<S: State> {
+    runtime: Arc<Runtime>,
+    inner: api::MikrotikAPI<S>,
+}
+
+impl<S: State> MikrotikAPI<S> {
+    /// Sentence count and size statistics of the replies received so far, by command path
+    #[cfg(feature = "metrics")]
+    pub fn stats_report(&self) -> crate::StatsReport {
+        self.inner.stats_report()
+    }
+
+    /// Sets the policy every outgoing command is checked against
+    pub fn set_policy(&mut self, policy: Policy) {
+        self.inner.set_policy(policy)
+    }
+
+    /// Enables warnings about calls waiting too long for a reply
+    /// or sentences taking too long to be deserialized
+    pub fn set_slow_reply_warnings(&mut self, warnings: SlowReplyWarnings) {
+        self.inner.set_slow_reply_warnings(warnings)
+    }
+}
+
+impl MikrotikAPI<Disconnected> {
+    /// Authenticate user with its login & password
+    pub fn authenticate(
+        self,
+        login: &str,
+        password: &str,
+    ) -> Result<MikrotikAPI<Authenticated>, Error> {
+        let inner = self
+            .runtime
+            .block_on(self.inner.authenticate(login, password))?;
+
+        Ok(MikrotikAPI {
+            runtime: self.runtime,
+            inner,
+        })
+    }
+}
+
+impl MikrotikAPI<Authenticated> {
+    /// Get details of the remote router such as architecture, processor, RAM, ...
+    pub fn system_resources(&mut self) -> Result<SystemResources, Error> {
+        self.runtime.block_on(self.inner.system_resources())
+    }
+
+    /// List interfaces and their state in great details
+    pub fn interfaces(&mut self) -> Result<Vec<Interface>, Error> {
+        self.runtime.block_on(self.inner.interfaces())
+    }
+
+    /// Sets the MTU of the interface `name_or_id`, checked against its L2 MTU
+    pub fn set_interface_mtu(&mut self, name_or_id: &str, mtu: InterfaceMTU) -> Result<(), Error> {
+        self.runtime
+            .block_on(self.inner.set_interface_mtu(name_or_id, mtu))
+    }
+
+    /// List the DHCP clients of the router along with their lease
+    #[cfg(feature = "dhcp")]
+    pub fn dhcp_clients(&mut self) -> Result<Vec<DhcpClient>, Error> {
+        self.runtime.block_on(self.inner.dhcp_clients())
+    }
+
+    /// Get the DHCP client running on `interface`, if any
+    #[cfg(feature = "dhcp")]
+    pub fn dhcp_client(&mut self, interface: &str) -> Result<Option<DhcpClient>, Error> {
+        self.runtime.block_on(self.inner.dhcp_client(interface))
+    }
+
+    /// Releases the lease of the DHCP client `id` and asks for a new one
+    #[cfg(feature = "dhcp")]
+    pub fn renew_dhcp_client(&mut self, id: &str) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.renew_dhcp_client(id))
+    }
+
+    /// Get the state of the bonding interface `name`
+    #[cfg(feature = "ethernet")]
+    pub fn bonding_status(&mut self, name: &str) -> Result<BondingStatus, Error> {
+        self.runtime.block_on(self.inner.bonding_status(name))
+    }
+
+    /// Runs a cable test on the ethernet interface `name`, until cancelled through `tag`
+    #[cfg(feature = "ethernet")]
+    pub fn cable_test(
+        &mut self,
+        name: &str,
+        tag: &mut u16,
+    ) -> Result<BlockingStream<CableTest>, Error> {
+        let stream = self.runtime.block_on(self.inner.cable_test(name, tag))?;
+
+        Ok(BlockingStream::new(self.runtime.clone(), stream))
+    }
+
+    /// Get the link state of the ethernet interface `name` and the diagnostics of its SFP module
+    #[cfg(feature = "ethernet")]
+    pub fn ethernet_monitor(&mut self, name: &str) -> Result<EthernetMonitor, Error> {
+        self.runtime.block_on(self.inner.ethernet_monitor(name))
+    }
+
+    /// Gathers interfaces, bridge ports, VLANs, neighbors and routes into a single graph
+    pub fn topology_snapshot(&mut self) -> Result<Topology, Error> {
+        self.runtime.block_on(self.inner.topology_snapshot())
+    }
+
+    /// Get the configuration of the management service `name`
+    pub fn ip_service(&mut self, name: &str) -> Result<Option<IpService>, Error> {
+        self.runtime.block_on(self.inner.ip_service(name))
+    }
+
+    /// Change the configuration of the management service `name`
+    pub fn set_ip_service(
+        &mut self,
+        name: &str,
+        settings: &IpServiceSettings,
+    ) -> Result<(), Error> {
+        self.runtime
+            .block_on(self.inner.set_ip_service(name, settings))
+    }
+
+    /// Get the state of MikroTik's cloud service: DDNS registration, public address, DNS name
+    pub fn ip_cloud(&mut self) -> Result<IpCloud, Error> {
+        self.runtime.block_on(self.inner.ip_cloud())
+    }
+
+    /// Change the settings of MikroTik's cloud service
+    pub fn set_ip_cloud(&mut self, settings: &IpCloudSettings) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.set_ip_cloud(settings))
+    }
+
+    /// Ask the cloud service to register the current public address right away
+    pub fn force_ddns_update(&mut self) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.force_ddns_update())
+    }
+
+    /// Get the SMTP server the router sends mail through
+    #[cfg(feature = "email")]
+    pub fn email_server(&mut self) -> Result<EmailServer, Error> {
+        self.runtime.block_on(self.inner.email_server())
+    }
+
+    /// Change the SMTP server the router sends mail through
+    #[cfg(feature = "email")]
+    pub fn set_email_server(&mut self, settings: &EmailSettings) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.set_email_server(settings))
+    }
+
+    /// Has the router send an e-mail through its SMTP server. Fails with `Error::Email` when it cannot deliver it
+    #[cfg(feature = "email")]
+    pub fn send_email(&mut self, to: &str, subject: &str, body: &str) -> Result<(), Error> {
+        self.runtime
+            .block_on(self.inner.send_email(to, subject, body))
+    }
+
+    /// Listen to user activity in terms of login/logout
+    pub fn active_users(&mut self, tag: &mut u16) -> Result<BlockingStream<ActiveUser>, Error> {
+        let stream = self.runtime.block_on(self.inner.active_users(tag))?;
+
+        Ok(BlockingStream::new(self.runtime.clone(), stream))
+    }
+
+    /// Same as `active_users`, only reporting the sessions selected by `filter`
+    pub fn active_users_with_filter(
+        &mut self,
+        tag: &mut u16,
+        filter: &ActiveUserFilter,
+    ) -> Result<BlockingStream<ActiveUser>, Error> {
+        let stream = self
+            .runtime
+            .block_on(self.inner.active_users_with_filter(tag, filter))?;
+
+        Ok(BlockingStream::new(self.runtime.clone(), stream))
+    }
+
+    /// Sessions currently open and selected by `filter`
+    pub fn current_active_users(
+        &mut self,
+        filter: &ActiveUserFilter,
+    ) -> Result<Vec<ActiveUser>, Error> {
+        self.runtime
+            .block_on(self.inner.current_active_users(filter))
+    }
+
+    /// Checks that the user of the connection is granted the `required` policies, ie `&["read", "write", "api"]`
+    pub fn verify_policies(&mut self, required: &[&str]) -> Result<PolicyCheck, Error> {
+        self.runtime.block_on(self.inner.verify_policies(required))
+    }
+
+    /// Finds the device of `query` in the ARP table, DHCP leases, bridge hosts and wireless registrations
+    #[cfg(feature = "mac")]
+    pub fn locate_device(&mut self, query: &MacQuery) -> Result<DeviceLocation, Error> {
+        self.runtime.block_on(self.inner.locate_device(query))
+    }
+
+    /// Listen to interface changes (up, down, ...)
+    pub fn interfaces_changes(
+        &mut self,
+        tag: &mut u16,
+    ) -> Result<BlockingStream<InterfaceChange>, Error> {
+        let stream = self.runtime.block_on(self.inner.interfaces_changes(tag))?;
+
+        Ok(BlockingStream::new(self.runtime.clone(), stream))
+    }
+
+    /// Runs `/tool/torch` on `interface`, to be sampled with `collect_for`
+    #[cfg(feature = "torch")]
+    pub fn torch(
+        &mut self,
+        interface: &str,
+        tag: &mut u16,
+    ) -> Result<BlockingStream<Torch>, Error> {
+        let stream = self.runtime.block_on(self.inner.torch(interface, tag))?;
+
+        Ok(BlockingStream::new(self.runtime.clone(), stream))
+    }
+
+    /// Scans for access points with `interface` during `duration`
+    #[cfg(feature = "wireless")]
+    pub fn wireless_scan(
+        &mut self,
+        interface: &str,
+        duration: Duration,
+        tag: &mut u16,
+    ) -> Result<BlockingStream<WirelessScan>, Error> {
+        let stream = self
+            .runtime
+            .block_on(self.inner.wireless_scan(interface, duration, tag))?;
+
+        Ok(BlockingStream::new(self.runtime.clone(), stream))
+    }
+
+    /// Compares the state of the router with `spec`, without changing anything
+    pub fn drift(&mut self, spec: &DriftSpec) -> Result<DriftReport, Error> {
+        self.runtime.block_on(self.inner.drift(spec))
+    }
+
+    /// Get the date, time and time zone of the router
+    #[cfg(feature = "clock")]
+    pub fn system_clock(&mut self) -> Result<SystemClock, Error> {
+        self.runtime.block_on(self.inner.system_clock())
+    }
+
+    /// Compares the clock of the router with the one of the host, compensating for the round trip of the call
+    #[cfg(feature = "clock")]
+    pub fn clock_drift(&mut self) -> Result<ClockDrift, Error> {
+        self.runtime.block_on(self.inner.clock_drift())
+    }
+
+    /// List the IKE peers the router is currently talking to
+    #[cfg(feature = "ipsec")]
+    pub fn ipsec_active_peers(&mut self) -> Result<Vec<IpsecActivePeer>, Error> {
+        self.runtime.block_on(self.inner.ipsec_active_peers())
+    }
+
+    /// List the IPsec policies along with their phase 2 state
+    #[cfg(feature = "ipsec")]
+    pub fn ipsec_policies(&mut self) -> Result<Vec<IpsecPolicy>, Error> {
+        self.runtime.block_on(self.inner.ipsec_policies())
+    }
+
+    /// List the SAs installed in the kernel
+    #[cfg(feature = "ipsec")]
+    pub fn ipsec_installed_sas(&mut self) -> Result<Vec<IpsecInstalledSa>, Error> {
+        self.runtime.block_on(self.inner.ipsec_installed_sas())
+    }
+
+    /// Get the model, serial number and RouterBOOT versions of the router
+    pub fn routerboard(&mut self) -> Result<Routerboard, Error> {
+        self.runtime.block_on(self.inner.routerboard())
+    }
+
+    /// Get the license level of the router
+    pub fn license(&mut self) -> Result<License, Error> {
+        self.runtime.block_on(self.inner.license())
+    }
+
+    /// Whether the RouterBOOT lags behind the one bundled with RouterOS
+    pub fn firmware_upgrade_pending(&mut self) -> Result<bool, Error> {
+        self.runtime.block_on(self.inner.firmware_upgrade_pending())
+    }
+
+    /// Gathers identity, board, serial number, RouterOS version, packages and license level
+    pub fn inventory(&mut self) -> Result<Inventory, Error> {
+        self.runtime.block_on(self.inner.inventory())
+    }
+
+    /// Takes a normalized snapshot of the menus `paths`, to be compared with `ConfigSnapshot::diff` or saved
+    pub fn config_snapshot(&mut self, paths: &[&str]) -> Result<ConfigSnapshot, Error> {
+        self.runtime.block_on(self.inner.config_snapshot(paths))
+    }
+
+    /// Lists all items of the table `T` is mapped onto
+    pub fn list<T: RosEntity>(&mut self) -> Result<Vec<T>, Error> {
+        self.runtime.block_on(self.inner.list())
+    }
+
+    /// Lists the items of the table `T` is mapped onto whose properties equal the given values
+    pub fn list_where<T: RosEntity>(&mut self, query: &[(&str, &str)]) -> Result<Vec<T>, Error> {
+        self.runtime.block_on(self.inner.list_where(query))
+    }
+
+    /// Fetches the properties `props` of the items `ids` of the menu `path` in a single `print`, by id
+    pub fn get_props(
+        &mut self,
+        path: &str,
+        ids: &[&str],
+        props: &[&str],
+    ) -> Result<HashMap<String, HashMap<String, String>>, Error> {
+        self.runtime
+            .block_on(self.inner.get_props(path, ids, props))
+    }
+
+    /// Adds `item` to the table `T` is mapped onto
+    pub fn add<T: RosEntity>(&mut self, item: &T) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.add(item))
+    }
+
+    /// Applies `changes` to the item identified by `id` in the table `T` is mapped onto
+    pub fn set<T: RosEntity, C: Serialize>(&mut self, id: &str, changes: &C) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.set::<T, C>(id, changes))
+    }
+
+    /// Removes the item identified by `id` from the table `T` is mapped onto
+    pub fn remove<T: RosEntity>(&mut self, id: &str) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.remove::<T>(id))
+    }
+
+    /// Allows to call generic commands returning a one-off response
+    pub fn generic_oneshot_call<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+    ) -> Result<T, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        self.runtime
+            .block_on(self.inner.generic_oneshot_call(command, attributes))
+    }
+
+    /// Allows to call generic commands returning a finite amount of items
+    pub fn generic_array_call<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        self.runtime
+            .block_on(self.inner.generic_array_call(command, attributes))
+    }
+
+    /// Renders `template` with `params` and calls it
+    pub fn run_template<T, K, V>(
+        &mut self,
+        template: &CommandTemplate,
+        params: &[(K, V)],
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.runtime
+            .block_on(self.inner.run_template(template, params))
+    }
+
+    /// Renders `template` for every set of `params`, then calls each rendered command in turn.
+    /// Nothing is sent unless every set renders
+    pub fn run_template_batch<T, I, P, K, V>(
+        &mut self,
+        template: &CommandTemplate,
+        sets: I,
+    ) -> Result<Vec<Result<Vec<T>, Error>>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+        I: IntoIterator<Item = P>,
+        P: AsRef<[(K, V)]>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.runtime
+            .block_on(self.inner.run_template_batch(template, sets))
+    }
+
+    /// Same as `generic_array_call`, deserialization being left to the caller so that the replies can be borrowed from
+    pub fn generic_array_call_borrowed(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+    ) -> Result<RawReplies, Error> {
+        self.runtime
+            .block_on(self.inner.generic_array_call_borrowed(command, attributes))
+    }
+
+    /// Same as `generic_oneshot_call`, with specific call options
+    pub fn generic_oneshot_call_with_options<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        options: &CallOptions,
+    ) -> Result<T, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        self.runtime.block_on(
+            self.inner
+                .generic_oneshot_call_with_options(command, attributes, options),
+        )
+    }
+
+    /// Same as `generic_array_call`, with specific call options
+    pub fn generic_array_call_with_options<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        options: &CallOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        self.runtime.block_on(
+            self.inner
+                .generic_array_call_with_options(command, attributes, options),
+        )
+    }
+
+    /// Allows to iterate over events of `listen` endpoints.
+    /// Takes a mutable `tag` argument that allows to stop (cancel) the stream afterwards
+    pub fn generic_streaming_call<T>(
+        &mut self,
+        command: &str,
+        attributes: Option<&[(&str, &str)]>,
+        tag: &mut u16,
+    ) -> Result<BlockingStream<T>, Error>
+    where
+        T: DeserializeOwned + Debug + Sync + Send + 'static,
+    {
+        let stream = self
+            .runtime
+            .block_on(self.inner.generic_streaming_call(command, attributes, tag))?;
+
+        Ok(BlockingStream::new(self.runtime.clone(), stream))
+    }
+
+    /// Calls `/cancel` on a specific tag.
+    /// Primary usage is to stop `listen` commands
+    pub fn cancel(&mut self, tag: u16) -> Response<()> {
+        self.runtime.block_on(self.inner.cancel(tag))
+    }
+
+    /// Collects the first `n` replies of `stream`, then cancels the command through its `tag`
+    pub fn collect_n<T>(
+        &mut self,
+        stream: BlockingStream<T>,
+        tag: u16,
+        n: usize,
+    ) -> Result<Vec<T>, Error> {
+        self.runtime
+            .block_on(self.inner.collect_n(stream.stream, tag, n))
+    }
+
+    /// Collects the replies of `stream` for `duration`, then cancels the command through its `tag`
+    pub fn collect_for<T>(
+        &mut self,
+        stream: BlockingStream<T>,
+        tag: u16,
+        duration: Duration,
+    ) -> Result<Vec<T>, Error> {
+        self.runtime
+            .block_on(self.inner.collect_for(stream.stream, tag, duration))
+    }
+}
+
+/// Iterator over the events of a `listen` command, blocking until the next one is received
+pub struct BlockingStream<T> {
+    runtime: Arc<Runtime>,
+    stream: Pin<Box<dyn Stream<Item = Response<T>> + Send>>,
+}
+
+impl<T> BlockingStream<T> {
+    fn new<S>(runtime: Arc<Runtime>, stream: S) -> Self
+    where
+        S: Stream<Item = Response<T>> + Send + 'static,
+    {
+        Self {
+            runtime,
+            stream: Box::pin(stream),
+        }
+    }
+}
+
+impl<T> Iterator for BlockingStream<T> {
+    type Item = Response<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}